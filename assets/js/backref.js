@@ -0,0 +1,10 @@
+// the ↩ of a source goes back to the citation of it that was clicked last
+function backref(id, citation) {
+    const entry = document.getElementById(id);
+    if (!entry) return;
+    for (const link of entry.getElementsByTagName("a")) {
+        if (link.textContent.trim() === "↩") {
+            link.href = "#" + citation;
+        }
+    }
+}
@@ -0,0 +1,22 @@
+// clicking an image shows it enlarged with its alt text, clicking next to it closes it again
+function makePopup(image) {
+    const popup = document.getElementById("popup");
+    if (!popup) return;
+    document.getElementById("popup-image").src = image.src;
+    document.getElementById("popup-caption").textContent = image.alt;
+    popup.classList.remove("popup-hidden");
+    popup.setAttribute("aria-hidden", "false");
+}
+
+function close_popup(region) {
+    const popup = region.closest("#popup");
+    popup.classList.add("popup-hidden");
+    popup.setAttribute("aria-hidden", "true");
+}
+
+document.addEventListener("keydown", (event) => {
+    const popup = document.getElementById("popup");
+    if (event.key === "Escape" && popup && !popup.classList.contains("popup-hidden")) {
+        close_popup(popup.firstElementChild);
+    }
+});
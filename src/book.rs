@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::anyhow;
+use crate::*;
+
+// a book is a manifest (a regular pmd file) whose frontmatter lists the chapter files in order,
+// every chapter is included into one combined document so references, contacts, notes and
+// the table of contents are shared across all of them.
+pub struct Book {
+    pub document: PawsMarkdown,
+    pub chapters: Vec<(/*name: */ String, PawsMarkdown)>,
+}
+
+fn get_chapters(data: &Frontmatter) -> Option<Vec<String>> {
+    let chapters = if let Some(chapters) = data["chapters"].as_sequence() {
+        chapters
+    } else if let Some(chapters) = data["chapter"].as_sequence() {
+        chapters
+    } else if let Some(chapters) = data["files"].as_sequence() {
+        chapters
+    } else {
+        return None;
+    };
+
+    Some(chapters.iter().filter_map(|chapter| chapter.as_string()).collect())
+}
+
+fn chapter_name(file: &String) -> String {
+    if let Some(stem) = Path::new(file).file_stem() {
+        stem.to_string_lossy().to_string()
+    } else {
+        file.clone()
+    }
+}
+
+fn collect_ids(body: &[(BlogBody, String)], page: &String, ids: &mut HashMap<String, String>) {
+    for (element, id) in body {
+        if is_valid_id(id) {
+            ids.insert(id.clone(), page.clone());
+        }
+        if let BlogBody::FactBox(factbox) = element {
+            collect_ids(&factbox.body, page, ids);
+            for (_, (_, id)) in &factbox.notes {
+                ids.insert(id.clone(), page.clone());
+            }
+        }
+    }
+}
+
+pub fn book_parse(manifest_path: &String, page_prefix: &str) -> Result<Book> {
+    let mut syntax = toplevel_parse(&fs::read_to_string(manifest_path)?)?;
+
+    let chapter_files = if let Some(TopLevelSyntax::FrontMatter(frontmatter)) = syntax.first()
+        && let Some(chapters) = get_chapters(frontmatter)
+    {
+        chapters
+    } else {
+        return Err(anyhow!("'{}' does not list any chapters, see 'pmd explain book'", manifest_path));
+    };
+
+    for file in &chapter_files {
+        syntax.push(TopLevelSyntax::Chapter(file.clone()));
+        syntax.push(TopLevelSyntax::Include(file.clone()));
+    }
+
+    let document = parse_syntax(resolve_includes(syntax, Some(manifest_path))?, Some(manifest_path))?;
+
+    let mut ranges = Vec::<(String, usize, usize)>::new();
+    for (i, (file, start)) in document.chapters.iter().enumerate() {
+        let end = if let Some((_, next)) = document.chapters.get(i + 1) { *next } else { document.body.len() };
+        let start = (*start).min(document.body.len());
+        let end   = end.min(document.body.len());
+        ranges.push((format!("{page_prefix}{}", chapter_name(file)), start, end));
+    }
+
+    let mut ids = HashMap::<String, String>::new();
+    for (i, (name, start, end)) in ranges.iter().enumerate() {
+        let page = format!("{name}.html");
+        collect_ids(&document.body[*start..*end], &page, &mut ids);
+        // the appendix is placed at the end of the last chapter
+        if i == ranges.len() - 1 {
+            collect_ids(&document.appendix, &page, &mut ids);
+        }
+    }
+
+    let mut chapters = Vec::<(String, PawsMarkdown)>::new();
+    for (i, (name, start, end)) in ranges.iter().enumerate() {
+        let page = format!("{name}.html");
+        let mut chapter = document.clone();
+        chapter.chapters.clear();
+        chapter.body = document.body[*start..*end].to_vec();
+        if i != ranges.len() - 1 {
+            chapter.appendix.clear();
+        }
+
+        if chapter.header.toc.is_some() {
+            chapter.body.insert(0, (BlogBody::TOCLocationMarker, String::new()));
+        }
+
+        chapter.header.external_ids = ids.iter()
+            .filter(|(_, other)| *other != &page)
+            .map(|(id, other)| (id.clone(), other.clone()))
+            .collect();
+
+        chapters.push((name.clone(), chapter));
+    }
+
+    Ok(Book { document, chapters })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::book::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn test_book_cross_chapter_links() {
+        let dir = TestDir::new("book");
+        let manifest = dir.write("book.pmd", "---\ntitle: book\nchapters:\n    - intro.pmd\n    - method.pmd\n---\n");
+        dir.write("intro.pmd", "# intro\n");
+        dir.write("method.pmd", "# method\n");
+
+        let manifest = manifest.to_string_lossy().to_string();
+        let result = book_parse(&manifest, "book-");
+
+        assert!(result.is_ok());
+        let book = result.unwrap();
+        assert_eq!(book.document.body.len(), 2);
+        assert_eq!(book.chapters.len(), 2);
+
+        let (name, intro) = &book.chapters[0];
+        assert_eq!(name, "book-intro");
+        assert_eq!(intro.body.len(), 1);
+        assert_eq!(intro.header.resolve_href("intro"),  "#intro");
+        assert_eq!(intro.header.resolve_href("method"), "book-method.html#method");
+    }
+}
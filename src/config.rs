@@ -6,9 +6,21 @@ pub const DEFAULT_BLOG_DIR: &'static str = "blog";
 pub const DEFAULT_BIBLIOGRAPHY_TITLE: &'static str = "Litteraturliste";
 pub const DEFAULT_NOTES_TITLE: &'static str = "Fodnoter";
 pub const DEFAULT_CONTACT_TITLE: &'static str = "Kildeliste";
+pub const DEFAULT_APPENDIX_TITLE: &'static str = "Bilag";
+pub const DEFAULT_GLOSSARY_TITLE: &'static str = "Ordliste";
+pub const DEFAULT_ABBREVIATIONS_TITLE: &'static str = "Forkortelser";
+pub const DEFAULT_INDEX_TITLE: &'static str = "Stikordsregister";
 pub const DEFAULT_FACTBOX_TITLE: &'static str = "Fakta";
+pub const DEFAULT_NOTE_TITLE: &'static str = "Bemærk";
+pub const DEFAULT_WARNING_TITLE: &'static str = "Advarsel";
+pub const DEFAULT_TIP_TITLE: &'static str = "Tip";
+pub const DEFAULT_DEFINITION_TITLE: &'static str = "Definition";
+pub const DEFAULT_EXAMPLE_TITLE: &'static str = "Eksempel";
+pub const DEFAULT_THEOREM_TITLE: &'static str = "Sætning";
 
 pub const MAX_ID_LENGTH: usize = 64;
+pub const MAX_SECTION_DEPTH: usize = 6;
+pub const MAX_FACTBOX_DEPTH: usize = 3;
 
 pub const FRONTMATTER_HIDE_NOTES: [&'static str; 6] = [
     "hide-notes", "hide-endnotes", "hide-end-notes",
@@ -29,3 +41,163 @@ pub const FRONTMATTER_SHOULD_CITE_CONTACTS: [&'static str; 4] = [
     "cite-contacts", "should-cite-contacts", "contacts-in-text",
     "contact-citation",
 ];
+
+pub const FRONTMATTER_NUMBER_CALLOUTS: [&'static str; 3] = [
+    "number-callouts", "number_callouts", "number callouts",
+];
+
+pub const FRONTMATTER_LIST_ABBREVIATIONS: [&'static str; 3] = [
+    "list-abbreviations", "list_abbreviations", "list abbreviations",
+];
+
+pub const FRONTMATTER_NO_EMOJI: [&'static str; 3] = [
+    "no-emoji", "no_emoji", "no emoji",
+];
+
+pub const UNNUMBERED_MARKERS: [&'static str; 2] = [
+    "{-}", "{.unnumbered}",
+];
+
+// a space between a number and one of these units is made non-breaking by the typography pass
+pub const TYPOGRAPHY_UNITS: [&'static str; 30] = [
+    "%", "‰", "°", "°C", "kr", "kr.", "€", "$",
+    "mm", "cm", "m", "km", "g", "kg", "t", "ml", "dl", "l",
+    "ms", "s", "min", "h", "Hz", "kHz", "MHz", "GHz",
+    "kB", "MB", "GB", "TB",
+];
+
+// as is the space after one of these abbreviations, so "s. 4" never ends up on two lines
+pub const TYPOGRAPHY_ABBREVIATIONS: [&'static str; 10] = [
+    "s.", "S.", "fig.", "Fig.", "nr.", "Nr.", "kap.", "Kap.", "p.", "pp.",
+];
+
+// schemes recognised by <...> autolinks, bare urls in text only use the first two
+pub const AUTOLINK_SCHEMES: [&'static str; 3] = [
+    "http://", "https://", "mailto:",
+];
+
+// the native pdf backend uses the first of these families that is installed, unless pdf-font says otherwise
+pub const PDF_FONT_FAMILIES: [&'static str; 6] = [
+    "Atkinson Hyperlegible", "DejaVu Sans", "Liberation Sans", "Noto Sans", "Open Sans", "Arial",
+];
+
+pub const PDF_MONO_FONT_FAMILIES: [&'static str; 5] = [
+    "DejaVu Sans Mono", "Liberation Mono", "Noto Sans Mono", "Ubuntu Mono", "Courier New",
+];
+
+// DejaVu Sans and DejaVu Sans Mono are built in, for systems without any fonts installed (e.g. a
+// minimal container), see assets/fonts/LICENSE-DejaVu
+pub const PDF_FALLBACK_FONTS: [&'static [u8]; 2] = [
+    include_bytes!("../assets/fonts/DejaVuSans.ttf"),
+    include_bytes!("../assets/fonts/DejaVuSansMono.ttf"),
+];
+
+// sizes are in points, pdf-paper picks one of these by name, the first is the default
+pub const PDF_PAPER_SIZES: [(&'static str, f32, f32); 5] = [
+    ("A4", 595.28, 841.89), ("A5", 419.53, 595.28), ("A3", 841.89, 1190.55),
+    ("Letter", 612.0, 792.0), ("Legal", 612.0, 1008.0),
+];
+pub const POINTS_PER_MM: f32 = 72.0 / 25.4;
+pub const PDF_MARGIN: f32 = 56.69;
+pub const PDF_COLUMN_GAP: f32 = 18.0;
+pub const PDF_TEXT_SIZE: f32 = 11.0;
+pub const PDF_LINE_HEIGHT: f32 = 1.4;
+
+// the toc and cross-references are laid out again until their page numbers stop changing,
+// at most this many times
+pub const PDF_LAYOUT_PASSES: usize = 3;
+
+// the word in front of page numbers in cross-references, set with pdf-page-label
+pub const PDF_PAGE_LABEL: &'static str = "page";
+
+// draft: true adds a watermark, line numbers and the time it was built, and leaves room for
+// comments in a right margin at least this wide (6cm)
+pub const DRAFT_WATERMARK: &'static str = "KLADDE/DRAFT";
+pub const DRAFT_TIMESTAMP_FORMAT: &'static str = "%Y-%m-%d %H:%M";
+pub const DRAFT_MARGIN: f32 = 170.08;
+
+// the built in title pages, pdf-title-page picks one by name or reads a template file. the
+// first is the default, the front page of a project at SDU
+pub const TITLE_PAGE_TEMPLATES: [(&'static str, &'static str); 2] = [
+    ("sdu", "\
+:::space 2cm
+:::center
+### Syddansk Universitet
+{institute}
+{course-code} {course}
+:::space 3cm
+# {title}
+## {subtitle}
+:::image {banner}
+:::bottom
+:::left
+:::if author
+### Forfattere
+{author} ({student-number})
+:::space 0.4cm
+:::end
+Vejleder: {supervisor}
+Afleveret: {hand-in}
+Antal anslag: {characters}
+"),
+    ("plain", "\
+:::space 8cm
+:::center
+# {title}
+## {subtitle}
+:::image {banner}
+"),
+];
+
+// the stylesheets and scripts of sirpaws.dev a self contained page is written with, so it doesn't
+// depend on the site
+pub const SELF_CONTAINED_STYLESHEETS: [&'static str; 2] = [
+    include_str!("../assets/css/base.css"),
+    include_str!("../assets/css/blog.css"),
+];
+pub const SELF_CONTAINED_SCRIPTS: [&'static str; 2] = [
+    include_str!("../assets/js/popup.js"),
+    include_str!("../assets/js/backref.js"),
+];
+
+// the built in html templates, html-template picks one by name or reads a template file. without
+// one the page gets the navbar and stylesheets of sirpaws.dev
+pub const HTML_TEMPLATES: [(&'static str, &'static str); 1] = [
+    ("plain", "\
+<!doctype html>
+<html>
+<head>
+<meta http-equiv=\"content-type\" content=\"text/html; charset=utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1, minimum-scale=1\">
+<title>{{title}}</title>
+<style>
+body { font-family: system-ui, sans-serif; line-height: 1.5; color: #222; margin: 0; }
+main { max-width: 46em; margin: 0 auto; padding: 1em; }
+.title { margin: 3em 0 2em 0; }
+.subtitle { color: #666; font-size: larger; }
+pre { background: #f3f3f3; padding: .75em; overflow-x: auto; }
+img { max-width: 100%; }
+a { color: #1a4f9c; }
+.page-break hr { border: none; border-top: 1px solid #ccc; margin: 2em 0; }
+#popup, a.paragraph, a.header { display: none; }
+footer { color: #666; font-size: small; text-align: center; padding: 2em; }
+</style>
+</head>
+<body>
+<main>
+{{title-section}}
+{{body}}
+{{notes}}
+{{glossary}}
+{{abbreviations}}
+{{contacts}}
+{{bibliography}}
+{{appendix}}
+</main>
+<footer>
+{{footer}}
+</footer>
+</body>
+</html>
+"),
+];
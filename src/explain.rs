@@ -13,16 +13,35 @@ pub fn explain(feature: &Option<String>, extra: &Option<String>) {
         println!("    notes-title           #[notes-title] New title");
         println!("    bibliography-title    #[bibliography-title] New title");
         println!("    toc                   #[toc] table of contents title here");
+        println!("    appendix              #[appendix] appendix title here");
+        println!("    book                  chapters: [01-intro.pmd, 02-method.pmd]");
+        println!("    glossary              #[glossary] term: definition here");
+        println!("    abbreviation          *[SDU]: Syddansk Universitet");
+        println!("    index                 #[index] index title here");
+        println!("    index term            parsers[+parser]");
+        println!("    emoji                 :smile:");
         println!("    image                 [[path/to/image] alt text here]");
         println!("    link                  [[http://example.link]]");
         println!("    header                # header text here");
+        println!("    unnumbered header     # header text here {{-}}");
         println!("    codeblock             ```lang_name\\n text here ```");
+        println!("    definition list       term\\n: definition here");
         println!("    bold                  **bold text here**");
         println!("    italics               *italics text here*");
+        println!("    strikethrough         ~~struck text here~~");
+        println!("    superscript           x^2^");
+        println!("    subscript             H~2~O");
+        println!("    highlight             ==highlighted text here==");
+        println!("    underline             ++underlined text here++");
+        println!("    fact box              [[fact] title\\n text here ]");
+        println!("    callout               [[warning] title\\n text here ]");
         println!("    hover                 %[base text](hovered text)");
         println!("    style                 £{{css_style: here;}}(text)");
         println!("    link                  [link text](https://link.here)");
+        println!("    reference link        [link text][label] and [label]: https://link.here");
+        println!("    autolink              <https://link.here>");
         println!("    citation              [£some-citation]");
+        println!("    note                  [^some-note]: note text here");
         println!("    contact               [?some-contact]");
     };
     
@@ -176,8 +195,133 @@ pub fn explain(feature: &Option<String>, extra: &Option<String>) {
                 println!("Example: ");
                 println!("    #  A level 1 header");
                 println!("    ## A level 2 header");
+                println!("    #  A header with a fixed id {{#fixed-id}}");
                 println!();
                 println!("Note: ");
+                println!("    a trailing {{#id}} sets the id used for links instead of");
+                println!("    generating one from the text, it also works on images,");
+                println!("    code blocks (after the language), quotes and fact boxes.");
+                println!("    explicit ids have to be unique.");
+                println!();
+            },
+            "book" | "books" | "chapter" | "chapters" => {
+                println!("Books:");
+                println!("    A book is a regular file whose frontmatter lists the chapter");
+                println!("    files in order, 'pmd book' outputs one combined document");
+                println!("    and a page per chapter.");
+                println!();
+                println!("Example: ");
+                println!("    ---");
+                println!("    title: Course compendium");
+                println!("    chapters:");
+                println!("        - chapters/01-intro.pmd");
+                println!("        - chapters/02-method.pmd");
+                println!("    ---");
+                println!("    #[toc] Contents");
+                println!();
+                println!("Note: ");
+                println!("    chapter paths are relative to the book file, references, contacts");
+                println!("    and notes are shared between all chapters, links to a header in");
+                println!("    another chapter will point to that chapter's page.");
+            },
+            "note" | "notes" | "footnote" | "footnotes" => {
+                println!("Notes:");
+                println!();
+                println!("Example: ");
+                println!("    some text that needs a note[^1]");
+                println!();
+                println!("    [^1]: the first paragraph of the note.");
+                println!();
+                println!("        a second paragraph, lines indented by four spaces or a");
+                println!("        tab continue the note.");
+                println!();
+                println!("        - lists, quotes and code blocks work as well");
+                println!();
+                println!("Note: ");
+                println!("    notes are listed at the end of the document.");
+                println!();
+            },
+            "fact" | "factbox" | "callout" | "callouts" => {
+                println!("Fact Boxes and Callouts:");
+                println!();
+                println!("Example: ");
+                println!("    [[fact] an optional title");
+                println!("        text, lists and other elements here");
+                println!("    ]");
+                println!();
+                println!("    [[definition] Prime number");
+                println!("        a number only divisible by itself and 1");
+                println!("    ]");
+                println!();
+                println!("Note: ");
+                println!("    the kinds are fact, note, warning, tip, definition, example and");
+                println!("    theorem, each with their own default title and style.");
+                println!("    with 'number-callouts' in the frontmatter definitions, examples");
+                println!("    and theorems are numbered, as in \"Definition 2.1\".");
+                println!("    fact boxes can be nested up to three levels deep, notes defined");
+                println!("    inside of a fact box belong to that fact box.");
+                println!();
+            },
+            "glossary" | "definition" | "definitions" | "definition-list" => {
+                println!("Definition Lists and Glossary:");
+                println!();
+                println!("Example: ");
+                println!("    Term");
+                println!("    : the definition of the term");
+                println!("    : a second definition");
+                println!();
+                println!("    #[glossary] API: Application Programming Interface");
+                println!();
+                println!("Note: ");
+                println!("    glossary entries are collected into a glossary at the end of");
+                println!("    the document, the first time a term is used in the text its");
+                println!("    definition is shown when hovering it.");
+                println!();
+            },
+            "abbreviation" | "abbreviations" | "abbr" => {
+                println!("Abbreviations:");
+                println!();
+                println!("Example: ");
+                println!("    *[SDU]: Syddansk Universitet");
+                println!();
+                println!("Note: ");
+                println!("    the first time an abbreviation is used it is written out, as in");
+                println!("    \"Syddansk Universitet (SDU)\", after that it is shown as is with");
+                println!("    the expansion on hover. abbreviations are case sensitive and");
+                println!("    have to be whole words. with 'list-abbreviations' in the");
+                println!("    frontmatter a list of them is added to the end of the document.");
+                println!();
+            },
+            "index" | "index-term" | "index-terms" => {
+                println!("Index:");
+                println!();
+                println!("Example: ");
+                println!("    a parser[+parser] reads tokens[+token]");
+                println!();
+                println!("    #[index] Stikordsregister");
+                println!();
+                println!("Note: ");
+                println!("    index terms aren't shown in the text, they mark where a term is");
+                println!("    mentioned. #[index] places an alphabetical index of the terms,");
+                println!("    in html every term links to the sections it is mentioned in and");
+                println!("    in pdfs it lists the pages instead.");
+                println!();
+            },
+            "emoji" | "emojis" | "symbol" | "symbols" => {
+                println!("Emoji and Symbols:");
+                println!();
+                println!("Example: ");
+                println!("    done :tada:");
+                println!();
+                println!("    ---");
+                println!("    symbols: {{\"(c)\": \"©\", \"->\": \"→\"}}");
+                println!("    ---");
+                println!();
+                println!("Note: ");
+                println!("    common emoji shortcodes like :smile:, :+1: and :warning: are");
+                println!("    replaced everywhere but in code, 'no-emoji' in the frontmatter");
+                println!("    turns them off. 'symbols' in the frontmatter adds replacements");
+                println!("    of your own.");
                 println!();
             },
             "codeblock" | "codeblocks" => {
@@ -234,8 +378,16 @@ pub fn explain(feature: &Option<String>, extra: &Option<String>) {
                 println!();
                 println!("Example: ");
                 println!("    [text goes here](https://link.goes.here)");
+                println!("    [text goes here][label]");
+                println!("    [label][]");
+                println!("    <https://link.goes.here>");
+                println!();
+                println!("    [label]: https://link.goes.here");
                 println!();
                 println!("Note: ");
+                println!("    labels are defined on their own line anywhere in the document and");
+                println!("    are case insensitive, bare urls starting with http:// or https://");
+                println!("    are turned into links automatically.");
                 println!();
             },
             "contact" | "contacts"  => {
@@ -48,6 +48,7 @@ impl PartialEq for Frontmatter {
 
 pub trait FrontmatterHelper {
     fn as_string(&self) -> Option<String>;
+    fn as_list(&self) -> Vec<String>;
 }
 
 impl FrontmatterHelper for Value {
@@ -59,6 +60,14 @@ impl FrontmatterHelper for Value {
             _ => None
         }
     }
+
+    // a single value or a list of them, as in "author: me" or "author: [me, you]"
+    fn as_list(&self) -> Vec<String> {
+        match self {
+            Value::Sequence(values) => values.iter().filter_map(|value| value.as_string()).collect(),
+            value => value.as_string().into_iter().collect(),
+        }
+    }
 }
 
 impl Index<&str> for Frontmatter {
@@ -17,6 +17,9 @@ mod explain;
 mod toplevel;
 #[macro_use]
 mod paws_markdown;
+mod typography;
+mod symbols;
+mod book;
 mod pmd_serializer;
 mod config;
 mod pdf;
@@ -25,23 +28,42 @@ mod ordered_map;
 mod pmd_pure_text;
 #[cfg(feature = "html")]
 mod pmd_html;
-#[cfg(feature = "pdf")]
+#[cfg(feature = "html")]
+mod self_contained;
+#[cfg(all(feature = "pdf", any(feature = "chrome_pdf", feature = "mock_pdf")))]
 mod pmd_pdf;
+#[cfg(feature = "pdf")]
+mod page_geometry;
+#[cfg(feature = "pdf")]
+mod title_page;
+#[cfg(all(feature = "native_pdf", not(any(feature = "chrome_pdf", feature = "mock_pdf"))))]
+mod pdf_fonts;
+#[cfg(all(feature = "native_pdf", not(any(feature = "chrome_pdf", feature = "mock_pdf"))))]
+mod pdf_layout;
+#[cfg(all(feature = "native_pdf", not(any(feature = "chrome_pdf", feature = "mock_pdf"))))]
+mod pdf_native;
 #[cfg(feature = "wasm")]
 mod pmd_wasm;
 #[cfg(any(feature = "wasm", feature = "html", feature = "pdf"))]
 mod pmd_html_shared;
+#[cfg(any(feature = "wasm", feature = "html", feature = "pdf"))]
+mod syntax;
+#[cfg(test)]
+mod test_dir;
 
 use frontmatter::*;
 use references::*;
 use toplevel::*;
 use paws_markdown::*;
+use typography::*;
+use symbols::*;
+use book::*;
 use pmd_serializer::*;
 #[cfg(feature = "text")]
 use pmd_pure_text::*;
 #[cfg(feature = "html")]
 use pmd_html::*;
-#[cfg(feature = "pdf")]
+#[cfg(all(feature = "pdf", any(feature = "chrome_pdf", feature = "mock_pdf")))]
 use pmd_pdf::*;
 
 
@@ -93,13 +115,20 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     #[cfg(feature = "html")]
-    Html {files: Vec<PathBuf> },
+    Html {
+        files: Vec<PathBuf>,
+        // writes the stylesheets, scripts and images into the page, so it is a single file
+        #[arg(long)] self_contained: bool,
+    },
     
     #[cfg(feature = "text")]
     Text  {files: Vec<PathBuf> },
 
     #[cfg(feature = "pdf")]
     Pdf   {files: Vec<PathBuf> },
+
+    #[cfg(feature = "html")]
+    Book  {manifest: PathBuf },
     
     #[cfg(feature = "text")]
     Paragraph {file: PathBuf },
@@ -144,7 +173,7 @@ pub fn execute() -> Result<()> {
             println!("{text}");
         },
         #[cfg(feature = "html")]
-        Commands::Html{files} => {
+        Commands::Html{files, self_contained} => {
             let out_dir = Path::new(dir.as_str());
             for file in files {
                 let stem = file.as_path().file_stem().context("expected file name")?;
@@ -153,7 +182,13 @@ pub fn execute() -> Result<()> {
                 println!("outputting to file {}", out_file.to_str().expect("whatever"));
 
                 let result = file_parse(&file.to_str().context("expected a file")?.to_string())?;
-                let html = to_string_from_boxed(&result, PMDHTMLSerializer::new(stem.to_str().context("converting OsStr to str")?))?;
+                let mut serializer = PMDHTMLSerializer::new(stem.to_str().context("converting OsStr to str")?);
+                serializer.self_contained = *self_contained;
+                let mut html = to_string_from_boxed(&result, serializer)?;
+                if *self_contained {
+                    let directories = vec![out_dir.to_path_buf(), file.parent().map(Path::to_path_buf).unwrap_or_default(), PathBuf::from(".")];
+                    html = self_contained::Inliner::new(directories).inline(&html);
+                }
                 if out_file.exists() {
                     fs::remove_file(&out_file)?;
                 }
@@ -171,13 +206,45 @@ pub fn execute() -> Result<()> {
                 println!("outputting to file {}", out_file.to_str().expect("whatever"));
 
                 let result = file_parse(&file.to_str().context("expected a file")?.to_string())?;
-                let html   = to_string_from_boxed(&result, PMDPDFSerializer::new(stem.as_str()))?;
+                let pdf    = pdf::render_pdf(&result, stem.as_str())?;
                 if out_file.exists() {
                     fs::remove_file(&out_file)?;
                 }
-                fs::write(out_file, html)?;
+                fs::write(out_file, pdf)?;
             }
         }, 
+        #[cfg(feature = "html")]
+        Commands::Book{manifest} => {
+            let out_dir = Path::new(dir.as_str());
+            let stem = manifest.as_path().file_stem().context("expected file name")?;
+            let stem = stem.to_str().context("converting OsStr to str")?;
+
+            let book = book_parse(&manifest.to_str().context("expected a file")?.to_string(), format!("{stem}-").as_str())?;
+
+            let mut out_file = out_dir.join(stem);
+            out_file.set_extension("html");
+            println!("outputting to file {}", out_file.to_str().expect("whatever"));
+            let html = to_string_from_boxed(&book.document, PMDHTMLSerializer::new(stem))?;
+            fs::write(out_file, html)?;
+
+            for (name, chapter) in &book.chapters {
+                let mut out_file = out_dir.join(name);
+                out_file.set_extension("html");
+                println!("outputting to file {}", out_file.to_str().expect("whatever"));
+                let html = to_string_from_boxed(chapter, PMDHTMLSerializer::new(name))?;
+                fs::write(out_file, html)?;
+            }
+
+            #[cfg(feature = "pdf")]
+            {
+                let stem = format!("pdf-{stem}");
+                let mut out_file = out_dir.join(stem.clone());
+                out_file.set_extension("pdf");
+                println!("outputting to file {}", out_file.to_str().expect("whatever"));
+                let pdf = pdf::render_pdf(&book.document, stem.as_str())?;
+                fs::write(out_file, pdf)?;
+            }
+        },
         #[cfg(feature = "text")]
         Commands::Text{files} => {
             for file in files {
@@ -229,6 +296,14 @@ pub fn execute() -> Result<()> {
                         println!("    ");
                         println!("    notes-title");
                         println!("    ");
+                        println!("    glossary-title");
+                        println!("    glossary_title");
+                        println!("    glossary title");
+                        println!("    ");
+                        println!("    abbreviations-title");
+                        println!("    abbreviations_title");
+                        println!("    abbreviations title");
+                        println!("    ");
                         println!("    bibliography-title");
                         println!("    references-title");
                         println!("    sources-title");
@@ -251,22 +326,70 @@ pub fn execute() -> Result<()> {
                         println!("    last_updated");
                         println!("    last updated");
                         println!("    ");
+                        println!("    author            one or more authors, written into the pdf metadata");
+                        println!("    authors");
+                        println!("    keywords          written into the pdf metadata");
+                        println!("    ");
                         println!("    cite-contacts");
                         println!("    ");
+                        println!("    chapters          list of chapter files, only used by 'pmd book'");
+                        println!("    ");
+                        println!("    number-sections   true, false or the deepest header level to number");
+                        println!("    number_sections");
+                        println!("    number sections");
+                        println!("    ");
+                        println!("    number-callouts   numbers definitions, examples and theorems");
+                        println!("    ");
+                        println!("    list-abbreviations adds a list of the abbreviations to the end of the document");
+                        println!("    ");
+                        println!("    typography        curly quotes, dashes and ellipses, either danish (true) or english");
+                        println!("    smart-typography");
+                        println!("    smart_typography");
+                        println!("    smart typography");
+                        println!("    ");
+                        println!("    symbols           replacements for the text, as in {{\"(c)\": \"©\"}}");
+                        println!("    no-emoji          leaves :shortcodes: as they are");
+                        println!("    draft             true adds a watermark, line numbers, a wide margin and the build time");
+                        println!("    html-template     the page around the html, 'plain', a template file or a directory with a template.html");
+                        println!("    code-theme        the colours of code blocks, 'github' (default), 'solarized', 'monokai' or 'none'");
+                        println!("    ");
                         println!("    pdf-no-first-page removes the first page and adds title/subtitle to the document");
+                        println!("    pdf-title-page    the template of the first page, 'sdu' (default), 'plain' or a file");
+                        println!("    authors           names, or name: student number pairs, also used on the title page");
+                        println!("    student-numbers");
+                        println!("    supervisor");
+                        println!("    course");
+                        println!("    course-code");
+                        println!("    institute");
+                        println!("    hand-in");
                         println!("    pdf-text-size     sets the font size for paragraphs");
                         println!("    pdf-line-height   sets the line height");
-                        println!("    pdf-font          changes the font");
+                        println!("    pdf-font          changes the font, either an installed font or a .ttf/.otf file");
+                        println!("    pdf-page-label    the word before page numbers in cross-references, 'page' by default");
+                        println!("    ");
+                        println!("    pdf-paper         A4 (default), A5, A3, Letter or Legal");
+                        println!("    pdf-orientation   portrait (default) or landscape");
+                        println!("    pdf-margin        all of the margins, e.g. 2cm, 20mm, 1in or 72pt");
+                        println!("    pdf-margin-top");
+                        println!("    pdf-margin-bottom");
+                        println!("    pdf-margin-inner  the left margin, or the margin towards the binding when mirrored");
+                        println!("    pdf-margin-outer");
+                        println!("    pdf-mirror-margins swaps the inner and outer margin on even pages");
+                        println!("    pdf-columns       the number of columns");
+                        println!("    pdf-column-gap    the space between the columns");
+                        println!("    pdf-landscape-sections ids of the sections to put on landscape pages");
                         println!("    ");
                         println!("    pdf-header        inserts text into the header, centered");
                         println!("    pdf-header-left   inserts text into the header, left aligned");
                         println!("    pdf-header-center inserts text into the header, centered");
                         println!("    pdf-header-right  inserts text into the header, right aligned");
+                        println!("    pdf-header-even   the header on even pages, also -left, -center and -right");
                         println!("    ");
                         println!("    pdf-footer        inserts text into the footer, centered");
                         println!("    pdf-footer-left   inserts text into the footer, left aligned");
                         println!("    pdf-footer-center inserts text into the footer, centered");
                         println!("    pdf-footer-right  inserts text into the footer, right aligned");
+                        println!("    pdf-footer-even   the footer on even pages, also -left, -center and -right");
                     },
                     "citation"    => {
                         println!("Citation Keys:");
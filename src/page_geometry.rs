@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+
+#[cfg(not(feature = "wasm"))]
+use color_print::cprintln;
+use serde_yaml::Value;
+use config::*;
+use crate::*;
+
+// the paper, margins and columns of a pdf. sizes are in points, margins are called inner and
+// outer so they can be swapped on even pages when the document is printed for binding
+#[derive(Debug, PartialEq, Clone)]
+pub struct PageGeometry {
+    pub width: f32,
+    pub height: f32,
+    pub top: f32,
+    pub bottom: f32,
+    pub inner: f32,
+    pub outer: f32,
+    pub mirrored: bool,
+    pub columns: usize,
+    pub column_gap: f32,
+    // ids of the sections that are put on landscape pages, e.g. for wide tables
+    pub landscape_sections: HashSet<String>,
+}
+
+// lengths are written as "2.5cm", "20mm", "1in" or "72pt", plain numbers are millimetres
+pub fn parse_length(value: &Value) -> Option<f32> {
+    if let Some(number) = value.as_f64() {
+        return Some(number as f32 * POINTS_PER_MM);
+    }
+    let text = value.as_str()?.trim().to_lowercase();
+    let unit_start = text.find(|c: char| c.is_alphabetic()).unwrap_or(text.len());
+    let number = text[..unit_start].trim().parse::<f32>().ok()?;
+    let scale = match text[unit_start..].trim() {
+        "" | "mm" => POINTS_PER_MM,
+        "cm" => POINTS_PER_MM * 10.0,
+        "in" => 72.0,
+        "pt" => 1.0,
+        _ => return None,
+    };
+    Some(number * scale)
+}
+
+fn length(frontmatter: &Frontmatter, key: &str, default: f32) -> f32 {
+    if !frontmatter.has(key) { return default }
+    parse_length(&frontmatter[key]).unwrap_or_else(|| {
+        #[cfg(not(feature = "wasm"))]
+        cprintln!("<y>warning:</> '{}' is not a length, write it as e.g. 2.5cm, 20mm, 1in or 72pt", key);
+        default
+    })
+}
+
+impl PageGeometry {
+    pub fn from_frontmatter(frontmatter: &Frontmatter) -> Self {
+        let paper = frontmatter["pdf-paper"].as_string().unwrap_or(PDF_PAPER_SIZES[0].0.into());
+        let (_, mut width, mut height) = *PDF_PAPER_SIZES.iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(paper.trim()))
+            .unwrap_or_else(|| {
+                #[cfg(not(feature = "wasm"))]
+                cprintln!("<y>warning:</> unknown paper size '{}', using {} instead", paper, PDF_PAPER_SIZES[0].0);
+                &PDF_PAPER_SIZES[0]
+            });
+        if frontmatter["pdf-orientation"].as_str().is_some_and(|orientation| orientation.trim() == "landscape") {
+            (width, height) = (height, width);
+        }
+
+        let margin = length(frontmatter, "pdf-margin", PDF_MARGIN);
+        let columns = frontmatter["pdf-columns"].as_u64().unwrap_or(1).max(1) as usize;
+        // drafts get a wide outer margin for comments
+        let mut outer = length(frontmatter, "pdf-margin-outer", margin);
+        if frontmatter["draft"].as_bool().unwrap_or(false) {
+            outer = outer.max(DRAFT_MARGIN);
+        }
+        Self {
+            width,
+            height,
+            top:    length(frontmatter, "pdf-margin-top", margin),
+            bottom: length(frontmatter, "pdf-margin-bottom", margin),
+            inner:  length(frontmatter, "pdf-margin-inner", margin),
+            outer,
+            mirrored: frontmatter["pdf-mirror-margins"].as_bool().unwrap_or(false),
+            columns,
+            column_gap: length(frontmatter, "pdf-column-gap", PDF_COLUMN_GAP),
+            landscape_sections: frontmatter["pdf-landscape-sections"].as_list().into_iter().collect(),
+        }
+    }
+
+    // the same margins on the paper turned sideways
+    pub fn landscape(&self) -> Self {
+        Self { width: self.height.max(self.width), height: self.height.min(self.width), ..self.clone() }
+    }
+
+    pub fn with_size(&self, width: f32, height: f32) -> Self {
+        Self { width, height, ..self.clone() }
+    }
+
+    // pages are counted from 0, so the first page is a right hand page with the inner margin to the left
+    pub fn left_margin(&self, page: usize) -> f32 {
+        if self.mirrored && page % 2 == 1 { self.outer } else { self.inner }
+    }
+
+    pub fn right_margin(&self, page: usize) -> f32 {
+        if self.mirrored && page % 2 == 1 { self.inner } else { self.outer }
+    }
+
+    pub fn column_width(&self) -> f32 {
+        let text_width = self.width - self.inner - self.outer;
+        (text_width - self.column_gap * (self.columns - 1) as f32) / self.columns as f32
+    }
+
+    // the left and right edge of a column
+    pub fn column(&self, page: usize, column: usize) -> (f32, f32) {
+        let left = self.left_margin(page) + (self.column_width() + self.column_gap) * column as f32;
+        (left, left + self.column_width())
+    }
+
+    // the left and right edge of the text across all of the columns
+    pub fn text_area(&self, page: usize) -> (f32, f32) {
+        (self.left_margin(page), self.width - self.right_margin(page))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::page_geometry::*;
+
+    #[test]
+    fn test_parse_length() {
+        assert_eq!(parse_length(&Value::String("1in".into())), Some(72.0));
+        assert_eq!(parse_length(&Value::String("10 pt".into())), Some(10.0));
+        assert_eq!(parse_length(&Value::String("2cm".into())), parse_length(&Value::Number(20.into())));
+        assert_eq!(parse_length(&Value::String("2 parsecs".into())), None);
+    }
+
+    #[test]
+    fn test_mirrored_columns() {
+        let text = "---\npdf-margin: 1in\npdf-margin-inner: 2in\npdf-mirror-margins: true\npdf-columns: 2\npdf-column-gap: 36pt\n---\n";
+        let (frontmatter, _) = parse_frontmatter(text);
+        let geometry = PageGeometry::from_frontmatter(&frontmatter.unwrap());
+        assert_eq!(geometry.left_margin(0), 144.0);
+        assert_eq!(geometry.left_margin(1), 72.0);
+        let width = geometry.column_width();
+        assert_eq!(geometry.column(1, 1), (72.0 + width + 36.0, 72.0 + width * 2.0 + 36.0));
+        assert_eq!(geometry.text_area(1).1, geometry.width - 144.0);
+    }
+}
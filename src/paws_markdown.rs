@@ -1,10 +1,12 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 #[cfg(not(feature = "wasm"))]
 use color_print::cprintln;
 use config::*;
 use contact::ContactDefinition;
 use ordered_map::OrderedMap;
+use anyhow::anyhow;
 use crate::*;
 
 macro_rules! no_id {
@@ -17,6 +19,14 @@ pub struct TableOfContent {
     pub index:   usize,
     pub max_depth: usize,
     pub headers: Vec<(Box<BlogBody>, /*depth: */ usize, /*id: */ String)>,
+    pub appendix: Vec<(Box<BlogBody>, /*depth: */ usize, /*id: */ String)>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Index {
+    pub title: String,
+    pub entries: Vec<(/*term: */ String, /*locations: */ Vec<(/*marker id: */ String, /*section id: */ String)>)>,
+    pub sections: HashMap<String, Box<BlogBody>>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -34,9 +44,25 @@ pub struct BlogHeader {
     pub hide_contacts: bool,
     pub should_cite_contacts: bool,
     pub toc: Option<TableOfContent>,
+    pub index: Option<Index>,
     pub contacts_title: String,
     pub bibliography_title: String,
     pub notes_title: String,
+    pub appendix_title: String,
+    pub glossary_title: String,
+    pub abbreviations_title: String,
+    pub number_sections: usize,
+    pub number_callouts: bool,
+    pub list_abbreviations: bool,
+    pub typography: Option<Typography>,
+    pub emoji: bool,
+    pub draft: bool,
+    pub symbols: Vec<(/*symbol: */ String, /*replacement: */ String)>,
+    pub section_numbers: HashMap<String, String>,
+    pub external_ids: HashMap<String, String>,
+    pub sources: HashMap<String, String>,
+    // the directory of the markdown file, empty when it isn't parsed from a file
+    pub document_dir: String,
     pub frontmatter: Option<Frontmatter>,
 }
 
@@ -52,6 +78,7 @@ impl BlogHeader {
             date_written: PmdDate::None,
             last_update: PmdDate::None,
             toc:      None,
+            index:    None,
             hide_references: false,
             hide_notes: false,
             hide_contacts: false,
@@ -59,9 +86,46 @@ impl BlogHeader {
             contacts_title: DEFAULT_CONTACT_TITLE.into(),
             bibliography_title: DEFAULT_BIBLIOGRAPHY_TITLE.into(),
             notes_title: DEFAULT_NOTES_TITLE.into(),
+            appendix_title: DEFAULT_APPENDIX_TITLE.into(),
+            glossary_title: DEFAULT_GLOSSARY_TITLE.into(),
+            abbreviations_title: DEFAULT_ABBREVIATIONS_TITLE.into(),
+            number_sections: 0,
+            number_callouts: false,
+            list_abbreviations: false,
+            typography: None,
+            emoji: true,
+            draft: false,
+            symbols: vec![],
+            section_numbers: HashMap::new(),
+            external_ids: HashMap::new(),
+            sources: HashMap::new(),
+            document_dir: String::new(),
             frontmatter: None,
         }
     }
+
+    // "'ch/a.pmd': " in front of the warnings about a citation, contact or note used in an included file
+    pub fn source_of(&self, key: &str) -> String {
+        self.sources.get(key).map(|source| format!("'{source}': ")).unwrap_or_default()
+    }
+
+    // paths in the frontmatter are relative to the markdown file, like #[include]
+    pub fn resolve_path(&self, path: &str) -> PathBuf {
+        Path::new(&self.document_dir).join(path)
+    }
+
+    pub fn section_number(&self, id: &String) -> Option<&String> {
+        self.section_numbers.get(id)
+    }
+
+    // ids that live in another output file (e.g. another chapter of a book) link to that file
+    pub fn resolve_href(&self, id: &str) -> String {
+        if let Some(page) = self.external_ids.get(id) {
+            format!("{page}#{id}")
+        } else {
+            format!("#{id}")
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -83,6 +147,11 @@ pub enum BlogBody {
     Header(Box<BlogBody>, usize),
     Italics(Box<BlogBody>),
     Bold(Box<BlogBody>),
+    Strikethrough(Box<BlogBody>),
+    Superscript(Box<BlogBody>),
+    Subscript(Box<BlogBody>),
+    Highlight(Box<BlogBody>),
+    Underline(Box<BlogBody>),
     InlineCode(String),
     CodeBlock(String),
     Image(String, String),
@@ -90,14 +159,20 @@ pub enum BlogBody {
     FactBox(FactBox),
     Quote(Vec<BlogBody>),
     List(Vec<BlogBody>),
+    DefinitionList(Vec<(/*term: */ BlogBody, /*definitions: */ Vec<BlogBody>)>),
     Paragraph(Box<BlogBody>),
     Text(String),
     Span(Span),
     Citation(String),
     ContactCitation(String),
+    Abbreviation(/*abbreviation: */ String, /*expansion: */ String),
+    IndexTerm(/*term: */ String, /*id: */ String),
     Note(String),
+    // the body of a footnote spanning several paragraphs, lists, quotes or code blocks
+    Blocks(Vec<(BlogBody, String)>),
     PageBreak,
     TOCLocationMarker,
+    IndexLocationMarker,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -106,19 +181,101 @@ pub struct PawsMarkdown {
     pub bibliography_id: String,
     pub notes_id: String,
     pub contacts_id: String,
+    pub appendix_id: String,
+    pub glossary_id: String,
+    pub abbreviations_id: String,
     pub contacts: OrderedMap<String, ContactDefinition>,
     pub references: OrderedMap<String, ReferenceDefinition>,
     pub notes: OrderedMap<String, BlogBody>,
     pub body: Vec<(BlogBody, String)>,
+    pub appendix: Vec<(BlogBody, String)>,
+    pub glossary: Vec<(/*term: */ String, /*definition: */ BlogBody, /*id: */ String)>,
+    pub abbreviations: Vec<(/*abbreviation: */ String, /*expansion: */ String)>,
+    pub chapters: Vec<(/*file: */ String, /*body index: */ usize)>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Hash, Eq)]
+pub enum FactBoxKind {
+    Fact,
+    Note,
+    Warning,
+    Tip,
+    Definition,
+    Example,
+    Theorem,
+}
+
+impl FactBoxKind {
+    pub const ALL: [FactBoxKind; 7] = [
+        FactBoxKind::Fact, FactBoxKind::Note, FactBoxKind::Warning, FactBoxKind::Tip,
+        FactBoxKind::Definition, FactBoxKind::Example, FactBoxKind::Theorem,
+    ];
+
+    // the names used in the marker, as in [[warning] title ...]
+    pub fn markers(&self) -> &'static [&'static str] {
+        match self {
+            FactBoxKind::Fact       => &["fact", "factbox"],
+            FactBoxKind::Note       => &["note"],
+            FactBoxKind::Warning    => &["warning"],
+            FactBoxKind::Tip        => &["tip"],
+            FactBoxKind::Definition => &["definition"],
+            FactBoxKind::Example    => &["example"],
+            FactBoxKind::Theorem    => &["theorem"],
+        }
+    }
+
+    pub fn default_title(&self) -> &'static str {
+        match self {
+            FactBoxKind::Fact       => DEFAULT_FACTBOX_TITLE,
+            FactBoxKind::Note       => DEFAULT_NOTE_TITLE,
+            FactBoxKind::Warning    => DEFAULT_WARNING_TITLE,
+            FactBoxKind::Tip        => DEFAULT_TIP_TITLE,
+            FactBoxKind::Definition => DEFAULT_DEFINITION_TITLE,
+            FactBoxKind::Example    => DEFAULT_EXAMPLE_TITLE,
+            FactBoxKind::Theorem    => DEFAULT_THEOREM_TITLE,
+        }
+    }
+
+    // the css class added next to 'factbox', plain fact boxes don't get one
+    pub fn class_name(&self) -> Option<&'static str> {
+        match self {
+            FactBoxKind::Fact       => None,
+            FactBoxKind::Note       => Some("note"),
+            FactBoxKind::Warning    => Some("warning"),
+            FactBoxKind::Tip        => Some("tip"),
+            FactBoxKind::Definition => Some("definition"),
+            FactBoxKind::Example    => Some("example"),
+            FactBoxKind::Theorem    => Some("theorem"),
+        }
+    }
+
+    pub fn is_numbered(&self) -> bool {
+        matches!(self, FactBoxKind::Definition | FactBoxKind::Example | FactBoxKind::Theorem)
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct FactBox {
+    pub kind: FactBoxKind,
     pub title: String,
+    pub number: Option<String>,
     pub notes: OrderedMap::<String, (BlogBody, String)>,
     pub body: Vec<(BlogBody, String)>
 }
 
+impl FactBox {
+    // numbered callouts are titled "Definition 2.1", a custom title follows after a colon
+    pub fn display_title(&self) -> String {
+        let Some(number) = &self.number else { return self.title.clone() };
+        let kind_title = self.kind.default_title();
+        if self.title.trim() == kind_title {
+            format!("{kind_title} {number}")
+        } else {
+            format!("{kind_title} {number}: {}", self.title.trim())
+        }
+    }
+}
+
 fn gather_link<'l>(mut end: std::iter::Peekable<std::str::Chars<'l>>, depth: &mut i32) -> Result<(String, std::iter::Peekable<std::str::Chars<'l>>)> {
     let mut alt = String::new();
     if end.peek() == Some(&'(') {
@@ -158,7 +315,73 @@ fn get_citation(text: &String) -> Option<BlogBody> {
     }
 }
 
+// finds the closing delimiter of inline markup like ~~strike~~, returning the text in between
+// and the iterator positioned after the closing delimiter, or None if it is never closed.
+// a delimiter right after whitespace doesn't close, as in "~~a ~~ b~~"
+fn gather_delimited<'l>(mut end: std::iter::Peekable<std::str::Chars<'l>>, delimiter: &str) -> Option<(String, std::iter::Peekable<std::str::Chars<'l>>)> {
+    let mut result = String::new();
+    loop {
+        let after_whitespace = result.ends_with(char::is_whitespace);
+        if !result.is_empty() && !after_whitespace && end.clone().take(delimiter.chars().count()).eq(delimiter.chars()) {
+            for _ in delimiter.chars() { end.next(); }
+            break;
+        }
+        let character = end.next()?;
+        result.push(character);
+        if character == '\\' {
+            result.push(end.next()?);
+        }
+    }
+
+    if result.is_empty() { None } else { Some((result, end)) }
+}
+
+// finds the url of an autolink like <https://example.com>, returning it
+// and the iterator positioned after the closing '>'
+fn gather_autolink<'l>(mut end: std::iter::Peekable<std::str::Chars<'l>>) -> Option<(String, std::iter::Peekable<std::str::Chars<'l>>)> {
+    end.next();
+    let mut url = String::new();
+    while let Some(character) = end.next() {
+        if character == '>' { break }
+        if character.is_whitespace() { return None }
+        url.push(character);
+    }
+
+    if AUTOLINK_SCHEMES.iter().any(|scheme| url.starts_with(scheme) && url.len() > scheme.len()) {
+        Some((url, end))
+    } else {
+        None
+    }
+}
+
+// finds a bare url like https://example.com in text, trailing punctuation and
+// unbalanced parentheses are not considered part of the url
+fn gather_bare_url<'l>(mut end: std::iter::Peekable<std::str::Chars<'l>>) -> Option<(String, std::iter::Peekable<std::str::Chars<'l>>)> {
+    let text: String = end.clone().take_while(|character| !character.is_whitespace()).collect();
+    let scheme = AUTOLINK_SCHEMES[..2].iter().find(|scheme| text.starts_with(*scheme))?;
+
+    let mut url = text.as_str();
+    loop {
+        if url.ends_with(['.', ',', ';', ':', '!', '?', '"', '\'']) {
+            url = &url[..(url.len() - 1)];
+        } else if url.ends_with(')') && url.matches(')').count() > url.matches('(').count() {
+            url = &url[..(url.len() - 1)];
+        } else {
+            break;
+        }
+    }
+
+    if url.len() <= scheme.len() { return None }
+    for _ in url.chars() { end.next(); }
+    Some((url.to_string(), end))
+}
+
 pub fn text_parse(text: &String) -> Result<(Box<BlogBody>, String)> {
+    text_parse_with(text, true)
+}
+
+// bare urls are not turned into links inside of links, as that would nest them
+fn text_parse_with(text: &String, bare_urls: bool) -> Result<(Box<BlogBody>, String)> {
 
     let mut body = Vec::<BlogBody>::new();
     let mut buffer = String::new();
@@ -296,6 +519,15 @@ pub fn text_parse(text: &String) -> Result<(Box<BlogBody>, String)> {
                     continue;
                 }
                 
+                if base.starts_with('+') && base.trim_start().chars().nth(1).is_some_and(|x| x.is_alphabetic()) {
+                    // this is an index term, the id is given once the whole document is parsed
+                    let term : String = base.chars().skip(1).collect();
+                    body.push(BlogBody::IndexTerm(term.trim().into(), String::new()));
+                    end.next();
+                    peekable = end.clone();
+                    continue;
+                }
+
                 if base.starts_with('^') && base.len() > 1 {
                     // this is a citation
                     let citation : String = base.chars().skip(1).collect();
@@ -314,8 +546,8 @@ pub fn text_parse(text: &String) -> Result<(Box<BlogBody>, String)> {
                         Alternative { base, alt: Box::new(element) }
                     ))
                 } else {
-                    let (base, id) = text_parse(&base)?;
-                    let (alt, _) = text_parse(&alt)?;
+                    let (base, id) = text_parse_with(&base, false)?;
+                    let (alt, _) = text_parse_with(&alt, false)?;
                     tmp_id.push(' ');
                     tmp_id += id.as_str();
                     tmp_id.push(' ');
@@ -346,6 +578,50 @@ pub fn text_parse(text: &String) -> Result<(Box<BlogBody>, String)> {
                 body.push(BlogBody::InlineCode(base));
                 continue;
             },
+            '~' | '^' | '=' | '+' => {
+                let marker = *character;
+                let mut start = peekable.clone();
+                start.next();
+                let double = start.peek() == Some(&marker);
+                if double { start.next(); }
+
+                // ~~strike~~, ~sub~, ^sup^, ==highlight== and ++underline++
+                // single markers (sub and superscript) can't contain whitespace
+                let delimiter = if double { format!("{marker}{marker}") } else { marker.to_string() };
+                // the opening delimiter has to be followed by text, and the double ones can't start
+                // in the middle of a word, so "C++ and C++" and "a == b" stay as they are.
+                // H~2~O and x^2^ are in the middle of words on purpose
+                let followed_by_text = start.peek().is_some_and(|next| !next.is_whitespace());
+                let mid_word = double && buffer.chars().last().is_some_and(char::is_alphanumeric);
+                let is_markup = followed_by_text && !mid_word && match marker {
+                    '~' => true,
+                    '^' => !double,
+                    _   => double,
+                };
+                let gathered = if is_markup { gather_delimited(start, &delimiter) } else { None };
+
+                if let Some((inner, end)) = gathered && (double || !inner.contains(char::is_whitespace)) {
+                    if buffer.len() != 0 {
+                        body.push(BlogBody::Text(buffer));
+                        buffer = String::new();
+                    }
+
+                    let (text, id) = text_parse(&inner)?;
+                    tmp_id += id.as_str();
+                    body.push(match (marker, double) {
+                        ('~', true)  => BlogBody::Strikethrough(text),
+                        ('~', false) => BlogBody::Subscript(text),
+                        ('^', _)     => BlogBody::Superscript(text),
+                        ('=', _)     => BlogBody::Highlight(text),
+                        _            => BlogBody::Underline(text),
+                    });
+                    peekable = end;
+                    continue;
+                }
+
+                buffer.push(marker); 
+                tmp_id.push(marker); 
+            },
             '*' => {
                 if buffer.len() != 0 {
                     body.push(BlogBody::Text(buffer));
@@ -392,9 +668,39 @@ pub fn text_parse(text: &String) -> Result<(Box<BlogBody>, String)> {
                 continue;
                 
             },
+            '<' => {
+                if let Some((url, end)) = gather_autolink(peekable.clone()) {
+                    if buffer.len() != 0 {
+                        body.push(BlogBody::Text(buffer));
+                        buffer = String::new();
+                    }
+                    body.push(BlogBody::Link(Alternative{
+                        base: Box::new(BlogBody::Text(url.clone())),
+                        alt:  Box::new(BlogBody::Text(url)),
+                    }));
+                    peekable = end;
+                    continue;
+                }
+                buffer.push('<'); 
+                tmp_id.push('<'); 
+            },
             _   => {
-                buffer.push(*character); 
-                tmp_id.push(*character); 
+                let character = *character;
+                let at_word_start = buffer.chars().last().is_none_or(|last| last.is_whitespace() || last == '(');
+                if bare_urls && character == 'h' && at_word_start && let Some((url, end)) = gather_bare_url(peekable.clone()) {
+                    if buffer.len() != 0 {
+                        body.push(BlogBody::Text(buffer));
+                        buffer = String::new();
+                    }
+                    body.push(BlogBody::Link(Alternative{
+                        base: Box::new(BlogBody::Text(url.clone())),
+                        alt:  Box::new(BlogBody::Text(url)),
+                    }));
+                    peekable = end;
+                    continue;
+                }
+                buffer.push(character); 
+                tmp_id.push(character); 
             },
         }
 
@@ -432,7 +738,7 @@ fn generate_id(text: &String) -> Option<String> {
     }
 }
 
-fn is_valid_id(text: &String) -> bool {
+pub fn is_valid_id(text: &String) -> bool {
     !text.split_whitespace().collect::<String>().is_empty()
 }
 
@@ -564,9 +870,504 @@ fn get_contacts_title(data: &Frontmatter) -> Option<String> {
     }
 }
 
-fn parse_factbox(toplevel_syntax: &Vec<TopLevelSyntax>) -> Result<PawsMarkdown> {
+fn get_glossary_title(data: &Frontmatter) -> Option<String> {
+    if let Some(title) = data["glossary-title"].as_string() {
+        Some(title)
+    } else if let Some(title) = data["glossary_title"].as_string() {
+        Some(title)
+    } else if let Some(title) = data["glossary title"].as_string() {
+        Some(title)
+    } else {
+        None
+    }
+}
+
+fn get_abbreviations_title(data: &Frontmatter) -> Option<String> {
+    if let Some(title) = data["abbreviations-title"].as_string() {
+        Some(title)
+    } else if let Some(title) = data["abbreviations_title"].as_string() {
+        Some(title)
+    } else if let Some(title) = data["abbreviations title"].as_string() {
+        Some(title)
+    } else {
+        None
+    }
+}
+
+fn get_number_sections(data: &Frontmatter) -> Option<usize> {
+    let value = if let Some(value) = data["number-sections"].as_string() {
+        value
+    } else if let Some(value) = data["number_sections"].as_string() {
+        value
+    } else if let Some(value) = data["number sections"].as_string() {
+        value
+    } else {
+        return None;
+    };
+
+    match value.trim() {
+        "true" | "yes" => Some(MAX_SECTION_DEPTH),
+        "false" | "no" => None,
+        depth => {
+            if let Ok(depth) = depth.parse::<usize>() {
+                Some(depth.min(MAX_SECTION_DEPTH))
+            } else {
+                #[cfg(not(feature = "wasm"))]
+                cprintln!("<y>warning:</> '{}' is not a valid value for number-sections, expected true, false or a depth", depth);
+                None
+            }
+        }
+    }
+}
+
+// 1 -> A, 26 -> Z, 27 -> AA
+fn appendix_letter(mut n: usize) -> String {
+    if n == 0 { return n.to_string() }
+    let mut result = String::new();
+    while n > 0 {
+        n -= 1;
+        result.insert(0, (b'A' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    result
+}
+
+fn remove_unnumbered_marker(text: &String) -> (String, bool) {
+    let trimmed = text.trim_end();
+    for marker in UNNUMBERED_MARKERS {
+        if let Some(stripped) = trimmed.strip_suffix(marker) {
+            return (stripped.trim_end().to_string(), true);
+        }
+    }
+    (text.clone(), false)
+}
+
+// an explicit id is written as a trailing {#id}, e.g. "# Method {#method}"
+fn remove_explicit_id(text: &String) -> (String, Option<String>) {
+    let trimmed = text.trim_end();
+    if trimmed.ends_with('}') && let Some(start) = trimmed.rfind("{#") {
+        let id = &trimmed[(start + 2)..(trimmed.len() - 1)];
+        if !id.is_empty() && !id.contains(|c: char| c.is_whitespace() || c == '{' || c == '}') {
+            return (trimmed[..start].trim_end().to_string(), Some(id.to_string()));
+        }
+    }
+    (text.clone(), None)
+}
+
+// code blocks carry their id on the language line, e.g. "```rust {#listing}"
+fn remove_codeblock_id(block: &String) -> (String, Option<String>) {
+    let (first_line, rest) = block.split_once('\n').unwrap_or((block.as_str(), ""));
+    let (first_line, id) = remove_explicit_id(&first_line.to_string());
+    if id.is_some() {
+        (format!("{first_line}\n{rest}"), id)
+    } else {
+        (block.clone(), None)
+    }
+}
+
+fn remove_header_attributes(text: &String) -> (String, bool, Option<String>) {
+    let (text, id) = remove_explicit_id(text);
+    let (text, unnumbered) = remove_unnumbered_marker(&text);
+    if id.is_some() {
+        (text, unnumbered, id)
+    } else {
+        let (text, id) = remove_explicit_id(&text);
+        (text, unnumbered, id)
+    }
+}
+
+// parses the body of a footnote defined over several indented lines, the ids of the
+// elements are prefixed by the note so they don't collide with the rest of the document
+fn parse_block_note(id: &String, syntax: &Vec<TopLevelSyntax>, source: &Option<String>, references: &mut OrderedMap<String, ReferenceDefinition>, contacts: &mut OrderedMap<String, ContactDefinition>) -> Result<BlogBody> {
+    // fact boxes aren't allowed inside of notes
+    let (note, _) = parse_factbox(syntax, MAX_FACTBOX_DEPTH, source)?;
+    for (key, def) in &note.references {
+        references.insert(key.clone(), def.clone());
+    }
+    for (key, def) in &note.contacts {
+        contacts.insert(key.clone(), def.clone());
+    }
+    if !note.notes.is_empty() {
+        #[cfg(not(feature = "wasm"))]
+        cprintln!("<r>error:</> {}note definitions inside of notes is not allowed", source_prefix(source));
+    }
+
+    Ok(BlogBody::Blocks(note.body.into_iter()
+        .map(|(element, child)| (element, format!("^{id}-{child}")))
+        .collect()))
+}
+
+fn number_callouts(factbox: &mut FactBox, prefix: &String, counters: &mut HashMap<FactBoxKind, usize>) {
+    if factbox.kind.is_numbered() {
+        let counter = counters.entry(factbox.kind).or_insert(0);
+        *counter += 1;
+        factbox.number = Some(format!("{prefix}{counter}"));
+    }
+
+    for (element, _) in &mut factbox.body {
+        if let BlogBody::FactBox(nested) = element {
+            number_callouts(nested, prefix, counters);
+        }
+    }
+}
+
+fn parse_definition_list(list: &Vec<(String, Vec<String>)>) -> Result<BlogBody> {
+    let mut result = Vec::<(BlogBody, Vec<BlogBody>)>::new();
+    for (term, definitions) in list {
+        let (term, _) = text_parse(term)?;
+        let mut parsed = Vec::<BlogBody>::new();
+        for definition in definitions {
+            let (object, _) = text_parse(definition)?;
+            parsed.push(Box::into_inner(object));
+        }
+        result.push((Box::into_inner(term), parsed));
+    }
+    Ok(BlogBody::DefinitionList(result))
+}
+
+// finds the first whole word occurrence of any of the words, returning where it is and which word it was
+fn find_first_word(text: &str, words: &Vec<String>) -> Option<(usize, usize, usize)> {
+    let mut found: Option<(usize, usize, usize)> = None;
+    for (n, word) in words.iter().enumerate() {
+        if word.is_empty() { continue }
+        for (start, _) in text.match_indices(word.as_str()) {
+            let end = start + word.len();
+            let before = text[..start].chars().last();
+            let after  = text[end..].chars().nth(0);
+            if before.is_some_and(|c| c.is_alphanumeric()) || after.is_some_and(|c| c.is_alphanumeric()) { continue }
+            if found.is_none_or(|(first, _, _)| start < first) {
+                found = Some((start, end, n));
+            }
+            break;
+        }
+    }
+    found
+}
+
+// calls rewrite on the running text of the element in document order, replacing the text
+// with whatever it returns. headers, links, code and the like are left alone
+fn rewrite_text(element: &mut BlogBody, rewrite: &mut dyn FnMut(&String) -> Option<BlogBody>) {
+    if let BlogBody::Text(text) = element {
+        if let Some(replacement) = rewrite(text) {
+            *element = replacement;
+        }
+        return;
+    }
+    for_each_child_mut(element, &mut |child, part| if matches!(part, Part::Inline | Part::Block) {
+        rewrite_text(child, rewrite);
+    });
+}
+
+// the first occurrence of every glossary term is shown with its definition on hover,
+// terms are removed from the list once they have been annotated. terms are case insensitive
+fn annotate_glossary_terms(element: &mut BlogBody, terms: &mut Vec<(String, BlogBody)>) {
+    rewrite_text(element, &mut |text| {
+        let lowercase = text.to_lowercase();
+        // lowercasing can change the length of some characters, in which case the text isn't searched
+        if terms.is_empty() || lowercase.len() != text.len() { return None }
+
+        let mut elements = Vec::<BlogBody>::new();
+        let mut position = 0;
+        loop {
+            let words = terms.iter().map(|(term, _)| term.to_lowercase()).collect();
+            let Some((start, end, n)) = find_first_word(&lowercase[position..], &words) else { break };
+            let (_, definition) = terms.remove(n);
+            if start != 0 {
+                elements.push(BlogBody::Text(text[position..(position + start)].into()));
+            }
+            elements.push(BlogBody::Hoverable(Alternative{
+                base: Box::new(BlogBody::Text(text[(position + start)..(position + end)].into())),
+                alt:  Box::new(definition),
+            }));
+            position += end;
+        }
+
+        if elements.is_empty() { return None }
+        if position != text.len() {
+            elements.push(BlogBody::Text(text[position..].into()));
+        }
+        Some(BlogBody::Span(Span{ elements }))
+    });
+}
+
+// the time a draft was built, shown in its footer
+pub fn draft_timestamp() -> String {
+    format!("{} {}", DRAFT_WATERMARK, chrono::Local::now().format(DRAFT_TIMESTAMP_FORMAT))
+}
+
+// the number of characters in the text, spaces included and line breaks left out, as counted for hand-ins. it's the body
+// only, so the toc, notes, bibliography and appendices aren't counted
+pub fn character_count(body: &Vec<(BlogBody, String)>) -> usize {
+    let mut count = 0;
+    for (element, _) in body {
+        visit_text(element, &mut |text| count += text.chars().filter(|c| *c != '\n').count());
+    }
+    count
+}
+
+// what an element is to the element it is inside of, so the walkers below agree on what is inside
+// of what and only have to decide which of it they care about
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Part {
+    // running text, as in a paragraph, an emphasis or a span
+    Inline,
+    // starts on its own, like a list item, a definition or a block of a note or fact box
+    Block,
+    // a header or the term of a definition list
+    Heading,
+    // the text of a link or the word a hover is on, it stands for something else
+    Label,
+    // what is shown on hover, like a glossary definition
+    Hover,
+    // where a link goes, a url, a citation or a note
+    Target,
+}
+
+// calls visit with every element directly inside of the element, in document order. the css of a
+// styled span and the title of a fact box aren't elements, so they aren't visited
+macro_rules! for_each_child {
+    ($name: ident $(, $mutability: tt)?) => {
+        pub fn $name(element: &$($mutability)? BlogBody, visit: &mut dyn FnMut(&$($mutability)? BlogBody, Part)) {
+            match element {
+                BlogBody::Header(text, _) => visit(text, Part::Heading),
+                BlogBody::Paragraph(text) |
+                BlogBody::Italics(text) | BlogBody::Bold(text) | BlogBody::Strikethrough(text) |
+                BlogBody::Superscript(text) | BlogBody::Subscript(text) | BlogBody::Highlight(text) |
+                BlogBody::Underline(text) => visit(text, Part::Inline),
+                BlogBody::Link(link) => {
+                    visit(&$($mutability)? link.base, Part::Label);
+                    visit(&$($mutability)? link.alt, Part::Target);
+                },
+                BlogBody::Hoverable(hoverable) => {
+                    visit(&$($mutability)? hoverable.base, Part::Label);
+                    visit(&$($mutability)? hoverable.alt, Part::Hover);
+                },
+                BlogBody::Styled(styled) => visit(&$($mutability)? styled.alt, Part::Inline),
+                BlogBody::Span(span) => {
+                    for element in &$($mutability)? span.elements {
+                        visit(element, Part::Inline);
+                    }
+                },
+                BlogBody::Quote(list) | BlogBody::List(list) => {
+                    for element in list {
+                        visit(element, Part::Block);
+                    }
+                },
+                BlogBody::DefinitionList(list) => {
+                    for (term, definitions) in list {
+                        visit(term, Part::Heading);
+                        for element in definitions {
+                            visit(element, Part::Block);
+                        }
+                    }
+                },
+                BlogBody::Blocks(blocks) => {
+                    for (element, _) in blocks {
+                        visit(element, Part::Block);
+                    }
+                },
+                BlogBody::FactBox(factbox) => {
+                    for (element, _) in &$($mutability)? factbox.body {
+                        visit(element, Part::Block);
+                    }
+                    for (_, (element, _)) in &$($mutability)? factbox.notes {
+                        visit(element, Part::Block);
+                    }
+                },
+                _ => {}
+            }
+        }
+    };
+}
+for_each_child!(for_each_child);
+for_each_child!(for_each_child_mut, mut);
+
+// calls visit on every piece of text the reader sees, including headers, link texts and fact box titles
+fn for_each_text(element: &mut BlogBody, visit: &mut dyn FnMut(&mut String)) {
+    match element {
+        BlogBody::Text(text) => visit(text),
+        BlogBody::FactBox(factbox) => visit(&mut factbox.title),
+        _ => {}
+    }
+    for_each_child_mut(element, &mut |child, part| if part != Part::Target {
+        for_each_text(child, visit);
+    });
+}
+
+// the same as for_each_text, for when the text is only read
+fn visit_text(element: &BlogBody, visit: &mut dyn FnMut(&String)) {
+    match element {
+        BlogBody::Text(text) => visit(text),
+        BlogBody::FactBox(factbox) => visit(&factbox.title),
+        _ => {}
+    }
+    for_each_child(element, &mut |child, part| if part != Part::Target {
+        visit_text(child, visit);
+    });
+}
+
+// calls visit with the term and id of every index term in the element
+fn visit_index_terms(element: &mut BlogBody, visit: &mut dyn FnMut(&String, &mut String)) {
+    if let BlogBody::IndexTerm(term, id) = element {
+        visit(term, id);
+        return;
+    }
+    for_each_child_mut(element, &mut |child, part| if part != Part::Target {
+        visit_index_terms(child, visit);
+    });
+}
+
+// calls visit with the key of every citation, contact citation and note in the element
+fn visit_citations(element: &BlogBody, visit: &mut dyn FnMut(&String)) {
+    match element {
+        BlogBody::Citation(key) | BlogBody::ContactCitation(key) | BlogBody::Note(key) => visit(key),
+        _ => for_each_child(element, &mut |child, _| visit_citations(child, visit)),
+    }
+}
+
+// remembers the included file a citation, contact or note is first used in, so warnings about it can name the file
+fn record_sources(element: &BlogBody, source: &Option<String>, sources: &mut HashMap<String, String>) {
+    let Some(source) = source else { return };
+    visit_citations(element, &mut |key| {
+        sources.entry(key.clone()).or_insert(source.clone());
+    });
+}
+
+// abbreviations apply to the whole document, even when they are defined inside of a fact box or note
+fn collect_abbreviations(element: &TopLevelSyntax, source: &Option<String>, abbreviations: &mut Vec<(String, String)>) {
+    match element {
+        TopLevelSyntax::AbbreviationDefinition{ abbreviation, expansion } => {
+            if abbreviations.iter().any(|(existing, _)| existing == abbreviation) {
+                #[cfg(not(feature = "wasm"))]
+                cprintln!("<y>warning:</> {}abbreviation '{}' is defined more than once, the first definition is used", source_prefix(source), abbreviation);
+                return;
+            }
+            abbreviations.push((abbreviation.clone(), expansion.clone()));
+        },
+        TopLevelSyntax::FactBox{ body, .. } | TopLevelSyntax::BlockNoteDefinition{ id: _, body } => {
+            for element in body {
+                collect_abbreviations(element, source, abbreviations);
+            }
+        },
+        _ => {}
+    }
+}
+
+// abbreviations are spelled out the first time they are used, as in "Syddansk Universitet (SDU)",
+// and marked as abbreviations after that
+fn expand_abbreviations(element: &mut BlogBody, abbreviations: &Vec<(String, String)>, used: &mut HashSet<String>) {
+    let words = abbreviations.iter().map(|(abbreviation, _)| abbreviation.clone()).collect();
+    rewrite_text(element, &mut |text| {
+        let mut elements = Vec::<BlogBody>::new();
+        let mut position = 0;
+        while let Some((start, end, n)) = find_first_word(&text[position..], &words) {
+            let (abbreviation, expansion) = &abbreviations[n];
+            let mut before = String::from(&text[position..(position + start)]);
+            if used.insert(abbreviation.clone()) {
+                before += format!("{expansion} ({abbreviation})").as_str();
+                if let Some(BlogBody::Text(last)) = elements.last_mut() {
+                    *last += before.as_str();
+                } else {
+                    elements.push(BlogBody::Text(before));
+                }
+            } else {
+                if !before.is_empty() {
+                    elements.push(BlogBody::Text(before));
+                }
+                elements.push(BlogBody::Abbreviation(abbreviation.clone(), expansion.clone()));
+            }
+            position += end;
+        }
+
+        if elements.is_empty() { return None }
+        if position != text.len() {
+            if let Some(BlogBody::Text(last)) = elements.last_mut() {
+                *last += &text[position..];
+            } else {
+                elements.push(BlogBody::Text(text[position..].into()));
+            }
+        }
+        if elements.len() == 1 {
+            elements.pop()
+        } else {
+            Some(BlogBody::Span(Span{ elements }))
+        }
+    });
+}
+
+// parses a fact box and everything nested inside of it, returns the fact box, its id (if it has
+// one), whether that id was explicit and the paths of the elements inside with explicit ids
+fn build_factbox(kind: FactBoxKind, title: &String, syntax: &Vec<TopLevelSyntax>, depth: usize, source: &Option<String>,
+    references: &mut OrderedMap<String, ReferenceDefinition>, contacts: &mut OrderedMap<String, ContactDefinition>,
+    glossary: &mut Vec<(String, BlogBody, String)>) -> Result<(FactBox, Option<String>, bool, HashSet<Vec<usize>>)> 
+{
+    let (factbox_parsed, explicit_children) = parse_factbox(syntax, depth, source)?;
+    let (title, explicit) = remove_explicit_id(title);
+    let mut factbox = FactBox {
+        kind,
+        title: title.clone(),
+        number: None,
+        notes: OrderedMap::new(),
+        body: factbox_parsed.body,
+    };
+
+    for (key, def) in &factbox_parsed.references {
+        references.insert(key.clone(), def.clone());
+    }
+
+    for (key, def) in &factbox_parsed.contacts {
+        contacts.insert(key.clone(), def.clone());
+    }
+
+    glossary.extend(factbox_parsed.glossary);
+
+    // the note ids are scoped by scope_factbox_ids, once the id of the fact box is known
+    for (key, elem) in &factbox_parsed.notes {
+        factbox.notes.insert(key.clone(), (elem.clone(), key.clone()))
+    }
+
+    let explicit_id = explicit.is_some();
+    let id = explicit.or(generate_id(&title));
+    Ok((factbox, id, explicit_id, explicit_children))
+}
+
+// prefixes the ids of everything inside of a fact box (and the fact boxes nested in it) with
+// the id of the fact box, explicit ids are kept as is but have to be unique
+fn scope_factbox_ids(factbox: &mut FactBox, id: &String, path: &Vec<usize>, explicit_children: &HashSet<Vec<usize>>,
+    explicit_ids: &mut HashSet<String>, ids: &mut HashSet<String>, suffix: usize) -> Result<()> 
+{
+    for (_, (_, note_id)) in &mut factbox.notes {
+        *note_id = format!("{id}-{note_id}");
+    }
+
+    for (i, (element, object_id)) in factbox.body.iter_mut().enumerate() {
+        let mut path = path.clone();
+        path.push(i);
+        if explicit_children.contains(&path) {
+            if explicit_ids.contains(object_id) {
+                return Err(anyhow!("the id '{}' is used more than once, explicit ids have to be unique", object_id));
+            }
+            explicit_ids.insert(object_id.clone());
+            ids.insert(object_id.clone());
+        } else if is_valid_id(object_id) {
+            *object_id = format!("{id}-{object_id}");
+            while ids.contains(object_id) {
+                *object_id += format!("-{suffix}").as_str();
+            }
+            ids.insert(object_id.clone());
+        }
+
+        if let BlogBody::FactBox(nested) = element {
+            scope_factbox_ids(nested, object_id, &path, explicit_children, explicit_ids, ids, suffix)?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_factbox(toplevel_syntax: &Vec<TopLevelSyntax>, depth: usize, source: &Option<String>) -> Result<(PawsMarkdown, HashSet<Vec<usize>>)> {
     let mut contacts = OrderedMap::<String, ContactDefinition>::new();
     let mut notes      = OrderedMap::<String, BlogBody>::new();
+    let mut glossary   = Vec::<(String, BlogBody, String)>::new();
     let mut references = OrderedMap::<String, ReferenceDefinition>::new();
     let header: BlogHeader = BlogHeader::default();
     let mut body = Vec::<(BlogBody, String)>::new();
@@ -575,45 +1376,65 @@ fn parse_factbox(toplevel_syntax: &Vec<TopLevelSyntax>) -> Result<PawsMarkdown>
     let mut num_codeblocks = 0usize;
     let mut num_image = 0usize;
     let mut num_lists = 0usize;
+    let mut num_definition_lists = 0usize;
     let mut num_quotes = 0usize;
+    let mut num_factboxes = 0usize;
 
+    let mut explicit_ids = HashSet::<Vec<usize>>::new();
     for elem in toplevel_syntax {
         let last_length = body.len();
+        let mut explicit_id = false;
         match elem {
-            TopLevelSyntax::FactBox{title: _, body: _} => {
-                #[cfg(not(feature = "wasm"))]
-                cprintln!("<r>error:</> fact boxes inside of fact boxes is not allowed");
+            TopLevelSyntax::FactBox{ kind, title, body: syntax } => {
+                if depth >= MAX_FACTBOX_DEPTH {
+                    #[cfg(not(feature = "wasm"))]
+                    cprintln!("<r>error:</> {}fact boxes can't be nested more than {} levels deep", source_prefix(source), MAX_FACTBOX_DEPTH);
+                } else {
+                    let (factbox, id, explicit, explicit_children) = build_factbox(*kind, title, syntax, depth + 1, source, &mut references, &mut contacts, &mut glossary)?;
+                    explicit_id = explicit;
+                    for path in explicit_children {
+                        explicit_ids.insert([vec![last_length], path].concat());
+                    }
+                    body.push((BlogBody::FactBox(factbox), id.unwrap_or(format!("factbox-{num_factboxes}"))));
+                    num_factboxes = num_factboxes + 1;
+                }
             },
             TopLevelSyntax::FrontMatter(_) => { // (data)   => {
                 #[cfg(not(feature = "wasm"))]
-                cprintln!("<r>error:</> frontmatter inside of fact boxes is not allowed");
+                cprintln!("<r>error:</> {}frontmatter inside of fact boxes is not allowed", source_prefix(source));
             }
             TopLevelSyntax::PageBreak => {
                 body.push((BlogBody::PageBreak, String::new()));
             },
             TopLevelSyntax::CodeBlock(block) => { 
+                let (block, id) = remove_codeblock_id(block);
+                explicit_id = id.is_some();
                 body.push((
-                        BlogBody::CodeBlock(block.to_string()),
-                        if num_codeblocks == 0 {
+                        BlogBody::CodeBlock(block),
+                        id.unwrap_or(if num_codeblocks == 0 {
                             format!("codeblock")
                         } else {
                             format!("codeblock-{num_codeblocks}")
-                        }
+                        })
                 ));
                 num_codeblocks = num_codeblocks + 1;
             },
             TopLevelSyntax::Image(img, alt) => {
-                let id = generate_id(alt);
+                let (alt, explicit) = remove_explicit_id(alt);
+                explicit_id = explicit.is_some();
+                let id = explicit.or(generate_id(&alt));
                 body.push((
-                        BlogBody::Image(img.to_string(), alt.to_string()),
+                        BlogBody::Image(img.to_string(), alt),
                         id.unwrap_or(format!("image-{num_image}"))
                 ));
                 num_image = num_image + 1;
             },
             // TopLevelSyntax::EmbeddedLink(img, alt) => { body.push(BlogBody::EmbeddedLink(img.to_string(), alt.to_string())); },
             TopLevelSyntax::Header(text, level) => { 
+                let (text, _, explicit) = remove_header_attributes(text);
+                explicit_id = explicit.is_some();
                 let (object, id) = text_parse(&text)?;
-                body.push((BlogBody::Header(object, *level), id));
+                body.push((BlogBody::Header(object, *level), explicit.unwrap_or(id)));
             },
             TopLevelSyntax::List(list) => {
                 let mut result = Vec::<BlogBody>::new();
@@ -624,17 +1445,33 @@ fn parse_factbox(toplevel_syntax: &Vec<TopLevelSyntax>) -> Result<PawsMarkdown>
                 body.push((BlogBody::List(result), format!("list-{num_lists}")));
                 num_lists = num_lists + 1;
             },
+            TopLevelSyntax::DefinitionList(list) => {
+                body.push((parse_definition_list(list)?, format!("definitions-{num_definition_lists}")));
+                num_definition_lists = num_definition_lists + 1;
+            },
+            TopLevelSyntax::GlossaryEntry{ term, definition } => {
+                let (object, _) = text_parse(definition)?;
+                glossary.push((term.clone(), Box::into_inner(object), String::new()));
+            },
             TopLevelSyntax::Paragraph(text) => {
                 let (object, id) = text_parse(&text)?;
                 body.push((BlogBody::Paragraph(object), id));
             },
             TopLevelSyntax::Quote(list) => { 
+                let mut list = list.clone();
+                let mut id = None;
+                if let Some(last) = list.last_mut() {
+                    (*last, id) = remove_explicit_id(last);
+                    if last.is_empty() { list.pop(); }
+                }
+                explicit_id = id.is_some();
+
                 let mut result = Vec::<BlogBody>::new();
-                for elem in list {
+                for elem in &list {
                     let (object, _) = text_parse(&elem)?;
                     result.push(Box::into_inner(object));
                 }
-                body.push((BlogBody::Quote(result), format!("quote-{num_quotes}")));
+                body.push((BlogBody::Quote(result), id.unwrap_or(format!("quote-{num_quotes}"))));
                 num_quotes = num_quotes + 1;
             },
             TopLevelSyntax::ContactDefinition(contact) => {
@@ -647,15 +1484,40 @@ fn parse_factbox(toplevel_syntax: &Vec<TopLevelSyntax>) -> Result<PawsMarkdown>
                 let (object, _) = text_parse(&text)?;
                 notes.insert(id.clone(), Box::into_inner(object));
             }
+            TopLevelSyntax::BlockNoteDefinition { id, body } => {
+                let note = parse_block_note(id, body, source, &mut references, &mut contacts)?;
+                notes.insert(id.clone(), note);
+            }
+            TopLevelSyntax::LinkDefinition { .. } => {},
+            TopLevelSyntax::AbbreviationDefinition { .. } => {},
             TopLevelSyntax::TOC(_) => {
                 #[cfg(not(feature = "wasm"))]
-                cprintln!("<r>error:</> table of contents inside of fact boxes is not allowed");
+                cprintln!("<r>error:</> {}table of contents inside of fact boxes is not allowed", source_prefix(source));
+            },
+            TopLevelSyntax::Index(_) => {
+                #[cfg(not(feature = "wasm"))]
+                cprintln!("<r>error:</> {}an index inside of fact boxes is not allowed", source_prefix(source));
+            },
+            TopLevelSyntax::Appendix(_) => {
+                #[cfg(not(feature = "wasm"))]
+                cprintln!("<r>error:</> {}appendices inside of fact boxes is not allowed", source_prefix(source));
+            },
+            TopLevelSyntax::Include(path) => {
+                #[cfg(not(feature = "wasm"))]
+                cprintln!("<y>warning:</> {}include of '{}' was never resolved", source_prefix(source), path);
+            },
+            TopLevelSyntax::Chapter(_) => {
+                #[cfg(not(feature = "wasm"))]
+                cprintln!("<r>error:</> {}chapters inside of fact boxes is not allowed", source_prefix(source));
             },
         };
 
         if body.len() != last_length {
             if let Some((_, id)) = body.last_mut() {
-                if is_valid_id(id) {
+                if explicit_id {
+                    // explicit ids are checked for duplicates once the fact box is merged into the document
+                    explicit_ids.insert(vec![last_length]);
+                } else if is_valid_id(id) {
                     while ids.contains(id) {
                         *id += format!("-{last_length}").as_str();
                     }
@@ -665,7 +1527,7 @@ fn parse_factbox(toplevel_syntax: &Vec<TopLevelSyntax>) -> Result<PawsMarkdown>
         }
     }
     
-    Ok(PawsMarkdown {
+    Ok((PawsMarkdown {
         header, 
         references, 
         contacts, 
@@ -674,7 +1536,44 @@ fn parse_factbox(toplevel_syntax: &Vec<TopLevelSyntax>) -> Result<PawsMarkdown>
         notes_id: String::new(), 
         bibliography_id: String::new(),
         contacts_id: String::new(),
-    })
+        appendix_id: String::new(),
+        glossary_id: String::new(),
+        abbreviations_id: String::new(),
+        appendix: vec![],
+        glossary,
+        abbreviations: vec![],
+        chapters: vec![],
+    }, explicit_ids))
+}
+
+// the sections added at the end (notes, bibliography, glossary, ...) keep their id, an element in
+// the document that already uses it is renamed unless the id was given explicitly
+fn unique_id(body: &mut Vec<(BlogBody, String)>, ids: &HashSet<String>, explicit_ids: &HashSet<String>, 
+    section_id: &String, section: &str) -> Result<()> 
+{
+    if explicit_ids.contains(section_id) {
+        return Err(anyhow!("the id '{}' is also used by the {}, explicit ids have to be unique", section_id, section));
+    }
+    if !ids.contains(section_id) { return Ok(()) }
+    'outer: for (elem, id) in body.iter_mut() {
+        if let BlogBody::FactBox(factbox) = elem {
+            for (_, factbox_id) in &mut factbox.body {
+                if factbox_id != section_id { continue }
+
+                while ids.contains(factbox_id) {
+                    *factbox_id = format!("{factbox_id}-disass");
+                }
+                break 'outer;
+            }
+        }
+        if id != section_id { continue }
+        while ids.contains(id) {
+            *id = format!("{id}-disass");
+        }
+
+        break;
+    }
+    Ok(())
 }
 
 pub fn file_parse(file_path: &String) -> Result<PawsMarkdown> {
@@ -682,145 +1581,225 @@ pub fn file_parse(file_path: &String) -> Result<PawsMarkdown> {
 }
 
 pub fn parse(file_content: &String, file_path: Option<&String>) -> Result<PawsMarkdown> {
-    let toplevel_syntax = toplevel_parse(file_content)?; 
+    parse_syntax(resolve_includes(toplevel_parse(file_content)?, file_path)?, file_path)
+}
 
-    let mut contacts = OrderedMap::<String, ContactDefinition>::new();
-    let mut notes      = OrderedMap::<String, BlogBody>::new();
+// adds the included file to an error, so it is clear where the markdown that failed is
+fn in_source<T>(result: Result<T>, source: &Option<String>) -> Result<T> {
+    match source {
+        Some(source) => result.with_context(|| format!("in the included file '{source}'")),
+        None => result,
+    }
+}
+
+pub fn parse_syntax(toplevel_syntax: Vec<(TopLevelSyntax, /*source: */ Option<String>)>, file_path: Option<&String>) -> Result<PawsMarkdown> {
+    let mut contacts = OrderedMap::<String, ContactDefinition>::new();
+    let mut notes      = OrderedMap::<String, BlogBody>::new();
+    let mut glossary   = Vec::<(String, BlogBody, String)>::new();
     let mut references = OrderedMap::<String, ReferenceDefinition>::new();
     let mut header: BlogHeader = BlogHeader::default();
     let mut body = Vec::<(BlogBody, String)>::new();
+    if let Some(directory) = file_path.and_then(|path| Path::new(path).parent()) {
+        header.document_dir = directory.display().to_string();
+    }
 
     let mut ids = HashSet::<String>::new();
     let mut num_codeblocks = 0usize;
     let mut num_image = 0usize;
     let mut num_lists = 0usize;
+    let mut num_definition_lists = 0usize;
     let mut num_quotes = 0usize;
     let mut num_factboxes = 0usize;
-
-    for elem in &toplevel_syntax {
-        let last_length = body.len();
-        match elem {
-            TopLevelSyntax::FactBox{ title, body: syntax} => {
-                // Should return a Factbox object
-                let factbox_parsed = parse_factbox(syntax)?;
-                let mut factbox = FactBox {
-                    title: title.clone(),
-                    notes: OrderedMap::new(),
-                    body: factbox_parsed.body,
-                };
-
-                if !factbox_parsed.references.is_empty() {
-                    for (key, def) in &factbox_parsed.references {
-                        references.insert(key.clone(), def.clone());
-                    }
+    let mut unnumbered_headers = HashSet::<usize>::new();
+    let mut appendix_index: Option<usize> = None;
+    let mut chapters = Vec::<(String, usize)>::new();
+
+    let toplevel_syntax = expand_link_references(toplevel_syntax);
+
+    let mut explicit_ids = HashSet::<String>::new();
+    let mut explicit_indices = HashSet::<usize>::new();
+    for (elem, source) in &toplevel_syntax {
+        let result = (|| -> Result<()> {
+            let last_length = body.len();
+            let mut explicit_id = false;
+            match elem {
+                TopLevelSyntax::FactBox{ kind, title, body: syntax} => {
+                    // Should return a Factbox object
+                    let (mut factbox, id, explicit, explicit_children) = build_factbox(*kind, title, syntax, 1, source, &mut references, &mut contacts, &mut glossary)?;
+                    explicit_id = explicit;
+
+                    let id = id.unwrap_or(format!("factbox-{num_factboxes}"));
+                    scope_factbox_ids(&mut factbox, &id, &vec![], &explicit_children, &mut explicit_ids, &mut ids, last_length)?;
+
+                    body.push((
+                            BlogBody::FactBox(factbox), 
+                            id
+                    ));
+                    num_factboxes = num_factboxes + 1;
+                },
+                TopLevelSyntax::PageBreak => {
+                    body.push((BlogBody::PageBreak, String::new()));
+                },
+                TopLevelSyntax::FrontMatter(frontmatter) => {
+                    header.frontmatter = Some(frontmatter.clone());
                 }
-                
-                if !factbox_parsed.contacts.is_empty() {
-                    for (key, def) in &factbox_parsed.contacts {
-                        contacts.insert(key.clone(), def.clone());
+                TopLevelSyntax::CodeBlock(block) => {
+                    let (block, id) = remove_codeblock_id(block);
+                    explicit_id = id.is_some();
+                    body.push((
+                            BlogBody::CodeBlock(block),
+                            id.unwrap_or(if num_codeblocks == 0 {
+                                format!("codeblock")
+                            } else {
+                                format!("codeblock-{num_codeblocks}")
+                            })
+                    ));
+                    num_codeblocks = num_codeblocks + 1;
+                },
+                TopLevelSyntax::Image(img, alt) => {
+                    let (alt, explicit) = remove_explicit_id(alt);
+                    explicit_id = explicit.is_some();
+                    let id = explicit.or(generate_id(&alt));
+                    body.push((
+                            BlogBody::Image(img.to_string(), alt),
+                            id.unwrap_or(format!("image-{num_image}"))
+                    ));
+                    num_image = num_image + 1;
+                },
+                // TopLevelSyntax::EmbeddedLink(img, alt) => { body.push(BlogBody::EmbeddedLink(img.to_string(), alt.to_string())); },
+                TopLevelSyntax::Header(text, level) => {
+                    let (text, unnumbered, explicit) = remove_header_attributes(text);
+                    if unnumbered {
+                        unnumbered_headers.insert(body.len());
                     }
-                }
+                    explicit_id = explicit.is_some();
+                    let (object, id) = text_parse(&text)?;
+                    body.push((BlogBody::Header(object, *level), explicit.unwrap_or(id)));
+                },
+                TopLevelSyntax::List(list) => {
+                    let mut result = Vec::<BlogBody>::new();
+                    for elem in list {
+                        let (object, _) = text_parse(&elem)?;
+                        result.push(Box::into_inner(object))
+                    }
+                    body.push((BlogBody::List(result), format!("list-{num_lists}")));
+                    num_lists = num_lists + 1;
+                },
+                TopLevelSyntax::DefinitionList(list) => {
+                    body.push((parse_definition_list(list)?, format!("definitions-{num_definition_lists}")));
+                    num_definition_lists = num_definition_lists + 1;
+                },
+                TopLevelSyntax::GlossaryEntry{ term, definition } => {
+                    let (object, _) = text_parse(definition)?;
+                    glossary.push((term.clone(), Box::into_inner(object), String::new()));
+                },
+                TopLevelSyntax::Paragraph(text) => {
+                    let (object, id) = text_parse(&text)?;
+                    body.push((BlogBody::Paragraph(object), id));
+                },
+                TopLevelSyntax::Quote(list) => { 
+                    let mut list = list.clone();
+                    let mut id = None;
+                    if let Some(last) = list.last_mut() {
+                        (*last, id) = remove_explicit_id(last);
+                        if last.is_empty() { list.pop(); }
+                    }
+                    explicit_id = id.is_some();
 
-                let id = if let Some(id) = generate_id(title) { id } else { format!("factbox-{num_factboxes}") };
-                for (_, object_id) in &mut factbox.body {
-                    if is_valid_id(object_id) {
-                        *object_id = format!("{id}-{object_id}");
-                        while ids.contains(object_id) {
-                            *object_id += format!("-{last_length}").as_str();
-                        }
-                        ids.insert(id.clone());
+                    let mut result = Vec::<BlogBody>::new();
+                    for elem in &list {
+                        let (object, _) = text_parse(&elem)?;
+                        result.push(Box::into_inner(object));
                     }
+                    body.push((BlogBody::Quote(result), id.unwrap_or(format!("quote-{num_quotes}"))));
+                    num_quotes = num_quotes + 1;
+                },
+                TopLevelSyntax::ContactDefinition(contact) => { contacts.insert(contact.id.clone(), contact.clone()); },
+                TopLevelSyntax::ReferenceDefinition(reference) => {
+                    references.insert(reference.id.clone(), reference.clone());
+                },
+                TopLevelSyntax::NoteDefinition { id, text } => {
+                    let (object, _) = text_parse(&text)?;
+                    record_sources(&object, source, &mut header.sources);
+                    notes.insert(id.clone(), Box::into_inner(object));
                 }
-
-                for (key, elem) in &factbox_parsed.notes {
-                    factbox.notes.insert(key.clone(), (elem.clone(), format!("{id}-{key}")))
+                TopLevelSyntax::BlockNoteDefinition { id, body } => {
+                    let note = parse_block_note(id, body, source, &mut references, &mut contacts)?;
+                    record_sources(&note, source, &mut header.sources);
+                    notes.insert(id.clone(), note);
                 }
-
-                body.push((
-                        BlogBody::FactBox(factbox), 
-                        id
-                ));
-                num_factboxes = num_factboxes + 1;
-            },
-            TopLevelSyntax::PageBreak => {
-                body.push((BlogBody::PageBreak, String::new()));
-            },
-            TopLevelSyntax::FrontMatter(frontmatter) => {
-                header.frontmatter = Some(frontmatter.clone());
-            }
-            TopLevelSyntax::CodeBlock(block) => {
-                body.push((
-                        BlogBody::CodeBlock(block.to_string()),
-                        if num_codeblocks == 0 {
-                            format!("codeblock")
-                        } else {
-                            format!("codeblock-{num_codeblocks}")
+                TopLevelSyntax::LinkDefinition { .. } => {},
+                TopLevelSyntax::AbbreviationDefinition { .. } => {},
+ 
+                TopLevelSyntax::TOC(title) => {
+                    if header.toc.is_none() {
+                        header.toc = Some(TableOfContent{ title: title.clone(), index: body.len(), headers: vec![], appendix: vec![], max_depth: 1,});
+                        body.push((BlogBody::TOCLocationMarker, String::new()));
+                    }
+                },
+                TopLevelSyntax::Index(title) => {
+                    if header.index.is_none() {
+                        let title = if title.is_empty() { DEFAULT_INDEX_TITLE.into() } else { title.clone() };
+                        header.index = Some(Index{ title, entries: vec![], sections: HashMap::new() });
+                        body.push((BlogBody::IndexLocationMarker, String::new()));
+                    } else {
+                        #[cfg(not(feature = "wasm"))]
+                        cprintln!("<y>warning:</> {}a document can only have one index, ignoring '{}'", source_prefix(source), title);
+                    }
+                },
+                TopLevelSyntax::Appendix(title) => {
+                    if appendix_index.is_none() {
+                        if !title.is_empty() {
+                            header.appendix_title = title.clone();
                         }
-                ));
-                num_codeblocks = num_codeblocks + 1;
-            },
-            TopLevelSyntax::Image(img, alt) => {
-                let id = generate_id(alt);
-                body.push((
-                        BlogBody::Image(img.to_string(), alt.to_string()),
-                        id.unwrap_or(format!("image-{num_image}"))
-                ));
-                num_image = num_image + 1;
-            },
-            // TopLevelSyntax::EmbeddedLink(img, alt) => { body.push(BlogBody::EmbeddedLink(img.to_string(), alt.to_string())); },
-            TopLevelSyntax::Header(text, level) => {
-                let (object, id) = text_parse(&text)?;
-                body.push((BlogBody::Header(object, *level), id));
-            },
-            TopLevelSyntax::List(list) => {
-                let mut result = Vec::<BlogBody>::new();
-                for elem in list {
-                    let (object, _) = text_parse(&elem)?;
-                    result.push(Box::into_inner(object))
-                }
-                body.push((BlogBody::List(result), format!("list-{num_lists}")));
-                num_lists = num_lists + 1;
-            },
-            TopLevelSyntax::Paragraph(text) => {
-                let (object, id) = text_parse(&text)?;
-                body.push((BlogBody::Paragraph(object), id));
-            },
-            TopLevelSyntax::Quote(list) => { 
-                let mut result = Vec::<BlogBody>::new();
-                for elem in list {
-                    let (object, _) = text_parse(&elem)?;
-                    result.push(Box::into_inner(object));
-                }
-                body.push((BlogBody::Quote(result), format!("quote-{num_quotes}")));
-                num_quotes = num_quotes + 1;
-            },
-            TopLevelSyntax::ContactDefinition(contact) => { contacts.insert(contact.id.clone(), contact.clone()); },
-            TopLevelSyntax::ReferenceDefinition(reference) => {
-                references.insert(reference.id.clone(), reference.clone());
-            },
-            TopLevelSyntax::NoteDefinition { id, text } => {
-                let (object, _) = text_parse(&text)?;
-                notes.insert(id.clone(), Box::into_inner(object));
+                        appendix_index = Some(body.len());
+                    } else {
+                        #[cfg(not(feature = "wasm"))]
+                        cprintln!("<y>warning:</> {}a document can only have one appendix marker, ignoring '{}'", source_prefix(source), title);
+                    }
+                },
+                TopLevelSyntax::Include(path) => {
+                    #[cfg(not(feature = "wasm"))]
+                    cprintln!("<y>warning:</> {}include of '{}' was never resolved", source_prefix(source), path);
+                },
+                TopLevelSyntax::Chapter(file) => {
+                    chapters.push((file.clone(), body.len()));
+                },
+            }        
+
+            for (element, _) in &body[last_length..] {
+                record_sources(element, source, &mut header.sources);
             }
- 
-            TopLevelSyntax::TOC(title) => {
-                if header.toc.is_none() {
-                    header.toc = Some(TableOfContent{ title: title.clone(), index: body.len(), headers: vec![], max_depth: 1,});
-                    body.push((BlogBody::TOCLocationMarker, String::new()));
-                }
-            },
-        }        
         
-        if body.len() != last_length {
-            if let Some((_, id)) = body.last_mut() {
-                if is_valid_id(id) {
-                    while ids.contains(id) {
-                        *id += format!("-{last_length}").as_str();
+            if body.len() != last_length {
+                if let Some((_, id)) = body.last_mut() {
+                    if explicit_id {
+                        if explicit_ids.contains(id) {
+                            return Err(anyhow!("the id '{}' is used more than once, explicit ids have to be unique", id));
+                        }
+                        explicit_ids.insert(id.clone());
+                        explicit_indices.insert(last_length);
+                        ids.insert(id.clone());
+                    } else if is_valid_id(id) {
+                        while ids.contains(id) {
+                            *id += format!("-{last_length}").as_str();
+                        }
+                        ids.insert(id.clone());
                     }
-                    ids.insert(id.clone());
                 }
             }
+            Ok(())
+        })();
+        in_source(result, source)?;
+    }
+
+    // generated ids give way to explicit ids that appear later in the document
+    for (i, (_, id)) in body.iter_mut().enumerate() {
+        if explicit_indices.contains(&i) || !explicit_ids.contains(id) { continue }
+        while ids.contains(id) {
+            *id += format!("-{i}").as_str();
         }
+        ids.insert(id.clone());
     }
 
     if let Some(frontmatter) = &header.frontmatter {
@@ -845,6 +1824,14 @@ pub fn parse(file_content: &String, file_path: Option<&String>) -> Result<PawsMa
             header.notes_title = title;
         }
 
+        if let Some(title) = get_glossary_title(frontmatter) {
+            header.glossary_title = title;
+        }
+
+        if let Some(title) = get_abbreviations_title(frontmatter) {
+            header.abbreviations_title = title;
+        }
+
         if let Some(title) = get_bibliography_title(frontmatter) {
             header.bibliography_title = title;
         }
@@ -880,6 +1867,17 @@ pub fn parse(file_content: &String, file_path: Option<&String>) -> Result<PawsMa
         header.hide_references = check_frontmatter(frontmatter, &FRONTMATTER_HIDE_REFERENCES);
         header.hide_contacts   = check_frontmatter(frontmatter, &FRONTMATTER_HIDE_CONTACTS);
         header.should_cite_contacts = check_frontmatter(frontmatter, &FRONTMATTER_SHOULD_CITE_CONTACTS);
+        header.number_callouts = check_frontmatter(frontmatter, &FRONTMATTER_NUMBER_CALLOUTS);
+        header.list_abbreviations = check_frontmatter(frontmatter, &FRONTMATTER_LIST_ABBREVIATIONS);
+        header.typography = get_typography(frontmatter);
+        header.emoji = !check_frontmatter(frontmatter, &FRONTMATTER_NO_EMOJI);
+        header.symbols = get_symbols(frontmatter);
+        // unlike the flags above, "draft: false" turns it off again
+        header.draft = frontmatter["draft"].as_bool().unwrap_or(false);
+
+        if let Some(depth) = get_number_sections(frontmatter) {
+            header.number_sections = depth;
+        }
     } else {
         //TODO(Paw): this should really be a warning
         if let Some(file_path) = file_path {
@@ -900,76 +1898,187 @@ pub fn parse(file_content: &String, file_path: Option<&String>) -> Result<PawsMa
         let default_id = generate_id(&DEFAULT_CONTACT_TITLE.to_string()).unwrap();
         default_id
     };
+    let appendix_id = if let Some(id) = generate_id(&header.appendix_title) { id } else {
+        let default_id = generate_id(&DEFAULT_APPENDIX_TITLE.to_string()).unwrap();
+        default_id
+    };
+    let glossary_id = if let Some(id) = generate_id(&header.glossary_title) { id } else {
+        let default_id = generate_id(&DEFAULT_GLOSSARY_TITLE.to_string()).unwrap();
+        default_id
+    };
+    let abbreviations_id = if let Some(id) = generate_id(&header.abbreviations_title) { id } else {
+        let default_id = generate_id(&DEFAULT_ABBREVIATIONS_TITLE.to_string()).unwrap();
+        default_id
+    };
 
     if !notes.is_empty() {
-        if ids.contains(&notes_id) {
-            'outer: for (elem, id) in &mut body.iter_mut() {
-                if let BlogBody::FactBox(factbox) = elem {
-                    for (_, factbox_id) in &mut factbox.body {
-                        if factbox_id != &notes_id { continue }
-        
-                        while ids.contains(factbox_id) {
-                            *factbox_id = format!("{factbox_id}-disass");
-                        }
-                        break 'outer;
-                    }
-                }
-                if id != &notes_id { continue }
-                while ids.contains(id) {
-                    *id = format!("{id}-disass");
-                }
-        
-                break;
-            }
-        }
+        unique_id(&mut body, &ids, &explicit_ids, &notes_id, "notes")?;
     }
     
     if !references.is_empty() {
-        if ids.contains(&bibliography_id) {
-            'outer: for (elem, id) in &mut body.iter_mut() {
-                if let BlogBody::FactBox(factbox) = elem {
-                    for (_, factbox_id) in &mut factbox.body {
-                        if factbox_id != &bibliography_id { continue }
-        
-                        while ids.contains(factbox_id) {
-                            *factbox_id = format!("{factbox_id}-disass");
-                        }
-                        break 'outer;
-                    }
-                }
-                if id != &bibliography_id { continue }
-                while ids.contains(id) {
-                    *id = format!("{id}-disass");
-                }
-        
-                break;
+        unique_id(&mut body, &ids, &explicit_ids, &bibliography_id, "bibliography")?;
+    }
+
+    if !contacts.is_empty() {
+        unique_id(&mut body, &ids, &explicit_ids, &contacts_id, "contacts")?;
+    }
+
+    if appendix_index.is_some() {
+        unique_id(&mut body, &ids, &explicit_ids, &appendix_id, "appendix")?;
+    }
+    
+    // symbols are replaced first, so abbreviations, glossary terms and the index see the same text as the reader
+    let mut substitute = |text: &mut String| *text = substitute_text(text, &header.symbols, header.emoji);
+    for (element, _) in &mut body {
+        for_each_text(element, &mut substitute);
+    }
+    for (_, element) in &mut notes {
+        for_each_text(element, &mut substitute);
+    }
+    for (_, definition, _) in &mut glossary {
+        for_each_text(definition, &mut substitute);
+    }
+
+    let mut abbreviations = Vec::<(String, String)>::new();
+    for (element, source) in &toplevel_syntax {
+        collect_abbreviations(element, source, &mut abbreviations);
+    }
+    if !abbreviations.is_empty() {
+        let mut used = HashSet::<String>::new();
+        for (element, _) in &mut body {
+            expand_abbreviations(element, &abbreviations, &mut used);
+        }
+        abbreviations.sort_by_key(|(abbreviation, _)| abbreviation.to_lowercase());
+
+        if header.list_abbreviations {
+            unique_id(&mut body, &ids, &explicit_ids, &abbreviations_id, "list of abbreviations")?;
+        }
+    }
+
+    if !glossary.is_empty() {
+        unique_id(&mut body, &ids, &explicit_ids, &glossary_id, "glossary")?;
+
+        glossary.sort_by_key(|(term, _, _)| term.to_lowercase());
+        for (term, _, id) in &mut glossary {
+            *id = format!("{glossary_id}-{}", generate_id(term).unwrap_or_default());
+        }
+
+        let mut terms: Vec<_> = glossary.iter().map(|(term, definition, _)| (term.clone(), definition.clone())).collect();
+        for (element, _) in &mut body {
+            annotate_glossary_terms(element, &mut terms);
+        }
+    }
+
+    // every index term points to the section it is in, terms inside of a header point to the header itself
+    let mut index_terms = Vec::<(String, String, String)>::new();
+    let mut sections = HashMap::<String, Box<BlogBody>>::new();
+    let mut section = String::new();
+    for (element, id) in &mut body {
+        let in_header = if let BlogBody::Header(text, _) = element {
+            section = id.clone();
+            sections.insert(id.clone(), text.clone());
+            true
+        } else { false };
+        visit_index_terms(element, &mut |term, marker| {
+            let location = if in_header { section.clone() } else {
+                *marker = format!("index-term-{}", index_terms.len());
+                marker.clone()
+            };
+            index_terms.push((term.clone(), location, section.clone()));
+        });
+    }
+
+    if let Some(index) = &mut header.index {
+        index_terms.sort_by_key(|(term, _, _)| term.to_lowercase());
+        for (term, marker, section) in index_terms {
+            match index.entries.last_mut() {
+                Some((last, locations)) if last.to_lowercase() == term.to_lowercase() => locations.push((marker, section)),
+                _ => index.entries.push((term, vec![(marker, section)])),
             }
         }
+        index.sections = sections;
+
+        if index.entries.is_empty() {
+            #[cfg(not(feature = "wasm"))]
+            cprintln!("<y>warning:</> the document has an index but no index terms, mark terms with [+term]");
+        }
+    } else if !index_terms.is_empty() {
+        #[cfg(not(feature = "wasm"))]
+        cprintln!("<y>warning:</> the document has index terms but no index, add one with #[index]");
     }
 
-    if !contacts.is_empty() {
-        if ids.contains(&contacts_id) {
-            'outer: for (elem, id) in &mut body.iter_mut() {
-                if let BlogBody::FactBox(factbox) = elem {
-                    for (_, factbox_id) in &mut factbox.body {
-                        if factbox_id != &contacts_id { continue }
-        
-                        while ids.contains(factbox_id) {
-                            *factbox_id = format!("{factbox_id}-disass");
-                        }
-                        break 'outer;
+    if let Some(typography) = header.typography {
+        let mut state = TypesetState::new(typography);
+        for (element, _) in &mut body {
+            state.reset();
+            typeset(element, &mut state);
+        }
+        for (_, element) in &mut notes {
+            state.reset();
+            typeset(element, &mut state);
+        }
+        for (_, definition, _) in &mut glossary {
+            state.reset();
+            typeset(definition, &mut state);
+        }
+        if let Some(index) = &mut header.index {
+            for (_, text) in &mut index.sections {
+                state.reset();
+                typeset(text, &mut state);
+            }
+        }
+    }
+
+    let appendix_start = appendix_index.unwrap_or(body.len());
+    if header.number_sections != 0 || appendix_index.is_some() {
+        let mut counters = [0usize; MAX_SECTION_DEPTH];
+        for (i, (item, id)) in body.iter().enumerate() {
+            if i == appendix_start {
+                counters = [0usize; MAX_SECTION_DEPTH];
+            }
+
+            let BlogBody::Header(_, depth) = item else { continue };
+            if unnumbered_headers.contains(&i) { continue }
+
+            // appendices are always lettered, even when the rest of the document isn't numbered
+            let in_appendix = i >= appendix_start;
+            let max_depth = if in_appendix { header.number_sections.max(1) } else { header.number_sections };
+            if *depth == 0 || *depth > max_depth { continue }
+
+            counters[depth - 1] += 1;
+            for counter in &mut counters[*depth..] {
+                *counter = 0;
+            }
+
+            let number = counters[..*depth].iter().enumerate()
+                .map(|(n, counter)| if in_appendix && n == 0 { appendix_letter(*counter) } else { counter.to_string() })
+                .collect::<Vec<_>>()
+                .join(".");
+            header.section_numbers.insert(id.clone(), number);
+        }
+    }
+
+    // definitions, examples and theorems are counted per kind within each top level section,
+    // as in "Definition 2.1", or through the whole document when sections aren't numbered
+    if header.number_callouts {
+        let mut prefix = String::new();
+        let mut counters = HashMap::<FactBoxKind, usize>::new();
+        for (item, id) in body.iter_mut() {
+            match item {
+                BlogBody::Header(_, 1) => {
+                    if let Some(number) = header.section_numbers.get(id) {
+                        prefix = format!("{number}.");
+                        counters.clear();
                     }
-                }
-                if id != &contacts_id { continue }
-                while ids.contains(id) {
-                    *id = format!("{id}-disass");
-                }
-        
-                break;
+                },
+                BlogBody::FactBox(factbox) => number_callouts(factbox, &prefix, &mut counters),
+                _ => {}
             }
         }
     }
-    
+
+    let appendix = body.split_off(appendix_start);
+
     if let Some(toc) = header.toc.as_mut() {
         for (i, (item, id)) in body.iter().enumerate() {
             if i < toc.index { continue; }
@@ -987,10 +2096,37 @@ pub fn parse(file_content: &String, file_path: Option<&String>) -> Result<PawsMa
             }
         }
 
+        if !appendix.is_empty() {
+            toc.appendix.push((Box::new(BlogBody::Text(header.appendix_title.clone())), 1, appendix_id.clone()))
+        }
+
+        for (item, id) in &appendix {
+            if let BlogBody::Header(text, depth) = item {
+                if depth > &toc.max_depth {
+                    toc.max_depth = *depth;
+                }
+                toc.appendix.push((text.clone(), depth.clone(), id.clone()));
+            }
+            else if let BlogBody::FactBox(_) = item {
+                if 2 > toc.max_depth {
+                    toc.max_depth = 2;
+                }
+                toc.appendix.push((Box::new(item.clone()), 2, id.clone()));
+            }
+        }
+
         if !notes.is_empty() {
             toc.headers.push((Box::new(BlogBody::Text(header.notes_title.clone())), 1, notes_id.clone()))
         }
 
+        if !glossary.is_empty() {
+            toc.headers.push((Box::new(BlogBody::Text(header.glossary_title.clone())), 1, glossary_id.clone()))
+        }
+
+        if header.list_abbreviations && !abbreviations.is_empty() {
+            toc.headers.push((Box::new(BlogBody::Text(header.abbreviations_title.clone())), 1, abbreviations_id.clone()))
+        }
+
         if !references.is_empty() {
             toc.headers.push((Box::new(BlogBody::Text(header.bibliography_title.clone())), 1, bibliography_id.clone()))
         }
@@ -1003,8 +2139,15 @@ pub fn parse(file_content: &String, file_path: Option<&String>) -> Result<PawsMa
         notes_id, 
         bibliography_id, 
         contacts_id,
+        appendix_id,
+        glossary_id,
+        abbreviations_id,
         notes, 
-        body 
+        body,
+        appendix,
+        glossary,
+        abbreviations,
+        chapters,
     })
 }
 
@@ -1012,6 +2155,10 @@ pub fn parse(file_content: &String, file_path: Option<&String>) -> Result<PawsMa
 #[cfg(test)]
 mod tests {
     use crate::*;
+    use crate::paws_markdown::*;
+    use ordered_map::OrderedMap;
+    use config::*;
+    use crate::test_dir::TestDir;
 
     #[test]
     fn test_parse_remove_escaped() {
@@ -1212,6 +2359,342 @@ mod tests {
         let inner = Box::into_inner(result.unwrap());
         assert!(inner == BlogBody::ContactCitation("-other-example".into()))
     }
+
+    #[test]
+    fn test_parse_inline_markup() {
+        let text: String = "~~old~~ H~2~O x^2^ ==marked== ++under++".into();
+        let result = text_parse(&text);
+        assert!(result.is_ok());
+        let inner = Box::into_inner(result.unwrap().0);
+        assert_eq!(inner, BlogBody::Span(Span{ elements: vec![
+            BlogBody::Strikethrough(Box::new(BlogBody::Text("old".into()))),
+            BlogBody::Text(" H".into()),
+            BlogBody::Subscript(Box::new(BlogBody::Text("2".into()))),
+            BlogBody::Text("O x".into()),
+            BlogBody::Superscript(Box::new(BlogBody::Text("2".into()))),
+            BlogBody::Text(" ".into()),
+            BlogBody::Highlight(Box::new(BlogBody::Text("marked".into()))),
+            BlogBody::Text(" ".into()),
+            BlogBody::Underline(Box::new(BlogBody::Text("under".into()))),
+        ]}));
+    }
+
+    #[test]
+    fn test_parse_inline_markup_unclosed() {
+        let text: String = "a == b, 5 ~ 6 and 2^10".into();
+        let result = text_parse(&text);
+        assert!(result.is_ok());
+        let inner = Box::into_inner(result.unwrap().0);
+        assert_eq!(inner, BlogBody::Text("a == b, 5 ~ 6 and 2^10".into()));
+    }
+
+    #[test]
+    fn test_parse_inline_markup_flanking() {
+        for text in ["We compare C++ and C++", "a == b and c == d", "a ~~ b ~~ c"] {
+            let result = text_parse(&text.to_string());
+            assert!(result.is_ok());
+            assert_eq!(Box::into_inner(result.unwrap().0), BlogBody::Text(text.into()));
+        }
+        let result = text_parse(&"==a == b==".to_string());
+        assert_eq!(Box::into_inner(result.unwrap().0), BlogBody::Highlight(Box::new(BlogBody::Text("a == b".into()))));
+    }
+
+    #[test]
+    fn test_parse_glossary() {
+        let text: String = "# the API\nan api, the API and rapid\n\n#[glossary] rapid: fast\n#[glossary] API: Application Programming Interface\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_ok());
+        let md = result.unwrap();
+        let terms: Vec<_> = md.glossary.iter().map(|(term, _, id)| (term.as_str(), id.as_str())).collect();
+        assert_eq!(terms, vec![("API", "Ordliste-API"), ("rapid", "Ordliste-rapid")]);
+
+        let hover = |base: &str, alt: &str| BlogBody::Hoverable(Alternative{
+            base: Box::new(BlogBody::Text(base.into())),
+            alt:  Box::new(BlogBody::Text(alt.into())),
+        });
+        // headers are left alone, only the first use in the text is annotated
+        assert_eq!(md.body[1].0, BlogBody::Paragraph(Box::new(BlogBody::Span(Span{ elements: vec![
+            BlogBody::Text("an ".into()),
+            hover("api", "Application Programming Interface"),
+            BlogBody::Text(", the API and ".into()),
+            hover("rapid", "fast"),
+            BlogBody::Text("\n".into()),
+        ]}))));
+    }
+
+    #[test]
+    fn test_parse_abbreviations() {
+        let text: String = "---\nlist-abbreviations: true\n---\n# SDU\nSDU and SDUX, *SDU*\n\n*[SDU]: Syddansk Universitet\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_ok());
+        let md = result.unwrap();
+        assert!(md.header.list_abbreviations);
+        assert_eq!(md.abbreviations, vec![("SDU".to_string(), "Syddansk Universitet".to_string())]);
+
+        // headers are left alone, the first use is written out and the rest are marked
+        assert_eq!(md.body[0].0, BlogBody::Header(Box::new(BlogBody::Text("SDU".into())), 1));
+        let BlogBody::Paragraph(paragraph) = &md.body[1].0 else { panic!("expected a paragraph") };
+        let BlogBody::Span(span) = paragraph.as_ref() else { panic!("expected a span") };
+        assert_eq!(span.elements[0], BlogBody::Text("Syddansk Universitet (SDU) and SDUX, ".into()));
+        assert_eq!(span.elements[1], BlogBody::Italics(Box::new(BlogBody::Abbreviation("SDU".into(), "Syddansk Universitet".into()))));
+    }
+
+    #[test]
+    fn test_parse_index() {
+        let text: String = "before[+parser]\n\n# Method [+method]\na Parser[+Parser]\n\n#[index]\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_ok());
+        let md = result.unwrap();
+        assert_eq!(md.body[0].0, BlogBody::Paragraph(Box::new(BlogBody::Span(Span{ elements: vec![
+            BlogBody::Text("before".into()),
+            BlogBody::IndexTerm("parser".into(), "index-term-0".into()),
+            BlogBody::Text("\n".into()),
+        ]}))));
+
+        let index = md.header.index.unwrap();
+        assert_eq!(index.title, DEFAULT_INDEX_TITLE);
+        // terms are sorted and grouped regardless of case, terms in headers point to the header
+        assert_eq!(index.entries, vec![
+            ("method".to_string(), vec![("Method".to_string(), "Method".to_string())]),
+            ("parser".to_string(), vec![
+                ("index-term-0".to_string(), "".to_string()),
+                ("index-term-2".to_string(), "Method".to_string()),
+            ]),
+        ]);
+        assert!(index.sections.contains_key("Method"));
+    }
+
+    #[test]
+    fn test_walkers_agree() {
+        let text = |text: &str| Box::new(BlogBody::Text(text.into()));
+        let mut notes = OrderedMap::new();
+        notes.insert("1".to_string(), (BlogBody::Span(Span{ elements: vec![
+            BlogBody::Text("note ".into()),
+            BlogBody::IndexTerm("note".into(), String::new()),
+            BlogBody::Citation("a".into()),
+        ]}), "1".to_string()));
+        let mut element = BlogBody::FactBox(FactBox{ kind: FactBoxKind::Fact, title: "title".into(), number: None, notes, body: vec![
+            (BlogBody::Styled(Alternative{ base: text("color: red"), alt: Box::new(BlogBody::Citation("b".into())) }), String::new()),
+            (BlogBody::Hoverable(Alternative{ base: text("term"), alt: Box::new(BlogBody::IndexTerm("hover".into(), String::new())) }), String::new()),
+            (BlogBody::Link(Alternative{ base: text("link"), alt: text("https://example.org") }), String::new()),
+        ]});
+
+        // the css of a styled span and the url of a link are never text, hovers and notes always are
+        let mut texts = Vec::<String>::new();
+        for_each_text(&mut element, &mut |text| texts.push(text.clone()));
+        assert_eq!(texts, vec!["title", "term", "link", "note "]);
+        let mut terms = Vec::<String>::new();
+        visit_index_terms(&mut element, &mut |term, _| terms.push(term.clone()));
+        assert_eq!(terms, vec!["hover", "note"]);
+        let mut keys = Vec::<String>::new();
+        visit_citations(&element, &mut |key| keys.push(key.clone()));
+        assert_eq!(keys, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_no_emoji() {
+        for key in FRONTMATTER_NO_EMOJI {
+            let md = parse(&format!("---\n{key}: true\n---\n:smile:\n"), None).unwrap();
+            assert!(!md.header.emoji);
+            assert_eq!(md.body[0].0, BlogBody::Paragraph(Box::new(BlogBody::Text(":smile:\n".into()))));
+        }
+    }
+
+    #[test]
+    fn test_parse_symbols() {
+        let text: String = "---\nsymbols: {\"(c)\": \"©\"}\n---\n(c) :smile: `(c) :smile:`\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_ok());
+        let md = result.unwrap();
+        assert_eq!(md.body[0].0, BlogBody::Paragraph(Box::new(BlogBody::Span(Span{ elements: vec![
+            BlogBody::Text("© 😄 ".into()),
+            BlogBody::InlineCode("(c) :smile:".into()),
+            BlogBody::Text("\n".into()),
+        ]}))));
+    }
+
+    #[test]
+    fn test_parse_nested_factboxes() {
+        let text: String = "[[fact] outer\ntext[^1]\n[[note] inner {#inner}\ntext[^1]\n[^1]: inner note\n]\n[^1]: outer note\n]\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_ok());
+        let md = result.unwrap();
+        assert_eq!(md.body.len(), 1);
+        let (BlogBody::FactBox(outer), outer_id) = &md.body[0] else { panic!("expected a fact box") };
+        assert_eq!(outer_id, "outer");
+        assert_eq!(outer.notes.get("1").map(|(_, id)| id.as_str()), Some("outer-1"));
+
+        assert_eq!(outer.body[0].1, "outer-text");
+        let (BlogBody::FactBox(inner), inner_id) = &outer.body[1] else { panic!("expected a nested fact box") };
+        assert_eq!(inner_id, "inner");
+        assert_eq!(inner.kind, FactBoxKind::Note);
+        assert_eq!(inner.body[0].1, "inner-text");
+        assert_eq!(inner.notes.get("1").map(|(_, id)| id.as_str()), Some("inner-1"));
+    }
+
+    #[test]
+    fn test_parse_numbered_callouts() {
+        let text: String = "---\nnumber-sections: 1\nnumber-callouts: true\n---\n# first\n[[definition]\ntext\n]\n# second\n[[definition] Prime\ntext\n]\n[[example]\ntext\n]\n[[definition]\ntext\n]\n[[note]\ntext\n]\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_ok());
+        let titles: Vec<_> = result.unwrap().body.iter().filter_map(|(element, _)| match element {
+            BlogBody::FactBox(factbox) => Some(factbox.display_title()),
+            _ => None,
+        }).collect();
+        assert_eq!(titles, vec![
+            format!("{DEFAULT_DEFINITION_TITLE} 1.1"),
+            format!("{DEFAULT_DEFINITION_TITLE} 2.1: Prime"),
+            format!("{DEFAULT_EXAMPLE_TITLE} 2.1"),
+            format!("{DEFAULT_DEFINITION_TITLE} 2.2"),
+            DEFAULT_NOTE_TITLE.to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_block_note() {
+        let text: String = "text[^1]\n\n[^1]: first\n\n    ```rust\n    fn main() {}\n    ```\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_ok());
+        let md = result.unwrap();
+        assert_eq!(md.body.len(), 1);
+        assert_eq!(md.notes.get("1"), Some(&BlogBody::Blocks(vec![
+            (BlogBody::Paragraph(Box::new(BlogBody::Text("first\n".into()))), "^1-first".into()),
+            (BlogBody::CodeBlock("rust\nfn main() {}\n".into()), "^1-codeblock".into()),
+        ])));
+    }
+
+    #[test]
+    fn test_parse_autolinks() {
+        let text: String = "<https://example.com> or https://sirpaws.dev/blog.".into();
+        let result = text_parse(&text);
+        assert!(result.is_ok());
+        let inner = Box::into_inner(result.unwrap().0);
+        let link = |url: &str| BlogBody::Link(Alternative{
+            base: Box::new(BlogBody::Text(url.into())),
+            alt:  Box::new(BlogBody::Text(url.into())),
+        });
+        assert_eq!(inner, BlogBody::Span(Span{ elements: vec![
+            link("https://example.com"),
+            BlogBody::Text(" or ".into()),
+            link("https://sirpaws.dev/blog"),
+            BlogBody::Text(".".into()),
+        ]}));
+    }
+
+    #[test]
+    fn test_parse_autolinks_ignored() {
+        let text: String = "a <b> c, xhttps://x.dk and [https://x.dk](https://x.dk)".into();
+        let result = text_parse(&text);
+        assert!(result.is_ok());
+        let inner = Box::into_inner(result.unwrap().0);
+        assert_eq!(inner, BlogBody::Span(Span{ elements: vec![
+            BlogBody::Text("a <b> c, xhttps://x.dk and ".into()),
+            BlogBody::Link(Alternative{
+                base: Box::new(BlogBody::Text("https://x.dk".into())),
+                alt:  Box::new(BlogBody::Text("https://x.dk".into())),
+            }),
+        ]}));
+    }
+
+    #[test]
+    fn test_parse_number_sections() {
+        let text: String = "---\nnumber-sections: 2\n---\n# first\n## second\n### third\n# preface {-}\n# fourth\n## fifth\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_ok());
+        let header = result.unwrap().header;
+        assert_eq!(header.number_sections, 2);
+        assert_eq!(header.section_number(&"first".into()),   Some(&"1".into()));
+        assert_eq!(header.section_number(&"second".into()),  Some(&"1.1".into()));
+        assert_eq!(header.section_number(&"third".into()),   None);
+        assert_eq!(header.section_number(&"preface".into()), None);
+        assert_eq!(header.section_number(&"fourth".into()),  Some(&"2".into()));
+        assert_eq!(header.section_number(&"fifth".into()),   Some(&"2.1".into()));
+    }
+
+    #[test]
+    fn test_parse_draft() {
+        let draft = parse(&"---\ndraft: true\n---\ntext\n".into(), None).unwrap();
+        assert!(draft.header.draft);
+        let finished = parse(&"---\ndraft: false\n---\ntext\n".into(), None).unwrap();
+        assert!(!finished.header.draft);
+    }
+
+    #[test]
+    fn test_parse_explicit_ids() {
+        let text: String = "# Method {#method}\n# method\n[[image.png] a figure {#fig}]\n```rust {#listing}\nfn main() {}\n```\n> quoted\n> {#quote}\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_ok());
+        let ids = result.unwrap().body.into_iter().map(|(_, id)| id).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["method", "method-1", "fig", "listing", "quote"]);
+    }
+
+    #[test]
+    fn test_parse_duplicate_explicit_ids() {
+        let text: String = "# first {#same}\n# second {#same}\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_appendix() {
+        let text: String = "---\nnumber-sections: true\n---\n#[toc] Indhold\n# first\n#[appendix] Bilag\n# data\n## raw\n# code\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_ok());
+        let md = result.unwrap();
+        assert_eq!(md.body.len(), 2);
+        assert_eq!(md.appendix.len(), 3);
+        assert_eq!(md.appendix_id, "Bilag");
+        assert_eq!(md.header.section_number(&"first".into()), Some(&"1".into()));
+        assert_eq!(md.header.section_number(&"data".into()),  Some(&"A".into()));
+        assert_eq!(md.header.section_number(&"raw".into()),   Some(&"A.1".into()));
+        assert_eq!(md.header.section_number(&"code".into()),  Some(&"B".into()));
+
+        let toc = md.header.toc.unwrap();
+        assert_eq!(toc.headers.len(), 1);
+        assert_eq!(toc.appendix.len(), 4);
+    }
+
+    #[test]
+    fn test_section_ids_are_kept() {
+        let text: String = "# Ordliste\n#[glossary] term: a definition\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_ok());
+        let md = result.unwrap();
+        assert_eq!(md.body[0].1, "Ordliste-disass");
+        assert_eq!(md.glossary[0].2, "Ordliste-term");
+    }
+
+    #[test]
+    fn test_explicit_section_ids() {
+        let text: String = "# Words {#Ordliste}\n#[glossary] term: a definition\n".into();
+        let result = parse(&text, None);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("glossary"));
+    }
+
+    #[test]
+    fn test_included_sources() {
+        let dir = TestDir::new("included-sources");
+        let root = dir.write("root.pmd", "# root {#root}\n[£known]\n#[include] ch/a.pmd\n[£after]\n");
+        dir.write("ch/a.pmd", "# a\n[£missing] and [?someone]\n");
+        dir.write("ch/b.pmd", "# b {#root}\n");
+        let broken = dir.write("broken.pmd", "# root {#root}\n#[include] ch/b.pmd\n");
+        let dir = dir.path();
+
+        let result = file_parse(&root.to_string_lossy().to_string());
+        let error = file_parse(&broken.to_string_lossy().to_string());
+
+        assert!(result.is_ok());
+        let header = result.unwrap().header;
+        let source = dir.join("ch/a.pmd").display().to_string();
+        assert_eq!(header.source_of("missing"), format!("'{source}': "));
+        assert_eq!(header.source_of("someone"), format!("'{source}': "));
+        assert_eq!(header.source_of("known"), "");
+        assert_eq!(header.source_of("after"), "");
+
+        let error = format!("{:#}", error.unwrap_err());
+        assert!(error.contains(&dir.join("ch/b.pmd").display().to_string()));
+    }
     
 }
 
@@ -2,10 +2,10 @@
 use crate::*;
 #[cfg(feature = "mock_pdf")]
 use anyhow::anyhow;
-#[cfg(all(feature = "pdf", not(feature = "mock_pdf")))]
+#[cfg(all(feature = "chrome_pdf", not(feature = "mock_pdf")))]
 use headless_chrome::Browser;
 
-#[cfg(all(feature = "pdf", not(feature = "mock_pdf")))]
+#[cfg(all(feature = "chrome_pdf", not(feature = "mock_pdf")))]
 pub fn build_pdf(path: &str) -> Result<String> {
     let browser = Browser::default()?;
     let tab = browser.new_tab()?;
@@ -22,4 +22,13 @@ pub fn build_pdf(_: &str) -> Result<String> {
     Err(anyhow!("this pdf was mocked, this is a message to the user to check the Cargo.toml, if this is intended as debug check the tmp.html file for the actual 'pdf'"))
 }
 
+// the native backend is used unless the html is printed with chrome (or mocked)
+#[cfg(all(feature = "pdf", any(feature = "chrome_pdf", feature = "mock_pdf")))]
+pub fn render_pdf(md: &PawsMarkdown, name: &str) -> Result<Vec<u8>> {
+    Ok(to_string_from_boxed(md, PMDPDFSerializer::new(name))?.into_bytes())
+}
 
+#[cfg(all(feature = "native_pdf", not(any(feature = "chrome_pdf", feature = "mock_pdf"))))]
+pub fn render_pdf(md: &PawsMarkdown, _: &str) -> Result<Vec<u8>> {
+    pdf_native::to_pdf(md)
+}
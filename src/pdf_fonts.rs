@@ -0,0 +1,316 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(not(feature = "wasm"))]
+use color_print::cprintln;
+use anyhow::anyhow;
+use fontdb::{Database, Family, Query, Style, Weight};
+use pdf_writer::{Filter, Finish, Name, Pdf, Rect, Ref, Str};
+use pdf_writer::types::{CidFontType, FontFlags, SystemInfo, UnicodeCmap};
+use rustybuzz::{Face, UnicodeBuffer};
+use rustybuzz::ttf_parser::{name_id, GlyphId};
+use config::*;
+use crate::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum FontKind {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+    Mono,
+}
+
+impl FontKind {
+    pub const ALL: [FontKind; 5] = [
+        FontKind::Regular, FontKind::Bold, FontKind::Italic, FontKind::BoldItalic, FontKind::Mono,
+    ];
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn bold(&self) -> Self {
+        match self {
+            FontKind::Regular => FontKind::Bold,
+            FontKind::Italic  => FontKind::BoldItalic,
+            kind => *kind,
+        }
+    }
+
+    pub fn italic(&self) -> Self {
+        match self {
+            FontKind::Regular => FontKind::Italic,
+            FontKind::Bold    => FontKind::BoldItalic,
+            kind => *kind,
+        }
+    }
+
+    fn weight(&self) -> Weight {
+        match self {
+            FontKind::Bold | FontKind::BoldItalic => Weight::BOLD,
+            _ => Weight::NORMAL,
+        }
+    }
+
+    fn style(&self) -> Style {
+        match self {
+            FontKind::Italic | FontKind::BoldItalic => Style::Italic,
+            _ => Style::Normal,
+        }
+    }
+}
+
+pub struct FontData {
+    pub data: Vec<u8>,
+    pub index: u32,
+}
+
+// a glyph as shaped by rustybuzz, distances are in ems so the same glyphs work at any size.
+// text is what the glyph stands for (several characters for a ligature), used for copy/paste
+#[derive(Debug, PartialEq, Clone)]
+pub struct Glyph {
+    pub id: u16,
+    pub advance: f32,
+    pub x_offset: f32,
+    pub y_offset: f32,
+    pub text: String,
+}
+
+fn is_font_file(font: &str) -> bool {
+    let font = font.to_lowercase();
+    font.ends_with(".ttf") || font.ends_with(".otf") || font.ends_with(".ttc")
+}
+
+// loads a face for every FontKind, pdf-font is either the name of an installed family or a path
+// to a font file (relative to the markdown file), in which case that file is used for all of the
+// text except code. the built in fonts are used when no fonts are installed at all
+pub fn load_fonts(header: &BlogHeader) -> Result<Vec<FontData>> {
+    let mut database = Database::new();
+    database.load_system_fonts();
+    if database.is_empty() {
+        for font in PDF_FALLBACK_FONTS {
+            database.load_font_data(font.to_vec());
+        }
+    }
+
+    let font = header.frontmatter.as_ref().and_then(|frontmatter| frontmatter["pdf-font"].as_string());
+    let mut font_file = None;
+    if let Some(font) = &font && is_font_file(font) {
+        let data = std::fs::read(header.resolve_path(font)).map_err(|error| anyhow!("could not read the font '{font}': {error}"))?;
+        font_file = Some(data);
+    }
+
+    let mut families = Vec::<Family>::new();
+    if let Some(font) = &font && font_file.is_none() {
+        if database.query(&Query { families: &[Family::Name(font)], ..Query::default() }).is_none() {
+            #[cfg(not(feature = "wasm"))]
+            cprintln!("<y>warning:</> the font '{}' is not installed, using the default font instead", font);
+        }
+        families.push(Family::Name(font));
+    }
+    families.extend(PDF_FONT_FAMILIES.iter().map(|family| Family::Name(family)));
+    families.push(Family::SansSerif);
+
+    let mut mono_families: Vec<Family> = PDF_MONO_FONT_FAMILIES.iter().map(|family| Family::Name(family)).collect();
+    mono_families.push(Family::Monospace);
+
+    let mut fonts = Vec::new();
+    for kind in FontKind::ALL {
+        if kind != FontKind::Mono && let Some(data) = &font_file {
+            fonts.push(FontData { data: data.clone(), index: 0 });
+            continue;
+        }
+
+        let families = if kind == FontKind::Mono { &mono_families } else { &families };
+        let query = Query { families, weight: kind.weight(), style: kind.style(), ..Query::default() };
+        let id = database.query(&query)
+            .or_else(|| database.query(&Query { families: &families, ..Query::default() }))
+            .or_else(|| database.faces().next().map(|face| face.id))
+            .ok_or(anyhow!("no fonts are installed, install e.g. DejaVu Sans or set pdf-font to a .ttf file"))?;
+        let font = database.with_face_data(id, |data, index| FontData { data: data.to_vec(), index })
+            .ok_or(anyhow!("could not load the font for {:?} text", kind))?;
+        fonts.push(font);
+    }
+    Ok(fonts)
+}
+
+pub struct FontSet<'a> {
+    faces: Vec<Face<'a>>,
+    missing: RefCell<BTreeSet<char>>,
+}
+
+impl<'a> FontSet<'a> {
+    pub fn new(fonts: &'a Vec<FontData>) -> Result<Self> {
+        let mut faces = Vec::new();
+        for font in fonts {
+            let face = Face::from_slice(&font.data, font.index).ok_or(anyhow!("could not parse one of the fonts"))?;
+            faces.push(face);
+        }
+        Ok(Self { faces, missing: RefCell::new(BTreeSet::new()) })
+    }
+
+    pub fn face(&self, kind: FontKind) -> &Face<'a> {
+        &self.faces[kind.index()]
+    }
+
+    fn units_per_em(&self, kind: FontKind) -> f32 {
+        self.face(kind).units_per_em() as f32
+    }
+
+    pub fn ascender(&self, kind: FontKind) -> f32 {
+        self.face(kind).ascender() as f32 / self.units_per_em(kind)
+    }
+
+    pub fn descender(&self, kind: FontKind) -> f32 {
+        self.face(kind).descender() as f32 / self.units_per_em(kind)
+    }
+
+    pub fn shape(&self, kind: FontKind, text: &str) -> Vec<Glyph> {
+        let face = self.face(kind);
+        let scale = 1.0 / self.units_per_em(kind);
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        let output = rustybuzz::shape(face, &[], buffer);
+
+        let infos = output.glyph_infos();
+        let positions = output.glyph_positions();
+        let mut glyphs = Vec::with_capacity(infos.len());
+        for (n, (info, position)) in infos.iter().zip(positions).enumerate() {
+            // clusters are byte offsets into the text, only the first glyph of a cluster gets its text
+            let start = info.cluster as usize;
+            let first = n == 0 || infos[n - 1].cluster != info.cluster;
+            let end = infos[n..].iter()
+                .map(|info| info.cluster as usize)
+                .find(|&cluster| cluster > start)
+                .unwrap_or(text.len());
+            let glyph_text = if first && start < end { text[start..end].to_string() } else { String::new() };
+
+            if info.glyph_id == 0 {
+                self.missing.borrow_mut().extend(glyph_text.chars().filter(|c| !c.is_whitespace()));
+            }
+
+            glyphs.push(Glyph {
+                id: info.glyph_id as u16,
+                advance:  position.x_advance as f32 * scale,
+                x_offset: position.x_offset  as f32 * scale,
+                y_offset: position.y_offset  as f32 * scale,
+                text: glyph_text,
+            });
+        }
+        glyphs
+    }
+
+    pub fn width(&self, kind: FontKind, text: &str) -> f32 {
+        self.shape(kind, text).iter().map(|glyph| glyph.advance).sum()
+    }
+
+    // the advance the pdf viewer uses for a glyph, shaped advances differ from it with kerning
+    pub fn default_advance(&self, kind: FontKind, id: u16) -> f32 {
+        let face = self.face(kind);
+        face.glyph_hor_advance(GlyphId(id)).unwrap_or(0) as f32 / self.units_per_em(kind)
+    }
+
+    pub fn warn_missing(&self) {
+        let missing = self.missing.borrow();
+        if !missing.is_empty() {
+            let _characters: String = missing.iter().collect();
+            #[cfg(not(feature = "wasm"))]
+            cprintln!("<y>warning:</> the font has no glyphs for '{}', they are left out of the pdf", _characters);
+        }
+    }
+
+    // embeds the used glyphs of a face as a Type0 font with Identity-H encoding, so the glyph ids
+    // from shaping are written as is. ligatures copy as the text they were made from
+    pub fn write_font(&self, pdf: &mut Pdf, next_ref: &mut Ref, font_ref: Ref, kind: FontKind, font: &FontData, glyphs: &BTreeMap<u16, String>) -> Result<()> {
+        let face = self.face(kind);
+        let cid_ref = next_ref.bump();
+        let descriptor_ref = next_ref.bump();
+        let cmap_ref = next_ref.bump();
+        let file_ref = next_ref.bump();
+
+        let postscript_name = face.names().into_iter()
+            .find(|name| name.name_id == name_id::POST_SCRIPT_NAME)
+            .and_then(|name| name.to_string())
+            .unwrap_or("Font".into());
+        // subset fonts are named with a tag of six capital letters
+        let tag: String = (0..6).map(|n| (b'A' + ((font_ref.get() as u32 / 26u32.pow(n)) % 26) as u8) as char).collect();
+        let base_font = format!("{tag}+{postscript_name}");
+        let base_font = Name(base_font.as_bytes());
+
+        let mut used: Vec<u16> = glyphs.keys().copied().collect();
+        used.push(0);
+        let subset = subsetter::subset(&font.data, font.index, subsetter::Profile::pdf(&used))
+            .map_err(|error| anyhow!("could not embed the font '{postscript_name}': {error}"))?;
+        let subset = miniz_oxide::deflate::compress_to_vec_zlib(&subset, 6);
+        let is_cff = face.tables().cff.is_some();
+
+        let system_info = SystemInfo { registry: Str(b"Adobe"), ordering: Str(b"Identity"), supplement: 0 };
+        pdf.type0_font(font_ref)
+            .base_font(base_font)
+            .encoding_predefined(Name(b"Identity-H"))
+            .descendant_font(cid_ref)
+            .to_unicode(cmap_ref);
+
+        let units_per_em = self.units_per_em(kind);
+        let to_pdf_units = |value: f32| value * 1000.0 / units_per_em;
+
+        let mut cid_font = pdf.cid_font(cid_ref);
+        cid_font.subtype(if is_cff { CidFontType::Type0 } else { CidFontType::Type2 })
+            .base_font(base_font)
+            .system_info(system_info)
+            .font_descriptor(descriptor_ref)
+            .default_width(0.0);
+        if !is_cff {
+            cid_font.cid_to_gid_map_predefined(Name(b"Identity"));
+        }
+        let mut widths = cid_font.widths();
+        for id in glyphs.keys() {
+            widths.consecutive(*id, [self.default_advance(kind, *id) * 1000.0]);
+        }
+        widths.finish();
+        cid_font.finish();
+
+        let mut flags = FontFlags::NON_SYMBOLIC;
+        if face.is_italic() { flags |= FontFlags::ITALIC; }
+        if face.is_monospaced() { flags |= FontFlags::FIXED_PITCH; }
+        let bbox = face.global_bounding_box();
+        let ascender = to_pdf_units(face.ascender() as f32);
+        let mut descriptor = pdf.font_descriptor(descriptor_ref);
+        descriptor.name(base_font)
+            .flags(flags)
+            .bbox(Rect::new(
+                to_pdf_units(bbox.x_min as f32), to_pdf_units(bbox.y_min as f32),
+                to_pdf_units(bbox.x_max as f32), to_pdf_units(bbox.y_max as f32),
+            ))
+            .italic_angle(face.italic_angle())
+            .ascent(ascender)
+            .descent(to_pdf_units(face.descender() as f32))
+            .cap_height(face.capital_height().map(|height| to_pdf_units(height as f32)).unwrap_or(ascender))
+            .stem_v(if face.is_bold() { 120.0 } else { 80.0 });
+        if is_cff {
+            descriptor.font_file3(file_ref);
+        } else {
+            descriptor.font_file2(file_ref);
+        }
+        descriptor.finish();
+
+        let mut cmap = UnicodeCmap::new(Name(b"Custom"), system_info);
+        for (id, text) in glyphs {
+            if !text.is_empty() {
+                cmap.pair_with_multiple(*id, text.chars());
+            }
+        }
+        let cmap = cmap.finish();
+        pdf.cmap(cmap_ref, &cmap);
+
+        let mut stream = pdf.stream(file_ref, &subset);
+        stream.filter(Filter::FlateDecode);
+        if is_cff {
+            stream.pair(Name(b"Subtype"), Name(b"OpenType"));
+        }
+        stream.finish();
+        Ok(())
+    }
+}
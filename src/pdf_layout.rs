@@ -0,0 +1,1603 @@
+use std::collections::{HashMap, HashSet};
+
+#[cfg(not(feature = "wasm"))]
+use color_print::cprintln;
+use config::*;
+use page_geometry::*;
+use title_page::*;
+use pdf_fonts::*;
+use pdf_native::*;
+use syntax::*;
+use crate::*;
+
+// the native pdf backend lays the document out in points with the origin in the top left
+// corner of the page, y grows downwards and text is placed by its baseline.
+pub type Color = (f32, f32, f32);
+
+const BLACK: Color = (0.0, 0.0, 0.0);
+const GREY: Color = (0.35, 0.35, 0.35);
+const RULE_COLOR: Color = (0.6, 0.6, 0.6);
+const LINK_COLOR: Color = (0.0, 0.0, 0.8);
+const MISSING_COLOR: Color = (0.8, 0.0, 0.0);
+const CODE_BACKGROUND: Color = (0.94, 0.94, 0.94);
+const HIGHLIGHT_COLOR: Color = (1.0, 0.94, 0.4);
+const WATERMARK_COLOR: Color = (0.9, 0.9, 0.9);
+const FACTBOX_BACKGROUND: Color = (0.953, 0.953, 0.953);
+const NESTED_FACTBOX_BACKGROUND: Color = (0.9, 0.9, 0.9);
+
+const HEADER_SIZES: [f32; MAX_SECTION_DEPTH] = [18.0, 16.0, 14.0, 12.0, 12.0, 12.0];
+// marks a hard line break in the text of a piece, as between the lines of a quote
+const LINE_BREAK: char = '\u{2028}';
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum LinkTarget {
+    Url(String),
+    // an id in the document, written as a named destination
+    Anchor(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Op {
+    // the angle turns the text counterclockwise around the start of its baseline, in degrees
+    Text { x: f32, y: f32, angle: f32, font: FontKind, size: f32, color: Color, glyphs: Vec<Glyph> },
+    Rect { x: f32, y: f32, width: f32, height: f32, color: Color },
+    Line { x1: f32, y1: f32, x2: f32, y2: f32, width: f32, color: Color },
+    Image { image: usize, x: f32, y: f32, width: f32, height: f32 },
+    Link { x: f32, y: f32, width: f32, height: f32, target: LinkTarget },
+}
+
+impl Op {
+    fn move_down(&mut self, offset: f32) {
+        match self {
+            Op::Text { y, .. } | Op::Rect { y, .. } | Op::Image { y, .. } | Op::Link { y, .. } => *y += offset,
+            Op::Line { y1, y2, .. } => {
+                *y1 += offset;
+                *y2 += offset;
+            },
+        }
+    }
+}
+
+pub struct Page {
+    pub ops: Vec<Op>,
+    pub width: f32,
+    pub height: f32,
+}
+
+pub struct LaidOutDocument {
+    pub pages: Vec<Page>,
+    pub images: Vec<PdfImage>,
+    // where every id ended up, as (page, y)
+    pub anchors: HashMap<String, (usize, f32)>,
+    // the bookmarks, as (title, depth, id)
+    pub outline: Vec<(String, usize, String)>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Style {
+    font: FontKind,
+    size: f32,
+    color: Color,
+    rise: f32,
+    underline: bool,
+    strike: bool,
+    background: Option<Color>,
+    link: Option<LinkTarget>,
+}
+
+// a piece of text with one style, inline elements are flattened into these
+struct Piece {
+    text: String,
+    style: Style,
+    anchor: Option<String>,
+}
+
+#[derive(Clone)]
+struct Fragment {
+    glyphs: Vec<Glyph>,
+    style: Style,
+    width: f32,
+    anchor: Option<String>,
+}
+
+#[derive(Clone)]
+enum Item {
+    Word(Vec<Fragment>),
+    Space(f32),
+    Break,
+}
+
+impl Item {
+    fn width(&self) -> f32 {
+        match self {
+            Item::Word(fragments) => fragments.iter().map(|fragment| fragment.width).sum(),
+            Item::Space(width) => *width,
+            Item::Break => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// backgrounds and borders of the blocks the cursor is inside of, drawn next to every line so
+// a fact box or code block can continue on the next page
+struct Decoration {
+    left: f32,
+    right: f32,
+    background: Option<Color>,
+    border: Option<(Color, f32)>,
+}
+
+pub struct PageFurniture {
+    pub header: [String; 3],
+    pub footer: [String; 3],
+    // the same as the ones above, unless pdf-header-even or pdf-footer-even are set
+    pub even_header: [String; 3],
+    pub even_footer: [String; 3],
+}
+
+fn furniture_text(frontmatter: &Frontmatter, key: &str) -> String {
+    substitute_document_fields(&frontmatter[key].as_string().unwrap_or_default(), frontmatter)
+}
+
+// the left, center and right text of e.g. pdf-header, where pdf-header is the same as pdf-header-center
+fn furniture_row(frontmatter: &Frontmatter, key: &str) -> [String; 3] {
+    let mut row = ["left", "center", "right"].map(|place| furniture_text(frontmatter, format!("{key}-{place}").as_str()));
+    if row[1].is_empty() { row[1] = furniture_text(frontmatter, key); }
+    row
+}
+
+impl PageFurniture {
+    pub fn from_frontmatter(frontmatter: &Frontmatter) -> Self {
+        let header = furniture_row(frontmatter, "pdf-header");
+        let footer = furniture_row(frontmatter, "pdf-footer");
+        let even_header = furniture_row(frontmatter, "pdf-header-even");
+        let even_footer = furniture_row(frontmatter, "pdf-footer-even");
+        let or_odd = |even: [String; 3], odd: &[String; 3]| if even.iter().all(|text| text.is_empty()) { odd.clone() } else { even };
+        Self {
+            even_header: or_odd(even_header, &header),
+            even_footer: or_odd(even_footer, &footer),
+            header,
+            footer,
+        }
+    }
+
+    // pages are counted from 0 here, so the first page is odd
+    pub fn rows(&self, page: usize) -> (&[String; 3], &[String; 3]) {
+        if page % 2 == 1 { (&self.even_header, &self.even_footer) } else { (&self.header, &self.footer) }
+    }
+}
+
+// %chapter and %section insert the current top level and second level heading
+pub fn substitute_headings(text: &str, chapter: &str, section: &str) -> String {
+    text.replace("%chapter", chapter).replace("%section", section)
+}
+
+// %p and %page insert the page number, %np and %pages the number of pages
+pub fn substitute_page_numbers(text: &str, page: usize, pages: usize) -> String {
+    text.replace("%pages", pages.to_string().as_str())
+        .replace("%page", page.to_string().as_str())
+        .replace("%np", pages.to_string().as_str())
+        .replace("%p", page.to_string().as_str())
+}
+
+// the text of simple inline elements, used for link targets
+fn text_of(element: &BlogBody) -> String {
+    match element {
+        BlogBody::Text(text) | BlogBody::InlineCode(text) => text.clone(),
+        BlogBody::Span(span) => span.elements.iter().map(text_of).collect(),
+        BlogBody::Italics(text) | BlogBody::Bold(text) | BlogBody::Strikethrough(text) |
+        BlogBody::Superscript(text) | BlogBody::Subscript(text) | BlogBody::Highlight(text) |
+        BlogBody::Underline(text) | BlogBody::Paragraph(text) | BlogBody::Header(text, _) => text_of(text),
+        _ => String::new(),
+    }
+}
+
+fn is_block(element: &BlogBody) -> bool {
+    matches!(element,
+        BlogBody::Header(_, _) | BlogBody::Paragraph(_) | BlogBody::CodeBlock(_) | BlogBody::Image(_, _) |
+        BlogBody::FactBox(_) | BlogBody::Quote(_) | BlogBody::List(_) | BlogBody::DefinitionList(_) |
+        BlogBody::Blocks(_) | BlogBody::PageBreak | BlogBody::TOCLocationMarker | BlogBody::IndexLocationMarker)
+}
+
+struct Layout<'a, 'f> {
+    fonts: &'a FontSet<'f>,
+    md: &'a PawsMarkdown,
+    pages: Vec<Page>,
+    images: Vec<PdfImage>,
+    anchors: HashMap<String, (usize, f32)>,
+    // the page every id was on in the previous pass, so the toc and cross-references can refer to it
+    page_numbers: HashMap<String, usize>,
+    page_label: String,
+    warnings: Vec<String>,
+    used_references: HashSet<String>,
+    decorations: Vec<Decoration>,
+    marker: Option<(Vec<Fragment>, f32)>,
+    factbox_depth: usize,
+    text_size: f32,
+    line_height: f32,
+    // the geometry from the frontmatter, and the one of the current page, which is turned
+    // sideways inside of landscape sections
+    document_geometry: PageGeometry,
+    geometry: PageGeometry,
+    landscape_depth: Option<usize>,
+    // the page, depth and text of every heading, for %chapter and %section in the headers
+    heading_marks: Vec<(usize, usize, String)>,
+    // the pages that start with a chapter heading, these get no header
+    chapter_pages: Vec<usize>,
+    draft: bool,
+    // the page, left edge of the column and baseline of every line, numbered in drafts
+    numbered_lines: Vec<(usize, f32, f32)>,
+    code_theme: &'static CodeTheme,
+    column: usize,
+    column_top: f32,
+    column_edges: (f32, f32),
+    left: f32,
+    right: f32,
+    y: f32,
+    last_baseline: f32,
+}
+
+impl<'a, 'f> Layout<'a, 'f> {
+    fn new(md: &'a PawsMarkdown, fonts: &'a FontSet<'f>, frontmatter: &Frontmatter, page_numbers: HashMap<String, usize>) -> Self {
+        let text_size = frontmatter["pdf-text-size"].as_f64().map(|size| size as f32).unwrap_or(PDF_TEXT_SIZE);
+        let line_height = frontmatter["pdf-line-height"].as_f64().map(|height| height as f32).unwrap_or(PDF_LINE_HEIGHT);
+        let geometry = PageGeometry::from_frontmatter(frontmatter);
+        let (left, right) = geometry.column(0, 0);
+        let mut warnings = vec![];
+        let code_theme = code_theme(frontmatter).unwrap_or_else(|error| {
+            warnings.push(error.to_string());
+            &CODE_THEMES[0]
+        });
+        Self {
+            fonts,
+            md,
+            pages: vec![Page { ops: vec![], width: geometry.width, height: geometry.height }],
+            images: vec![],
+            anchors: HashMap::new(),
+            page_numbers,
+            page_label: frontmatter["pdf-page-label"].as_string().unwrap_or(PDF_PAGE_LABEL.into()),
+            warnings,
+            used_references: HashSet::new(),
+            decorations: vec![],
+            marker: None,
+            factbox_depth: 0,
+            text_size,
+            line_height,
+            column: 0,
+            column_top: geometry.top,
+            column_edges: (left, right),
+            left,
+            right,
+            y: geometry.top,
+            last_baseline: geometry.top,
+            document_geometry: geometry.clone(),
+            geometry,
+            landscape_depth: None,
+            heading_marks: vec![],
+            chapter_pages: vec![],
+            draft: md.header.draft,
+            numbered_lines: vec![],
+            code_theme,
+        }
+    }
+
+    // warnings are printed once, after the last pass
+    fn warn(&mut self, message: String) {
+        self.warnings.push(message);
+    }
+
+    // pages are numbered from 1, the same way as %p in the headers and footers
+    fn page_number(&self, id: &str) -> String {
+        self.page_numbers.get(id).map(|page| (page + 1).to_string()).unwrap_or("?".into())
+    }
+
+    fn base_style(&self) -> Style {
+        Style {
+            font: FontKind::Regular,
+            size: self.text_size,
+            color: BLACK,
+            rise: 0.0,
+            underline: false,
+            strike: false,
+            background: None,
+            link: None,
+        }
+    }
+
+    fn top(&self) -> f32 { self.column_top }
+    fn bottom(&self) -> f32 { self.geometry.height - self.geometry.bottom }
+
+    fn page(&self) -> usize {
+        self.pages.len() - 1
+    }
+
+    fn push(&mut self, op: Op) {
+        self.pages.last_mut().unwrap().ops.push(op);
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Page { ops: vec![], width: self.geometry.width, height: self.geometry.height });
+        self.column = 0;
+        self.column_top = self.geometry.top;
+        self.y = self.top();
+        self.move_to_column();
+    }
+
+    // text continues in the next column, or on the next page after the last one
+    fn next_column(&mut self) {
+        if self.column + 1 < self.geometry.columns {
+            self.column += 1;
+            self.y = self.top();
+            self.move_to_column();
+        } else {
+            self.new_page();
+        }
+    }
+
+    // the edges of the column can move between pages with mirrored margins or landscape pages,
+    // indents and the blocks the cursor is inside of are moved along with them
+    fn move_to_column(&mut self) {
+        let (left, right) = self.geometry.column(self.page(), self.column);
+        let (left_delta, right_delta) = (left - self.column_edges.0, right - self.column_edges.1);
+        self.column_edges = (left, right);
+        self.left  += left_delta;
+        self.right += right_delta;
+        for decoration in &mut self.decorations {
+            decoration.left  += left_delta;
+            decoration.right += right_delta;
+        }
+        if let Some((_, x)) = &mut self.marker {
+            *x += left_delta;
+        }
+    }
+
+    // text that goes across all of the columns, like the title
+    fn span_columns(&mut self, span: bool) {
+        let (left, right) = if span { self.geometry.text_area(self.page()) } else { self.geometry.column(self.page(), self.column) };
+        self.left  += left - self.column_edges.0;
+        self.right += right - self.column_edges.1;
+        self.column_edges = (left, right);
+    }
+
+    // a new page is started when the orientation changes, unless the current one is still empty
+    fn set_landscape(&mut self, landscape: bool) {
+        let geometry = if landscape { self.document_geometry.landscape() } else { self.document_geometry.clone() };
+        if geometry == self.geometry { return }
+        self.geometry = geometry;
+        if self.pages.len() > 1 && self.pages.last().is_some_and(|page| page.ops.is_empty()) {
+            self.pages.pop();
+        }
+        self.new_page();
+    }
+
+    fn at_top(&self) -> bool {
+        self.y <= self.top()
+    }
+
+    // starts a new page unless the height fits on this one
+    fn ensure(&mut self, height: f32) {
+        if self.y + height > self.bottom() && !self.at_top() {
+            self.next_column();
+        }
+    }
+
+    fn fill_decorations(&mut self, from: f32, to: f32) {
+        let mut ops = vec![];
+        for decoration in &self.decorations {
+            if let Some(color) = decoration.background {
+                ops.push(Op::Rect { x: decoration.left, y: from, width: decoration.right - decoration.left, height: to - from, color });
+            }
+            if let Some((color, width)) = decoration.border {
+                ops.push(Op::Rect { x: decoration.left, y: from, width, height: to - from, color });
+            }
+        }
+        for op in ops {
+            self.push(op);
+        }
+    }
+
+    // vertical space between blocks, it's dropped at the top of a page
+    fn space(&mut self, amount: f32) {
+        if self.at_top() { return }
+        if self.y + amount > self.bottom() {
+            self.next_column();
+            return;
+        }
+        self.fill_decorations(self.y, self.y + amount);
+        self.y += amount;
+    }
+
+    fn anchor(&mut self, id: &String) {
+        if !id.is_empty() {
+            self.anchors.insert(id.clone(), (self.page(), self.y));
+        }
+    }
+
+    fn rule(&mut self) {
+        self.space(self.text_size);
+        self.ensure(self.text_size * 4.0);
+        let (left, right, y) = (self.left, self.right, self.y);
+        self.push(Op::Line { x1: left, y1: y, x2: right, y2: y, width: 0.5, color: RULE_COLOR });
+        self.space(self.text_size);
+    }
+
+    //////////////////////////////////////////////////////////////////////////////////////////
+    // inline elements
+
+    fn piece(&self, text: impl Into<String>, style: &Style, pieces: &mut Vec<Piece>) {
+        pieces.push(Piece { text: text.into(), style: style.clone(), anchor: None });
+    }
+
+    fn superscript(style: &Style) -> Style {
+        Style { size: style.size * 0.7, rise: style.rise + style.size * 0.35, ..style.clone() }
+    }
+
+    fn subscript(style: &Style) -> Style {
+        Style { size: style.size * 0.7, rise: style.rise - style.size * 0.15, ..style.clone() }
+    }
+
+    fn linked(style: &Style, id: &str) -> Style {
+        Style { link: Some(LinkTarget::Anchor(id.into())), ..style.clone() }
+    }
+
+    fn inline(&mut self, element: &BlogBody, style: &Style, pieces: &mut Vec<Piece>) {
+        let md = self.md;
+        let header = &md.header;
+        match element {
+            BlogBody::Text(text) => self.piece(text, style, pieces),
+            BlogBody::Span(span) => {
+                for element in &span.elements {
+                    self.inline(element, style, pieces);
+                }
+            },
+            BlogBody::Italics(text) => self.inline(text, &Style { font: style.font.italic(), ..style.clone() }, pieces),
+            BlogBody::Bold(text) => self.inline(text, &Style { font: style.font.bold(), ..style.clone() }, pieces),
+            BlogBody::Strikethrough(text) => self.inline(text, &Style { strike: true, ..style.clone() }, pieces),
+            BlogBody::Underline(text) => self.inline(text, &Style { underline: true, ..style.clone() }, pieces),
+            BlogBody::Highlight(text) => self.inline(text, &Style { background: Some(HIGHLIGHT_COLOR), ..style.clone() }, pieces),
+            BlogBody::Superscript(text) => self.inline(text, &Self::superscript(style), pieces),
+            BlogBody::Subscript(text) => self.inline(text, &Self::subscript(style), pieces),
+            BlogBody::InlineCode(text) => {
+                let style = Style { font: FontKind::Mono, size: style.size * 0.9, background: Some(CODE_BACKGROUND), ..style.clone() };
+                self.piece(text, &style, pieces);
+            },
+            BlogBody::Hoverable(hoverable) => self.inline(&hoverable.base, style, pieces),
+            BlogBody::Styled(styled) => self.inline(&styled.alt, style, pieces),
+            BlogBody::Link(link) => self.link(link, style, pieces),
+            BlogBody::Citation(id) => {
+                if let Some(reference) = self.md.references.get(id) {
+                    self.used_references.insert(id.clone());
+                    if !header.hide_references {
+                        self.piece(to_citation(reference), &Self::linked(style, id), pieces);
+                    }
+                } else {
+                    self.warn(format!("{}{} has no source", header.source_of(id), id));
+                    self.missing("(MISSING CITATION)", id, style, pieces);
+                }
+            },
+            BlogBody::ContactCitation(id) => {
+                if self.md.contacts.get(id).is_none() {
+                    self.warn(format!("{}{} has no source", header.source_of(id), id));
+                    self.missing("(MISSING CONTACT)", id, style, pieces);
+                } else if header.should_cite_contacts && !header.hide_contacts {
+                    self.piece("?", &Self::linked(&Self::subscript(style), id), pieces);
+                }
+            },
+            BlogBody::Abbreviation(abbreviation, _) => self.piece(abbreviation, style, pieces),
+            // the term is only a marker for the index, as in the html
+            BlogBody::IndexTerm(_, id) => {
+                if !id.is_empty() {
+                    pieces.push(Piece { text: String::new(), style: style.clone(), anchor: Some(id.clone()) });
+                }
+            },
+            BlogBody::Note(id) => {
+                if self.draft && self.md.notes.get(id).is_none() {
+                    self.missing("(MISSING NOTE)", id, style, pieces);
+                } else if !header.hide_notes {
+                    self.piece(id, &Self::linked(&Self::superscript(style), &format!("^{id}")), pieces);
+                }
+            },
+            BlogBody::Paragraph(text) | BlogBody::Header(text, _) => self.inline(text, style, pieces),
+            BlogBody::Image(_, alt) => self.piece(alt, &Style { font: style.font.italic(), ..style.clone() }, pieces),
+            BlogBody::CodeBlock(text) => {
+                let code = text.split_once('\n').map(|(_, code)| code).unwrap_or(text);
+                self.piece(code.trim_end(), &Style { font: FontKind::Mono, ..style.clone() }, pieces)
+            },
+            BlogBody::Quote(lines) | BlogBody::List(lines) => {
+                for (n, line) in lines.iter().enumerate() {
+                    if n != 0 { self.piece(LINE_BREAK, style, pieces); }
+                    self.inline(line, style, pieces);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    // drafts mark what is missing, with the key, so it stands out when the draft is read
+    fn missing(&mut self, text: &str, id: &String, style: &Style, pieces: &mut Vec<Piece>) {
+        if self.draft {
+            let style = Style { color: MISSING_COLOR, background: Some(HIGHLIGHT_COLOR), ..style.clone() };
+            self.piece(format!("{text} {id}"), &style, pieces);
+        } else {
+            self.piece(text, &Style { color: MISSING_COLOR, ..style.clone() }, pieces);
+        }
+    }
+
+    fn link(&mut self, link: &Alternative, style: &Style, pieces: &mut Vec<Piece>) {
+        let md = self.md;
+        let header = &md.header;
+        match &link.alt {
+            box BlogBody::Citation(citation) => {
+                if self.md.references.get(citation).is_some() {
+                    self.used_references.insert(citation.clone());
+                    if !header.hide_references {
+                        self.inline(&link.base, &Self::linked(style, citation), pieces);
+                    }
+                } else {
+                    self.warn(format!("{}{} has no source", header.source_of(citation), citation));
+                    self.inline(&link.base, &Style { color: MISSING_COLOR, ..style.clone() }, pieces);
+                }
+            },
+            box BlogBody::Note(note) => {
+                if !header.hide_notes {
+                    self.inline(&link.base, &Self::linked(style, &format!("^{note}")), pieces);
+                }
+            },
+            alt => {
+                let href = text_of(alt);
+                if let Some(target) = href.strip_prefix('#') {
+                    let style = &Self::linked(style, target);
+                    // without a text, sections are referred to by number and everything else by page
+                    if text_of(&link.base).trim().is_empty() {
+                        let reference = match header.section_number(&target.to_string()) {
+                            Some(number) => number.clone(),
+                            None => format!("{} {}", self.page_label, self.page_number(target)),
+                        };
+                        self.piece(reference, style, pieces);
+                    } else {
+                        self.inline(&link.base, style, pieces);
+                    }
+                } else {
+                    let style = Style { color: LINK_COLOR, link: Some(LinkTarget::Url(href)), ..style.clone() };
+                    self.inline(&link.base, &style, pieces);
+                }
+            }
+        }
+    }
+
+    //////////////////////////////////////////////////////////////////////////////////////////
+    // line breaking
+
+    fn fragment(&self, text: &str, style: &Style, anchor: Option<String>) -> Fragment {
+        let glyphs = self.fonts.shape(style.font, text);
+        let width = glyphs.iter().map(|glyph| glyph.advance).sum::<f32>() * style.size;
+        Fragment { glyphs, style: style.clone(), width, anchor }
+    }
+
+    fn items(&self, pieces: &Vec<Piece>) -> Vec<Item> {
+        let mut items = Vec::new();
+        let mut word = Vec::<Fragment>::new();
+        for piece in pieces {
+            let mut anchor = piece.anchor.clone();
+            let mut text = String::new();
+            for character in piece.text.chars() {
+                if !(character.is_whitespace() && character != '\u{a0}') {
+                    text.push(character);
+                    continue;
+                }
+                if !text.is_empty() || anchor.is_some() {
+                    word.push(self.fragment(&text, &piece.style, anchor.take()));
+                    text.clear();
+                }
+                if !word.is_empty() {
+                    items.push(Item::Word(std::mem::take(&mut word)));
+                }
+                if character == LINE_BREAK {
+                    items.push(Item::Break);
+                } else if !matches!(items.last(), Some(Item::Space(_))) {
+                    items.push(Item::Space(self.fonts.width(piece.style.font, " ") * piece.style.size));
+                }
+            }
+            if !text.is_empty() || anchor.is_some() {
+                word.push(self.fragment(&text, &piece.style, anchor.take()));
+            }
+        }
+        if !word.is_empty() {
+            items.push(Item::Word(word));
+        }
+        items
+    }
+
+    // a word wider than the line is broken between its glyphs
+    fn split_word(word: Vec<Fragment>, width: f32) -> Vec<Item> {
+        let mut words = Vec::new();
+        let mut current = Vec::<Fragment>::new();
+        let mut current_width = 0.0;
+        for fragment in word {
+            let mut part = Fragment { glyphs: vec![], width: 0.0, ..fragment.clone() };
+            let continuation = Fragment { anchor: None, ..part.clone() };
+            for glyph in fragment.glyphs {
+                let advance = glyph.advance * fragment.style.size;
+                if current_width + advance > width && current_width > 0.0 {
+                    if !part.glyphs.is_empty() {
+                        current.push(part.clone());
+                    }
+                    words.push(Item::Word(std::mem::take(&mut current)));
+                    part = continuation.clone();
+                    current_width = 0.0;
+                }
+                part.width += advance;
+                part.glyphs.push(glyph);
+                current_width += advance;
+            }
+            current.push(part);
+        }
+        if !current.is_empty() {
+            words.push(Item::Word(current));
+        }
+        words
+    }
+
+    fn break_lines(items: Vec<Item>, width: f32) -> Vec<Vec<Item>> {
+        let mut lines = Vec::new();
+        let mut line = Vec::<Item>::new();
+        let mut line_width = 0.0;
+        let mut pending_space = 0.0;
+        let mut queue: std::collections::VecDeque<Item> = items.into();
+        while let Some(item) = queue.pop_front() {
+            match item {
+                Item::Break => {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0.0;
+                    pending_space = 0.0;
+                },
+                Item::Space(space) => {
+                    if !line.is_empty() { pending_space = space; }
+                },
+                Item::Word(word) => {
+                    let word_width = Item::Word(word.clone()).width();
+                    if line.is_empty() && word_width > width {
+                        for part in Self::split_word(word, width).into_iter().rev() {
+                            queue.push_front(part);
+                        }
+                        // the parts are pushed back on the queue, the first one fits on its own
+                        if let Some(Item::Word(first)) = queue.pop_front() {
+                            line_width = Item::Word(first.clone()).width();
+                            line.push(Item::Word(first));
+                        }
+                        continue;
+                    }
+                    if !line.is_empty() && line_width + pending_space + word_width > width {
+                        lines.push(std::mem::take(&mut line));
+                        line_width = 0.0;
+                        pending_space = 0.0;
+                    }
+                    if !line.is_empty() && pending_space > 0.0 {
+                        line.push(Item::Space(pending_space));
+                        line_width += pending_space;
+                    }
+                    pending_space = 0.0;
+                    line_width += word_width;
+                    line.push(Item::Word(word));
+                },
+            }
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    // the height of a line and the distance from its top to the baseline
+    fn line_metrics(&self, line: &Vec<Item>) -> (f32, f32) {
+        let mut size: f32 = 0.0;
+        let mut ascent: f32 = 0.0;
+        let mut descent: f32 = 0.0;
+        for item in line {
+            if let Item::Word(fragments) = item {
+                for fragment in fragments {
+                    let style = &fragment.style;
+                    size    = size.max(style.size);
+                    ascent  = ascent.max(style.size * self.fonts.ascender(style.font) + style.rise);
+                    descent = descent.max(-style.size * self.fonts.descender(style.font) - style.rise);
+                }
+            }
+        }
+        if size == 0.0 {
+            return (self.text_size * self.line_height, self.text_size);
+        }
+        let height = (size * self.line_height).max(ascent + descent);
+        (height, (height - (ascent + descent)) / 2.0 + ascent)
+    }
+
+    fn emit_fragment(&mut self, fragment: &Fragment, x: f32, baseline: f32) {
+        let style = &fragment.style;
+        let ascent  = style.size * self.fonts.ascender(style.font);
+        let descent = -style.size * self.fonts.descender(style.font);
+        let y = baseline - style.rise;
+        if let Some(color) = style.background {
+            self.push(Op::Rect { x, y: y - ascent, width: fragment.width, height: ascent + descent, color });
+        }
+        if let Some(anchor) = &fragment.anchor {
+            self.anchors.insert(anchor.clone(), (self.page(), y - ascent));
+        }
+        if !fragment.glyphs.is_empty() {
+            self.push(Op::Text { x, y, angle: 0.0, font: style.font, size: style.size, color: style.color, glyphs: fragment.glyphs.clone() });
+        }
+        let thickness = style.size / 18.0;
+        if style.underline {
+            self.push(Op::Line { x1: x, y1: y + style.size * 0.12, x2: x + fragment.width, y2: y + style.size * 0.12, width: thickness, color: style.color });
+        }
+        if style.strike {
+            self.push(Op::Line { x1: x, y1: y - style.size * 0.3, x2: x + fragment.width, y2: y - style.size * 0.3, width: thickness, color: style.color });
+        }
+        if let Some(target) = &style.link {
+            self.push(Op::Link { x, y: y - ascent, width: fragment.width, height: ascent + descent, target: target.clone() });
+        }
+    }
+
+    fn place_lines(&mut self, lines: Vec<Vec<Item>>, align: Align) {
+        for line in lines {
+            let (height, baseline) = self.line_metrics(&line);
+            self.ensure(height);
+            self.fill_decorations(self.y, self.y + height);
+            let baseline = self.y + baseline;
+            self.last_baseline = baseline;
+            if self.draft {
+                self.numbered_lines.push((self.page(), self.column_edges.0, baseline));
+            }
+
+            if let Some((marker, x)) = self.marker.take() {
+                let mut x = x;
+                for fragment in &marker {
+                    self.emit_fragment(fragment, x, baseline);
+                    x += fragment.width;
+                }
+            }
+
+            let width: f32 = line.iter().map(|item| item.width()).sum();
+            let mut x = match align {
+                Align::Left   => self.left,
+                Align::Center => self.left + (self.right - self.left - width) / 2.0,
+                Align::Right  => self.right - width,
+            };
+            for item in &line {
+                if let Item::Word(fragments) = item {
+                    for fragment in fragments {
+                        self.emit_fragment(fragment, x, baseline);
+                        x += fragment.width;
+                    }
+                } else {
+                    x += item.width();
+                }
+            }
+            self.y += height;
+        }
+    }
+
+    fn text_block(&mut self, pieces: &Vec<Piece>, align: Align) {
+        let items = self.items(pieces);
+        let lines = Self::break_lines(items, self.right - self.left);
+        self.place_lines(lines, align);
+    }
+
+    // the marker (a bullet, a note number) is put left of the first line of the next block
+    fn set_marker(&mut self, text: &str, style: &Style, x: f32) {
+        let marker = self.items(&vec![Piece { text: text.into(), style: style.clone(), anchor: None }]);
+        let fragments = marker.into_iter()
+            .filter_map(|item| if let Item::Word(fragments) = item { Some(fragments) } else { None })
+            .flatten()
+            .collect();
+        self.marker = Some((fragments, x));
+    }
+
+    //////////////////////////////////////////////////////////////////////////////////////////
+    // blocks
+
+    fn block(&mut self, element: &BlogBody, id: &String) {
+        match element {
+            BlogBody::Header(text, depth) => {
+                if self.landscape_depth.is_some_and(|landscape| *depth <= landscape) {
+                    self.landscape_depth = None;
+                    self.set_landscape(false);
+                }
+                if self.document_geometry.landscape_sections.contains(id) {
+                    self.landscape_depth = Some(*depth);
+                    self.set_landscape(true);
+                }
+                self.header(text, *depth, id)
+            },
+            BlogBody::Paragraph(text) => {
+                self.anchor(id);
+                let mut pieces = vec![];
+                self.inline(text, &self.base_style(), &mut pieces);
+                self.text_block(&pieces, Align::Left);
+                self.space(self.text_size * 0.6);
+            },
+            BlogBody::CodeBlock(text) => {
+                self.anchor(id);
+                self.code_block(text);
+                self.space(self.text_size * 0.6);
+            },
+            BlogBody::Image(src, alt) => {
+                self.anchor(id);
+                self.image(src, alt);
+                self.space(self.text_size * 0.6);
+            },
+            BlogBody::FactBox(factbox) => {
+                self.anchor(id);
+                self.factbox(factbox);
+                self.space(self.text_size * 0.6);
+            },
+            BlogBody::Quote(lines) => {
+                self.anchor(id);
+                self.quote(lines);
+                self.space(self.text_size * 0.6);
+            },
+            BlogBody::List(list) => {
+                self.anchor(id);
+                self.list(list);
+                self.space(self.text_size * 0.6);
+            },
+            BlogBody::DefinitionList(list) => {
+                self.anchor(id);
+                self.definition_list(list);
+                self.space(self.text_size * 0.6);
+            },
+            BlogBody::Blocks(blocks) => {
+                for (element, id) in blocks {
+                    self.block(element, id);
+                }
+            },
+            BlogBody::PageBreak => {
+                if !self.at_top() { self.new_page(); }
+            },
+            BlogBody::TOCLocationMarker => self.toc(),
+            BlogBody::IndexLocationMarker => self.index(),
+            element => {
+                let mut pieces = vec![];
+                self.inline(element, &self.base_style(), &mut pieces);
+                self.text_block(&pieces, Align::Left);
+                self.space(self.text_size * 0.6);
+            },
+        }
+    }
+
+    fn heading(&mut self, pieces: &Vec<Piece>, size: f32, depth: usize, id: &String) {
+        self.space(size);
+        // a header is never left alone at the bottom of a page
+        self.ensure(size * self.line_height + self.text_size * self.line_height * 3.0);
+        self.anchor(id);
+        if depth == 1 && self.pages.last().unwrap().ops.is_empty() {
+            self.chapter_pages.push(self.page());
+        }
+        if depth <= 2 {
+            let text: String = pieces.iter().map(|piece| piece.text.as_str()).collect();
+            self.heading_marks.push((self.page(), depth, text.trim().to_string()));
+        }
+        self.text_block(pieces, Align::Left);
+        self.space(size * 0.4);
+    }
+
+    fn header(&mut self, text: &Box<BlogBody>, depth: usize, id: &String) {
+        let size = HEADER_SIZES[depth.clamp(1, MAX_SECTION_DEPTH) - 1];
+        let style = Style { font: FontKind::Bold, size, ..self.base_style() };
+        let mut pieces = vec![];
+        if let Some(number) = self.md.header.section_number(id) {
+            self.piece(format!("{number} "), &style, &mut pieces);
+        }
+        self.inline(text, &style, &mut pieces);
+        self.heading(&pieces, size, depth, id);
+    }
+
+    fn title(&mut self, text: &String, id: &String) {
+        let size = HEADER_SIZES[0];
+        let style = Style { font: FontKind::Bold, size, ..self.base_style() };
+        let pieces = vec![Piece { text: text.clone(), style, anchor: None }];
+        self.heading(&pieces, size, 1, id);
+    }
+
+    // the first line of the text is the language
+    fn code_block(&mut self, text: &String) {
+        let (language, code) = text.split_once('\n').unwrap_or((text, ""));
+        let language = language.split_whitespace().next().unwrap_or("plaintext");
+        let theme = self.code_theme;
+        let style = Style { font: FontKind::Mono, size: self.text_size * 0.9, color: rgb(theme.foreground), ..self.base_style() };
+        let padding = self.text_size * 0.5;
+        self.decorations.push(Decoration { left: self.left, right: self.right, background: Some(rgb(theme.background)), border: None });
+        self.left  += padding;
+        self.right -= padding;
+        self.ensure(padding + style.size * self.line_height);
+        self.fill_decorations(self.y, self.y + padding);
+        self.y += padding;
+
+        // spaces are kept as they are, so every line of code is a single word
+        let mut lines = vec![];
+        let code = code.trim_end().replace("\r\n", "\n").replace('\t', "    ");
+        let mut words = vec![vec![]];
+        for (kind, text) in highlight(language, &code) {
+            let style = Style { color: rgb(theme.color(kind)), ..style.clone() };
+            for (n, line) in text.split('\n').enumerate() {
+                if n > 0 { words.push(vec![]) }
+                if !line.is_empty() {
+                    let fragment = self.fragment(line, &style, None);
+                    words.last_mut().unwrap().push(fragment);
+                }
+            }
+        }
+        for word in words {
+            let mut items = Self::split_word(word, self.right - self.left);
+            if items.is_empty() { items.push(Item::Break); }
+            for item in items {
+                lines.push(vec![item]);
+            }
+        }
+        let lines = lines.into_iter().map(|line| if let [Item::Break] = line.as_slice() { vec![] } else { line }).collect();
+        self.place_lines(lines, Align::Left);
+
+        self.fill_decorations(self.y, self.y + padding);
+        self.y += padding;
+        self.left  -= padding;
+        self.right += padding;
+        self.decorations.pop();
+    }
+
+    fn image(&mut self, src: &String, alt: &String) {
+        let image = match load_image(src, &self.md.header) {
+            Ok(image) => image,
+            Err(error) => {
+                self.warn(format!("could not include the image '{}' in the pdf: {}", src, error));
+                let pieces = vec![Piece { text: format!("[{alt}]"), style: Style { font: FontKind::Italic, color: MISSING_COLOR, ..self.base_style() }, anchor: None }];
+                self.text_block(&pieces, Align::Center);
+                return;
+            }
+        };
+
+        // images are 96 dpi unless they are too large to fit
+        let mut width  = image.width  as f32 * 0.75;
+        let mut height = image.height as f32 * 0.75;
+        let max_width  = self.right - self.left;
+        let max_height = (self.bottom() - self.top()) * 0.8;
+        let scale = (max_width / width).min(max_height / height).min(1.0);
+        width  *= scale;
+        height *= scale;
+
+        self.ensure(height);
+        let x = self.left + (max_width - width) / 2.0;
+        self.fill_decorations(self.y, self.y + height);
+        let index = self.images.len();
+        self.images.push(image);
+        self.push(Op::Image { image: index, x, y: self.y, width, height });
+        self.y += height;
+
+        if !alt.trim().is_empty() {
+            self.space(self.text_size * 0.3);
+            let style = Style { font: FontKind::Italic, size: self.text_size * 0.9, color: GREY, ..self.base_style() };
+            let pieces = vec![Piece { text: alt.clone(), style, anchor: None }];
+            self.text_block(&pieces, Align::Center);
+        }
+    }
+
+    fn quote(&mut self, lines: &Vec<BlogBody>) {
+        let indent = self.text_size;
+        self.decorations.push(Decoration { left: self.left, right: self.right, background: None, border: Some((BLACK, 1.5)) });
+        self.left += indent;
+        let mut pieces = vec![];
+        self.inline(&BlogBody::Quote(lines.clone()), &self.base_style(), &mut pieces);
+        self.text_block(&pieces, Align::Left);
+        self.left -= indent;
+        self.decorations.pop();
+    }
+
+    fn list(&mut self, list: &Vec<BlogBody>) {
+        let indent = self.text_size * 1.5;
+        self.left += indent;
+        for (n, element) in list.iter().enumerate() {
+            if n != 0 { self.space(self.text_size * 0.2); }
+            self.set_marker("•", &self.base_style(), self.left - indent * 0.6);
+            if is_block(element) {
+                self.block(element, &String::new());
+            } else {
+                let mut pieces = vec![];
+                self.inline(element, &self.base_style(), &mut pieces);
+                self.text_block(&pieces, Align::Left);
+            }
+        }
+        self.marker = None;
+        self.left -= indent;
+    }
+
+    fn definition_list(&mut self, list: &Vec<(BlogBody, Vec<BlogBody>)>) {
+        let indent = self.text_size * 2.0;
+        for (n, (term, definitions)) in list.iter().enumerate() {
+            if n != 0 { self.space(self.text_size * 0.3); }
+            let mut pieces = vec![];
+            self.inline(term, &Style { font: FontKind::Bold, ..self.base_style() }, &mut pieces);
+            self.text_block(&pieces, Align::Left);
+            self.left += indent;
+            for definition in definitions {
+                let mut pieces = vec![];
+                self.inline(definition, &self.base_style(), &mut pieces);
+                self.text_block(&pieces, Align::Left);
+            }
+            self.left -= indent;
+        }
+    }
+
+    fn factbox(&mut self, factbox: &FactBox) {
+        let padding = self.text_size;
+        let background = if self.factbox_depth == 0 { FACTBOX_BACKGROUND } else { NESTED_FACTBOX_BACKGROUND };
+        let (background, border) = match factbox.kind {
+            FactBoxKind::Fact    => (background, None),
+            FactBoxKind::Note    => (background, Some(((0.29, 0.48, 0.82), 4.0))),
+            FactBoxKind::Warning => ((0.99, 0.95, 0.91), Some(((0.82, 0.56, 0.29), 4.0))),
+            FactBoxKind::Tip     => (background, Some(((0.29, 0.63, 0.35), 4.0))),
+            FactBoxKind::Definition | FactBoxKind::Theorem => (background, Some(((0.33, 0.33, 0.33), 4.0))),
+            FactBoxKind::Example => (background, Some(((0.53, 0.53, 0.53), 4.0))),
+        };
+
+        self.ensure(padding * 2.0 + 14.0 * self.line_height);
+        self.decorations.push(Decoration { left: self.left, right: self.right, background: Some(background), border });
+        self.factbox_depth += 1;
+        self.left  += padding;
+        self.right -= padding;
+        self.fill_decorations(self.y, self.y + padding * 0.5);
+        self.y += padding * 0.5;
+
+        let style = Style { font: FontKind::Bold, size: 14.0, ..self.base_style() };
+        let pieces = vec![Piece { text: factbox.display_title(), style, anchor: None }];
+        self.text_block(&pieces, Align::Left);
+        self.space(padding * 0.5);
+
+        for (element, id) in &factbox.body {
+            self.block(element, id);
+        }
+
+        if !(factbox.notes.is_empty() || self.md.header.hide_notes) {
+            self.rule();
+            for (key, (note, id)) in &factbox.notes {
+                self.note(key, note, id);
+            }
+        }
+
+        if !self.at_top() {
+            self.fill_decorations(self.y, self.y + padding * 0.5);
+            self.y += padding * 0.5;
+        }
+        self.left  -= padding;
+        self.right += padding;
+        self.factbox_depth -= 1;
+        self.decorations.pop();
+    }
+
+    fn note(&mut self, key: &String, note: &BlogBody, id: &String) {
+        let indent = self.text_size * 2.0;
+        self.left += indent;
+        self.anchor(id);
+        self.anchor(&format!("^{key}"));
+        self.set_marker(key, &Self::superscript(&self.base_style()), self.left - indent);
+        if is_block(note) {
+            self.block(note, &String::new());
+        } else {
+            let mut pieces = vec![];
+            self.inline(note, &self.base_style(), &mut pieces);
+            self.text_block(&pieces, Align::Left);
+            self.space(self.text_size * 0.4);
+        }
+        self.marker = None;
+        self.left -= indent;
+    }
+
+    fn toc(&mut self) {
+        let Some(toc) = self.md.header.toc.clone() else { return };
+        self.title(&toc.title, &String::new());
+        let indent = self.text_size * 1.5;
+        for (text, depth, id) in toc.headers.iter().chain(toc.appendix.iter()) {
+            let offset = indent * depth.saturating_sub(1) as f32;
+            self.left += offset;
+            let style = Self::linked(&self.base_style(), id);
+            let mut pieces = vec![];
+            if let Some(number) = self.md.header.section_number(id) {
+                self.piece(format!("{number} "), &style, &mut pieces);
+            }
+            self.inline(text, &style, &mut pieces);
+
+            // the page number goes to the right of the last line of the entry
+            let page = self.fragment(&self.page_number(id), &style, None);
+            let right = self.right;
+            self.right -= page.width + self.text_size;
+            self.text_block(&pieces, Align::Left);
+            self.right = right;
+            let baseline = self.last_baseline;
+            self.emit_fragment(&page, right - page.width, baseline);
+            self.space(self.text_size * 0.2);
+            self.left -= offset;
+        }
+        if !self.at_top() { self.new_page(); }
+    }
+
+    fn index(&mut self) {
+        let Some(index) = self.md.header.index.clone() else { return };
+        self.title(&index.title, &"index".to_string());
+        for (term, locations) in &index.entries {
+            let style = self.base_style();
+            let mut pieces = vec![Piece { text: format!("{term}: "), style: Style { font: FontKind::Bold, ..style.clone() }, anchor: None }];
+            let pages = index_pages(locations, &self.page_numbers);
+            if pages.is_empty() {
+                self.piece("?", &style, &mut pieces);
+            }
+            for (n, (page, marker)) in pages.iter().enumerate() {
+                if n != 0 { self.piece(", ", &style, &mut pieces); }
+                self.piece((page + 1).to_string(), &Self::linked(&style, marker), &mut pieces);
+            }
+            self.text_block(&pieces, Align::Left);
+            self.space(self.text_size * 0.2);
+        }
+    }
+
+    //////////////////////////////////////////////////////////////////////////////////////////
+    // the document
+
+    fn title_page(&mut self, frontmatter: &Frontmatter, title: &Vec<TitleItem>) {
+        let md = self.md;
+        let header = &md.header;
+        self.span_columns(true);
+        if frontmatter.has("pdf-no-first-page") {
+            let style = Style { font: FontKind::Bold, size: 24.0, ..self.base_style() };
+            self.text_block(&vec![Piece { text: header.title.clone(), style, anchor: None }], Align::Left);
+            if !header.subtitle.trim().is_empty() {
+                let style = Style { size: 14.0, color: GREY, ..self.base_style() };
+                self.text_block(&vec![Piece { text: header.subtitle.clone(), style, anchor: None }], Align::Left);
+            }
+            self.space(self.text_size * 2.0);
+            // the columns start below the title
+            self.span_columns(false);
+            self.column_top = self.y;
+        } else {
+            // the lines after :::bottom are laid out below the rest and then moved down
+            let mut bottom = None;
+            for item in title {
+                match item {
+                    TitleItem::Text { text, size, align } => {
+                        let style = match size {
+                            TitleSize::Title    => Style { font: FontKind::Bold, size: 28.0, ..self.base_style() },
+                            TitleSize::Subtitle => Style { size: 16.0, color: GREY, ..self.base_style() },
+                            TitleSize::Heading  => Style { font: FontKind::Bold, size: self.text_size * 1.2, ..self.base_style() },
+                            TitleSize::Text     => self.base_style(),
+                        };
+                        let align = match align {
+                            TitleAlign::Left   => Align::Left,
+                            TitleAlign::Center => Align::Center,
+                            TitleAlign::Right  => Align::Right,
+                        };
+                        if *size == TitleSize::Title { self.space(self.text_size); }
+                        self.text_block(&vec![Piece { text: text.clone(), style, anchor: None }], align);
+                    },
+                    TitleItem::Space(space) => self.y += space,
+                    TitleItem::Image(src) => {
+                        self.space(self.text_size);
+                        self.image(src, &String::new());
+                    },
+                    TitleItem::Bottom => bottom = Some((self.pages[self.page()].ops.len(), self.y)),
+                }
+            }
+            if let Some((first, top)) = bottom {
+                let offset = (self.bottom() - self.y).max(0.0);
+                for op in &mut self.pages.last_mut().unwrap().ops[first..] {
+                    op.move_down(offset);
+                }
+                self.y = top.max(self.y);
+            }
+            self.span_columns(false);
+            self.new_page();
+        }
+    }
+
+    fn back_matter(&mut self) {
+        let md = self.md;
+        let header = &md.header;
+
+        if !(md.notes.is_empty() || header.hide_notes) {
+            self.rule();
+            self.title(&header.notes_title, &md.notes_id);
+            for (key, note) in &md.notes {
+                self.note(key, note, &String::new());
+            }
+        }
+
+        if !md.glossary.is_empty() {
+            self.rule();
+            self.title(&header.glossary_title, &md.glossary_id);
+            let list = md.glossary.iter()
+                .map(|(term, definition, _)| (BlogBody::Text(term.clone()), vec![definition.clone()]))
+                .collect();
+            for (_, _, id) in &md.glossary {
+                self.anchor(id);
+            }
+            self.definition_list(&list);
+        }
+
+        if header.list_abbreviations && !md.abbreviations.is_empty() {
+            self.rule();
+            self.title(&header.abbreviations_title, &md.abbreviations_id);
+            for (abbreviation, expansion) in &md.abbreviations {
+                let style = self.base_style();
+                let pieces = vec![
+                    Piece { text: format!("{abbreviation}: "), style: Style { font: FontKind::Bold, ..style.clone() }, anchor: None },
+                    Piece { text: expansion.clone(), style, anchor: None },
+                ];
+                self.text_block(&pieces, Align::Left);
+                self.space(self.text_size * 0.2);
+            }
+        }
+
+        if !(md.contacts.is_empty() || header.hide_contacts) {
+            self.rule();
+            self.title(&header.contacts_title, &md.contacts_id);
+            for (key, contact) in &md.contacts {
+                self.anchor(key);
+                let style = self.base_style();
+                let mut pieces = vec![Piece { text: format!("{}:", contact.name), style: Style { font: FontKind::Bold, ..style.clone() }, anchor: None }];
+                for (title, values) in [("address", &contact.address), ("tel", &contact.phone), ("email", &contact.email), ("url", &contact.website)] {
+                    if values.is_empty() { continue }
+                    let values: Vec<&str> = values.iter().map(|value| value.trim()).collect();
+                    self.piece(format!("{LINE_BREAK}{title}: {}", values.join(", ")), &style, &mut pieces);
+                }
+                self.text_block(&pieces, Align::Left);
+                self.space(self.text_size * 0.6);
+            }
+        }
+
+        if !(md.references.is_empty() || header.hide_references) {
+            for (key, _) in &md.references {
+                if !self.used_references.contains(key) {
+                    self.warn(format!("reference '{}' is not used and will not be included", key));
+                }
+            }
+            if !self.used_references.is_empty() {
+                self.rule();
+                self.title(&header.bibliography_title, &md.bibliography_id);
+                for (key, reference) in &md.references {
+                    if !self.used_references.contains(key) { continue }
+                    self.anchor(key);
+                    let pieces = vec![Piece { text: to_bibliography(reference), style: self.base_style(), anchor: None }];
+                    self.text_block(&pieces, Align::Left);
+                    self.space(self.text_size * 0.6);
+                }
+            }
+        }
+
+        if !md.appendix.is_empty() {
+            if !self.at_top() { self.new_page(); }
+            self.title(&header.appendix_title, &md.appendix_id);
+            for (element, id) in &md.appendix {
+                self.block(element, id);
+            }
+        }
+    }
+
+    // the chapter and section shown in the headers of every page, which is the first one that starts
+    // on the page or else the one continued from the pages before
+    fn running_headings(&self) -> Vec<(String, String)> {
+        let mut running = vec![];
+        let (mut chapter, mut section) = (String::new(), String::new());
+        for page in 0..self.pages.len() {
+            let marks: Vec<_> = self.heading_marks.iter().filter(|(mark_page, _, _)| *mark_page == page).collect();
+            let first = |depth: usize| marks.iter().find(|(_, mark_depth, _)| *mark_depth == depth).map(|(_, _, text)| text.clone());
+            let page_chapter = first(1).unwrap_or(chapter.clone());
+            let page_section = first(2).unwrap_or(if first(1).is_some() { String::new() } else { section.clone() });
+            running.push((page_chapter, page_section));
+            for (_, depth, text) in marks {
+                if *depth == 1 {
+                    chapter = text.clone();
+                    section.clear();
+                } else {
+                    section = text.clone();
+                }
+            }
+        }
+        running
+    }
+
+    // headers and footers are added last, when the number of pages is known. the headers are left
+    // out on the title page and on the pages that open with a chapter heading
+    fn page_furniture(&mut self, frontmatter: &Frontmatter) {
+        let furniture = PageFurniture::from_frontmatter(frontmatter);
+        let running = self.running_headings();
+        let pages = self.pages.len();
+        let first = if frontmatter.has("pdf-no-first-page") { 0 } else { 1 };
+        let style = Style { size: 9.0, color: GREY, ..self.base_style() };
+        self.decorations.clear();
+
+        for page in first..pages {
+            let geometry = self.document_geometry.with_size(self.pages[page].width, self.pages[page].height);
+            let header_baseline = geometry.top / 2.0;
+            let footer_baseline = geometry.height - geometry.bottom / 2.0;
+            (self.left, self.right) = geometry.text_area(page);
+            let chapter_opening = self.chapter_pages.contains(&page);
+            let (header, footer) = furniture.rows(page);
+            let empty = [String::new(), String::new(), String::new()];
+            let header = if chapter_opening { &empty } else { header };
+            for (texts, baseline) in [(header, header_baseline), (footer, footer_baseline)] {
+                for (text, align) in texts.iter().zip([Align::Left, Align::Center, Align::Right]) {
+                    if text.trim().is_empty() { continue }
+                    let (chapter, section) = &running[page];
+                    let text = substitute_page_numbers(&substitute_headings(text, chapter, section), page + 1, pages);
+                    let (items, width) = self.loose_line(text, &style);
+                    let x = match align {
+                        Align::Left   => self.left,
+                        Align::Center => (self.left + self.right - width) / 2.0,
+                        Align::Right  => self.right - width,
+                    };
+                    let ops = Self::loose_ops(&items, &style, x, baseline, 0.0);
+                    self.pages[page].ops.extend(ops);
+                }
+            }
+        }
+        if self.draft {
+            self.draft_furniture(first);
+        }
+    }
+
+    // the watermark goes under everything else on every page. the lines are numbered from the top
+    // of each page, so a comment can point at e.g. page 4, line 12
+    fn draft_furniture(&mut self, first: usize) {
+        let watermark_style = Style { font: FontKind::Bold, size: 72.0, color: WATERMARK_COLOR, ..self.base_style() };
+        let number_style = Style { size: 7.0, color: GREY, ..self.base_style() };
+        let timestamp_style = Style { size: 7.0, color: GREY, ..self.base_style() };
+        let (watermark, watermark_width) = self.loose_line(DRAFT_WATERMARK.into(), &watermark_style);
+        let (timestamp, timestamp_width) = self.loose_line(draft_timestamp(), &timestamp_style);
+        let numbered_lines = std::mem::take(&mut self.numbered_lines);
+
+        for page in 0..self.pages.len() {
+            let (width, height) = (self.pages[page].width, self.pages[page].height);
+            let angle = (height / width).atan();
+            let (sin, cos) = angle.sin_cos();
+            let x = width / 2.0 - watermark_width / 2.0 * cos;
+            let y = height / 2.0 + watermark_width / 2.0 * sin;
+            let ops = Self::loose_ops(&watermark, &watermark_style, x, y, angle.to_degrees());
+            self.pages[page].ops.splice(0..0, ops);
+
+            if page < first { continue }
+            let mut number = 0;
+            for (line_page, left, baseline) in &numbered_lines {
+                if *line_page != page { continue }
+                number += 1;
+                let (items, number_width) = self.loose_line(number.to_string(), &number_style);
+                let ops = Self::loose_ops(&items, &number_style, left - number_width - 8.0, *baseline, 0.0);
+                self.pages[page].ops.extend(ops);
+            }
+
+            let geometry = self.document_geometry.with_size(width, height);
+            let baseline = height - geometry.bottom / 2.0 + 10.0;
+            let ops = Self::loose_ops(&timestamp, &timestamp_style, (width - timestamp_width) / 2.0, baseline, 0.0);
+            self.pages[page].ops.extend(ops);
+        }
+    }
+
+    // a single line of text outside of the flow of the document, as in the headers, and its width
+    fn loose_line(&mut self, text: String, style: &Style) -> (Vec<Item>, f32) {
+        let items = self.items(&vec![Piece { text, style: style.clone(), anchor: None }]);
+        let width = items.iter().map(|item| item.width()).sum();
+        (items, width)
+    }
+
+    fn loose_ops(items: &Vec<Item>, style: &Style, x: f32, baseline: f32, angle: f32) -> Vec<Op> {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let (mut x, mut y) = (x, baseline);
+        let mut ops = vec![];
+        for item in items {
+            if let Item::Word(fragments) = item {
+                for fragment in fragments {
+                    ops.push(Op::Text { x, y, angle, font: style.font, size: style.size, color: style.color, glyphs: fragment.glyphs.clone() });
+                    x += fragment.width * cos;
+                    y -= fragment.width * sin;
+                }
+            } else {
+                x += item.width() * cos;
+                y -= item.width() * sin;
+            }
+        }
+        ops
+    }
+}
+
+fn layout_pass<'a, 'f>(md: &'a PawsMarkdown, fonts: &'a FontSet<'f>, frontmatter: &Frontmatter, title: &Vec<TitleItem>, page_numbers: HashMap<String, usize>) -> Layout<'a, 'f> {
+    let mut layout = Layout::new(md, fonts, frontmatter, page_numbers);
+
+    layout.title_page(frontmatter, title);
+    for (element, id) in &md.body {
+        layout.block(element, id);
+    }
+    // landscape sections end with the body of the document
+    layout.landscape_depth = None;
+    layout.set_landscape(false);
+    layout.back_matter();
+
+    // a page break at the very end leaves an empty page behind
+    if layout.pages.len() > 1 && layout.pages.last().is_some_and(|page| page.ops.is_empty()) {
+        layout.pages.pop();
+    }
+    layout
+}
+
+// the page numbers in the toc and cross-references are only known after the layout, so the
+// document is laid out again with the pages from the last pass until nothing moves
+pub fn layout(md: &PawsMarkdown, fonts: &FontSet) -> Result<LaidOutDocument> {
+    let frontmatter = md.header.frontmatter.clone().unwrap_or(Frontmatter::new());
+    let title = title_page(md);
+    let mut page_numbers = HashMap::new();
+    let mut layout = layout_pass(md, fonts, &frontmatter, &title, page_numbers.clone());
+    let mut passes = 1;
+    loop {
+        let pages: HashMap<String, usize> = layout.anchors.iter().map(|(id, (page, _))| (id.clone(), *page)).collect();
+        if pages == page_numbers { break }
+        if passes == PDF_LAYOUT_PASSES {
+            layout.warn(format!("the page numbers still changed after {} layout passes, some page references may be wrong", PDF_LAYOUT_PASSES));
+            break;
+        }
+        page_numbers = pages;
+        layout = layout_pass(md, fonts, &frontmatter, &title, page_numbers.clone());
+        passes += 1;
+    }
+
+    for id in &layout.document_geometry.landscape_sections {
+        if !layout.anchors.contains_key(id) {
+            layout.warnings.push(format!("there is no section '{}' to put on landscape pages", id));
+        }
+    }
+    for _warning in &layout.warnings {
+        #[cfg(not(feature = "wasm"))]
+        cprintln!("<y>warning:</> {}", _warning);
+    }
+    layout.page_furniture(&frontmatter);
+
+    let outline = outline(md).into_iter().filter(|(_, _, id)| layout.anchors.contains_key(id)).collect();
+    Ok(LaidOutDocument { pages: layout.pages, images: layout.images, anchors: layout.anchors, outline })
+}
+
+// the bookmarks follow the table of contents, or every header when there is none
+fn outline(md: &PawsMarkdown) -> Vec<(String, usize, String)> {
+    let header = &md.header;
+    let headers: Vec<(&BlogBody, usize, &String)> = if let Some(toc) = &header.toc {
+        toc.headers.iter().chain(toc.appendix.iter())
+            .map(|(text, depth, id)| (text.as_ref(), *depth, id))
+            .collect()
+    } else {
+        md.body.iter().chain(md.appendix.iter())
+            .filter_map(|(element, id)| match element {
+                BlogBody::Header(text, depth) => Some((text.as_ref(), *depth, id)),
+                _ => None,
+            })
+            .collect()
+    };
+
+    headers.into_iter()
+        .map(|(text, depth, id)| {
+            let text = match text {
+                BlogBody::FactBox(factbox) => factbox.display_title(),
+                text => text_of(text),
+            };
+            let title = match header.section_number(id) {
+                Some(number) => format!("{number} {text}"),
+                None => text,
+            };
+            (title, depth, id.clone())
+        })
+        .collect()
+}
+
+// the pages a term is on in the previous pass, each once and in order, with the first marker on the page
+fn index_pages(locations: &[(String, String)], page_numbers: &HashMap<String, usize>) -> Vec<(usize, String)> {
+    let mut pages: Vec<(usize, String)> = locations.iter()
+        .filter_map(|(marker, _)| page_numbers.get(marker).map(|page| (*page, marker.clone())))
+        .collect();
+    pages.sort_by_key(|(page, _)| *page);
+    pages.dedup_by_key(|(page, _)| *page);
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf_layout::*;
+
+    #[test]
+    fn test_index_pages() {
+        let locations: Vec<(String, String)> = ["a", "b", "c", "d"].iter().map(|marker| (marker.to_string(), String::new())).collect();
+        let page_numbers = HashMap::from([("a".to_string(), 4), ("b".to_string(), 1), ("c".to_string(), 4)]);
+        assert_eq!(index_pages(&locations, &page_numbers), vec![(1, "b".to_string()), (4, "a".to_string())]);
+    }
+
+    #[test]
+    fn test_page_numbers() {
+        assert_eq!(substitute_page_numbers("%p of %np", 2, 7), "2 of 7");
+        assert_eq!(substitute_page_numbers("side %page af %pages", 3, 4), "side 3 af 4");
+    }
+
+    #[test]
+    fn test_even_page_furniture() {
+        let (frontmatter, _) = parse_frontmatter("---\ntitle: Report\npdf-header-left: %chapter\npdf-header-even-right: %title\n---\n");
+        let furniture = PageFurniture::from_frontmatter(&frontmatter.unwrap());
+        assert_eq!(furniture.rows(0).0[0], "%chapter");
+        assert_eq!(furniture.rows(1).0[2], "Report");
+        assert_eq!(furniture.rows(1).1, furniture.rows(0).1);
+        assert_eq!(substitute_headings("%chapter: %section", "1 Intro", "1.2 Method"), "1 Intro: 1.2 Method");
+    }
+
+    #[test]
+    fn test_page_references() {
+        let text = "---\ntitle: Report\npdf-no-first-page: true\n---\n#[toc] Contents\nsee [](#fig)\n\n# Intro\nsome text\n\n---\n\n# Method\n> a quote\n> {#fig}\n".to_string();
+        let md = parse(&text, None).unwrap();
+        let font_data = load_fonts(&md.header).unwrap();
+        let fonts = FontSet::new(&font_data).unwrap();
+        let document = layout(&md, &fonts).unwrap();
+        let words = |page: &Page| -> Vec<String> {
+            page.ops.iter().filter_map(|op| match op {
+                Op::Text { glyphs, .. } => Some(glyphs.iter().map(|glyph| glyph.text.clone()).collect()),
+                _ => None,
+            }).collect()
+        };
+
+        // the references are filled in from the pages of the previous pass
+        assert_eq!(document.pages.len(), 3);
+        assert_eq!(words(&document.pages[0]), vec!["Report", "Contents", "Intro", "2", "Method", "3"]);
+        assert_eq!(words(&document.pages[1])[..3], ["see", "page", "3"]);
+        assert_eq!(document.anchors["fig"].0, 2);
+    }
+
+    #[test]
+    fn test_chapter_headers() {
+        let text = "---\ntitle: Report\npdf-no-first-page: true\npdf-header-right: %chapter\n---\n# Intro\ntext\n\n# Method\ntext\n\n---\n\n# Results\ntext\n".to_string();
+        let md = parse(&text, None).unwrap();
+        let font_data = load_fonts(&md.header).unwrap();
+        let fonts = FontSet::new(&font_data).unwrap();
+        let document = layout(&md, &fonts).unwrap();
+        let words = |page: &Page| -> Vec<String> {
+            page.ops.iter().filter_map(|op| match op {
+                Op::Text { glyphs, .. } => Some(glyphs.iter().map(|glyph| glyph.text.clone()).collect()),
+                _ => None,
+            }).collect()
+        };
+
+        // only the page that opens with a chapter leaves out the header
+        assert_eq!(document.pages.len(), 2);
+        assert_eq!(words(&document.pages[0]), vec!["Report", "Intro", "text", "Method", "text", "Intro"]);
+        assert_eq!(words(&document.pages[1]), vec!["Results", "text"]);
+    }
+
+    #[test]
+    fn test_internal_links() {
+        let text = "---\ntitle: Report\npdf-no-first-page: true\n---\ntext[^1] as in [£baudrillard] and [the book](£baudrillard)\n\n[^1]: the note\n\n£baudrillard {\n    title: Simulacra and Simulation,\n    author: Jean Baudrillard,\n    year: 1994,\n}\n".to_string();
+        let md = parse(&text, None).unwrap();
+        let font_data = load_fonts(&md.header).unwrap();
+        let fonts = FontSet::new(&font_data).unwrap();
+        let document = layout(&md, &fonts).unwrap();
+
+        let targets: Vec<&LinkTarget> = document.pages.iter().flat_map(|page| &page.ops).filter_map(|op| match op {
+            Op::Link { target, .. } => Some(target),
+            _ => None,
+        }).collect();
+        assert!(targets.contains(&&LinkTarget::Anchor("^1".into())));
+        assert!(targets.contains(&&LinkTarget::Anchor("baudrillard".into())));
+        assert!(targets.iter().all(|target| matches!(target, LinkTarget::Anchor(_))));
+    }
+
+    #[test]
+    fn test_break_lines() {
+        let style = Style { font: FontKind::Regular, size: 10.0, color: BLACK, rise: 0.0, underline: false, strike: false, background: None, link: None };
+        let word = |width: f32| Item::Word(vec![Fragment {
+            glyphs: vec![Glyph { id: 1, advance: width / 10.0, x_offset: 0.0, y_offset: 0.0, text: "a".into() }],
+            style: style.clone(), width, anchor: None,
+        }]);
+        let items = vec![word(40.0), Item::Space(5.0), word(40.0), Item::Space(5.0), word(40.0), Item::Break, word(10.0)];
+        let lines = Layout::break_lines(items, 90.0);
+        let widths: Vec<f32> = lines.iter().map(|line| line.iter().map(|item| item.width()).sum()).collect();
+        assert_eq!(widths, vec![85.0, 40.0, 10.0]);
+    }
+}
@@ -0,0 +1,509 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use pdf_writer::{Content, Date, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use pdf_writer::types::{ActionType, AnnotationType, PageMode};
+use pdf_fonts::*;
+use pdf_layout::*;
+use crate::*;
+
+pub enum ImageData {
+    // jpegs are embedded as they are, pdf readers decode them
+    Jpeg { data: Vec<u8>, components: u8 },
+    Pixels { color: Vec<u8>, alpha: Option<Vec<u8>>, gray: bool },
+}
+
+pub struct PdfImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: ImageData,
+}
+
+fn jpeg_size(data: &[u8]) -> Option<(u32, u32, u8)> {
+    let mut n = 2;
+    while n + 9 < data.len() {
+        if data[n] != 0xFF { return None }
+        let marker = data[n + 1];
+        let length = u16::from_be_bytes([data[n + 2], data[n + 3]]) as usize;
+        // the start of frame markers, except for the ones that are huffman or arithmetic tables
+        if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+            let height = u16::from_be_bytes([data[n + 5], data[n + 6]]) as u32;
+            let width  = u16::from_be_bytes([data[n + 7], data[n + 8]]) as u32;
+            return Some((width, height, data[n + 9]));
+        }
+        n += 2 + length;
+    }
+    None
+}
+
+fn load_png(data: &[u8]) -> Result<PdfImage> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+
+    let split = |channels: usize| -> (Vec<u8>, Vec<u8>) {
+        let mut color = Vec::with_capacity(buffer.len());
+        let mut alpha = Vec::with_capacity(buffer.len() / channels);
+        for pixel in buffer.chunks(channels) {
+            color.extend_from_slice(&pixel[..channels - 1]);
+            alpha.push(pixel[channels - 1]);
+        }
+        (color, alpha)
+    };
+    let data = match info.color_type {
+        png::ColorType::Rgb       => ImageData::Pixels { color: buffer.clone(), alpha: None, gray: false },
+        png::ColorType::Grayscale => ImageData::Pixels { color: buffer.clone(), alpha: None, gray: true },
+        png::ColorType::Rgba => {
+            let (color, alpha) = split(4);
+            ImageData::Pixels { color, alpha: Some(alpha), gray: false }
+        },
+        png::ColorType::GrayscaleAlpha => {
+            let (color, alpha) = split(2);
+            ImageData::Pixels { color, alpha: Some(alpha), gray: true }
+        },
+        png::ColorType::Indexed => return Err(anyhow!("indexed colors were not expanded")),
+    };
+    Ok(PdfImage { width: info.width, height: info.height, data })
+}
+
+// images are read relative to the markdown file, like the other files it refers to
+pub fn load_image(src: &String, header: &BlogHeader) -> Result<PdfImage> {
+    if src.starts_with("http://") || src.starts_with("https://") {
+        return Err(anyhow!("images from the internet can't be embedded, download it and link to the file"));
+    }
+    let data = std::fs::read(header.resolve_path(src))?;
+    if data.starts_with(&[0x89, b'P', b'N', b'G']) {
+        load_png(&data)
+    } else if data.starts_with(&[0xFF, 0xD8]) {
+        let (width, height, components) = jpeg_size(&data).ok_or(anyhow!("the jpeg has no size"))?;
+        Ok(PdfImage { width, height, data: ImageData::Jpeg { data, components } })
+    } else {
+        Err(anyhow!("only png and jpeg images are supported"))
+    }
+}
+
+fn font_name(kind: FontKind) -> String {
+    format!("F{}", kind.index())
+}
+
+fn image_name(image: usize) -> String {
+    format!("Im{image}")
+}
+
+fn compress(data: &[u8]) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(data, 6)
+}
+
+// y is the baseline in pdf coordinates, which start in the bottom left corner
+fn write_text(content: &mut Content, fonts: &FontSet, x: f32, y: f32, angle: f32, font: FontKind, size: f32, color: Color, glyphs: &Vec<Glyph>) {
+    content.begin_text();
+    content.set_font(Name(font_name(font).as_bytes()), size);
+    content.set_fill_rgb(color.0, color.1, color.2);
+    let (sin, cos) = angle.to_radians().sin_cos();
+    content.set_text_matrix([cos, sin, -sin, cos, x, y]);
+
+    // the viewer advances by the widths in the font, kerning and offsets from shaping are
+    // written as adjustments in thousandths of an em
+    let mut shown = content.show_positioned();
+    let mut items = shown.items();
+    let mut run = Vec::<u8>::new();
+    let mut adjustment = 0.0;
+    for glyph in glyphs {
+        adjustment -= glyph.x_offset * 1000.0;
+        if adjustment.abs() > 0.01 {
+            if !run.is_empty() {
+                items.show(Str(&run));
+                run.clear();
+            }
+            items.adjust(adjustment);
+        }
+        run.extend_from_slice(&glyph.id.to_be_bytes());
+        adjustment = (fonts.default_advance(font, glyph.id) - glyph.advance + glyph.x_offset) * 1000.0;
+    }
+    if !run.is_empty() {
+        items.show(Str(&run));
+    }
+    items.finish();
+    shown.finish();
+    content.end_text();
+}
+
+fn write_image(pdf: &mut Pdf, next_ref: &mut Ref, image_ref: Ref, image: &PdfImage) {
+    match &image.data {
+        ImageData::Jpeg { data, components } => {
+            let mut xobject = pdf.image_xobject(image_ref, data);
+            xobject.filter(Filter::DctDecode);
+            xobject.width(image.width as i32)
+                .height(image.height as i32)
+                .bits_per_component(8)
+                .color_space_name(Name(match components {
+                    1 => b"DeviceGray".as_slice(),
+                    4 => b"DeviceCMYK".as_slice(),
+                    _ => b"DeviceRGB".as_slice(),
+                }));
+            // adobe writes cmyk jpegs inverted
+            if *components == 4 {
+                xobject.decode([1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+            }
+            xobject.finish();
+        },
+        ImageData::Pixels { color, alpha, gray } => {
+            let mask_ref = alpha.as_ref().map(|_| next_ref.bump());
+            let data = compress(color);
+            let mut xobject = pdf.image_xobject(image_ref, &data);
+            xobject.filter(Filter::FlateDecode);
+            xobject.width(image.width as i32)
+                .height(image.height as i32)
+                .bits_per_component(8)
+                .color_space_name(Name(if *gray { b"DeviceGray".as_slice() } else { b"DeviceRGB".as_slice() }));
+            if let Some(mask_ref) = mask_ref {
+                xobject.s_mask(mask_ref);
+            }
+            xobject.finish();
+
+            if let (Some(alpha), Some(mask_ref)) = (alpha, mask_ref) {
+                let data = compress(alpha);
+                let mut mask = pdf.image_xobject(mask_ref, &data);
+                mask.filter(Filter::FlateDecode);
+                mask.width(image.width as i32)
+                    .height(image.height as i32)
+                    .bits_per_component(8)
+                    .color_space_name(Name(b"DeviceGray"));
+            }
+        },
+    }
+}
+
+fn pdf_date(date: &PmdDate) -> Option<Date> {
+    let (day, month, year) = date.split_date();
+    let mut result = Date::new(year? as u16);
+    if let Some(month) = month {
+        result = result.month(month.to_chrono_month().number_from_month() as u8);
+    }
+    if let Some(day) = day {
+        result = result.day(day as u8);
+    }
+    Some(result)
+}
+
+// the title, author and so on that pdf readers show in the document properties
+fn write_info(pdf: &mut Pdf, info_ref: Ref, md: &PawsMarkdown) {
+    let header = &md.header;
+    let frontmatter = header.frontmatter.clone().unwrap_or(Frontmatter::new());
+    let mut authors = frontmatter["author"].as_list();
+    authors.extend(frontmatter["authors"].as_list());
+    let keywords = frontmatter["keywords"].as_list();
+
+    let mut info = pdf.document_info(info_ref);
+    if !header.title.trim().is_empty() {
+        info.title(TextStr(header.title.trim()));
+    }
+    if !header.subtitle.trim().is_empty() {
+        info.subject(TextStr(header.subtitle.trim()));
+    }
+    if !authors.is_empty() {
+        info.author(TextStr(authors.join(", ").as_str()));
+    }
+    if !keywords.is_empty() {
+        info.keywords(TextStr(keywords.join(", ").as_str()));
+    }
+    info.creator(TextStr("pmd-parser"));
+    if let Some(date) = pdf_date(&header.date_written) {
+        info.creation_date(date);
+    }
+    if let Some(date) = pdf_date(&header.last_update) {
+        info.modified_date(date);
+    }
+    info.finish();
+}
+
+// the bookmarks are a tree, where every item points to its parent, siblings and children
+// where an outline item sits in the tree, as indices into the outline. count is the number of
+// items below it, which the viewer shows when the item is opened
+#[derive(Debug, PartialEq)]
+struct OutlineLinks {
+    parent: Option<usize>,
+    prev: Option<usize>,
+    next: Option<usize>,
+    children: Option<(/*first: */ usize, /*last: */ usize)>,
+    count: i32,
+}
+
+fn outline_links(depths: &[usize]) -> Vec<OutlineLinks> {
+    let mut parents = vec![None; depths.len()];
+    let mut stack: Vec<usize> = vec![];
+    for (n, depth) in depths.iter().enumerate() {
+        while stack.last().is_some_and(|&parent| depths[parent] >= *depth) {
+            stack.pop();
+        }
+        parents[n] = stack.last().copied();
+        stack.push(n);
+    }
+    let children = |parent: Option<usize>| -> Vec<usize> {
+        (0..depths.len()).filter(|&n| parents[n] == parent).collect()
+    };
+    // the items below an item are the ones after it that are deeper
+    let descendants = |item: usize| -> i32 {
+        depths[item + 1..].iter().take_while(|depth| **depth > depths[item]).count() as i32
+    };
+
+    (0..depths.len()).map(|n| {
+        let siblings = children(parents[n]);
+        let position = siblings.iter().position(|&sibling| sibling == n).unwrap();
+        let own = children(Some(n));
+        OutlineLinks {
+            parent: parents[n],
+            prev: if position > 0 { Some(siblings[position - 1]) } else { None },
+            next: siblings.get(position + 1).copied(),
+            children: own.first().zip(own.last()).map(|(first, last)| (*first, *last)),
+            count: if own.is_empty() { 0 } else { descendants(n) },
+        }
+    }).collect()
+}
+
+fn write_outline(pdf: &mut Pdf, next_ref: &mut Ref, outline_ref: Ref, outline: &Vec<(String, usize, String)>) {
+    let refs: Vec<Ref> = outline.iter().map(|_| next_ref.bump()).collect();
+    let depths: Vec<usize> = outline.iter().map(|(_, depth, _)| *depth).collect();
+    let links = outline_links(&depths);
+
+    let top: Vec<usize> = (0..links.len()).filter(|&n| links[n].parent.is_none()).collect();
+    let mut root = pdf.outline(outline_ref);
+    if let (Some(first), Some(last)) = (top.first(), top.last()) {
+        root.first(refs[*first]).last(refs[*last]);
+    }
+    root.count(outline.len() as i32);
+    root.finish();
+
+    for (n, ((title, _, id), links)) in outline.iter().zip(&links).enumerate() {
+        let mut item = pdf.outline_item(refs[n]);
+        item.title(TextStr(title.trim()));
+        item.parent(links.parent.map(|parent| refs[parent]).unwrap_or(outline_ref));
+        if let Some(prev) = links.prev {
+            item.prev(refs[prev]);
+        }
+        if let Some(next) = links.next {
+            item.next(refs[next]);
+        }
+        if let Some((first, last)) = links.children {
+            item.first(refs[first]).last(refs[last]).count(links.count);
+        }
+        item.dest_name(Name(id.as_bytes()));
+        item.finish();
+    }
+}
+
+pub fn write_pdf(md: &PawsMarkdown, document: &LaidOutDocument, fonts: &FontSet, font_data: &Vec<FontData>) -> Result<Vec<u8>> {
+    let mut next_ref = Ref::new(1);
+    let catalog_ref = next_ref.bump();
+    let tree_ref = next_ref.bump();
+    let font_refs: Vec<Ref> = FontKind::ALL.iter().map(|_| next_ref.bump()).collect();
+    let image_refs: Vec<Ref> = document.images.iter().map(|_| next_ref.bump()).collect();
+    let page_refs: Vec<Ref> = document.pages.iter().map(|_| next_ref.bump()).collect();
+    let destinations_ref = next_ref.bump();
+    let outline_ref = next_ref.bump();
+    let info_ref = next_ref.bump();
+
+    // the glyphs used from each font and the text they stand for
+    let mut used_glyphs: Vec<BTreeMap<u16, String>> = FontKind::ALL.iter().map(|_| BTreeMap::new()).collect();
+    for page in &document.pages {
+        for op in &page.ops {
+            let Op::Text { font, glyphs, .. } = op else { continue };
+            for glyph in glyphs {
+                let text = used_glyphs[font.index()].entry(glyph.id).or_default();
+                if text.is_empty() {
+                    *text = glyph.text.clone();
+                }
+            }
+        }
+    }
+
+    let mut pdf = Pdf::new();
+    let mut catalog = pdf.catalog(catalog_ref);
+    catalog.pages(tree_ref).destinations(destinations_ref);
+    if !document.outline.is_empty() {
+        catalog.outlines(outline_ref).page_mode(PageMode::UseOutlines);
+    }
+    catalog.finish();
+    pdf.pages(tree_ref).kids(page_refs.iter().copied()).count(page_refs.len() as i32);
+
+    for (page, page_ref) in document.pages.iter().zip(&page_refs) {
+        let content_ref = next_ref.bump();
+        let page_height = page.height;
+        let mut content = Content::new();
+        let mut links = vec![];
+        for op in &page.ops {
+            match op {
+                Op::Text { x, y, angle, font, size, color, glyphs } => write_text(&mut content, fonts, *x, page_height - y, *angle, *font, *size, *color, glyphs),
+                Op::Rect { x, y, width, height, color } => {
+                    content.set_fill_rgb(color.0, color.1, color.2);
+                    content.rect(*x, page_height - y - height, *width, *height);
+                    content.fill_nonzero();
+                },
+                Op::Line { x1, y1, x2, y2, width, color } => {
+                    content.set_stroke_rgb(color.0, color.1, color.2);
+                    content.set_line_width(*width);
+                    content.move_to(*x1, page_height - y1);
+                    content.line_to(*x2, page_height - y2);
+                    content.stroke();
+                },
+                Op::Image { image, x, y, width, height } => {
+                    content.save_state();
+                    content.transform([*width, 0.0, 0.0, *height, *x, page_height - y - height]);
+                    content.x_object(Name(image_name(*image).as_bytes()));
+                    content.restore_state();
+                },
+                Op::Link { x, y, width, height, target } => {
+                    links.push((Rect::new(*x, page_height - y - height, x + width, page_height - y), target));
+                },
+            }
+        }
+        let content = compress(&content.finish());
+        pdf.stream(content_ref, &content).filter(Filter::FlateDecode);
+
+        let mut page_writer = pdf.page(*page_ref);
+        page_writer.parent(tree_ref)
+            .media_box(Rect::new(0.0, 0.0, page.width, page.height))
+            .contents(content_ref);
+
+        let mut resources = page_writer.resources();
+        let mut font_dictionary = resources.fonts();
+        for kind in FontKind::ALL {
+            if !used_glyphs[kind.index()].is_empty() {
+                font_dictionary.pair(Name(font_name(kind).as_bytes()), font_refs[kind.index()]);
+            }
+        }
+        font_dictionary.finish();
+        let mut images = resources.x_objects();
+        for (n, image_ref) in image_refs.iter().enumerate() {
+            images.pair(Name(image_name(n).as_bytes()), *image_ref);
+        }
+        images.finish();
+        resources.finish();
+
+        let mut annotations = page_writer.annotations();
+        for (rect, target) in links {
+            let mut annotation = annotations.push();
+            annotation.subtype(AnnotationType::Link).rect(rect).border(0.0, 0.0, 0.0, None);
+            match target {
+                LinkTarget::Url(url) => {
+                    annotation.action().action_type(ActionType::Uri).uri(Str(url.as_bytes()));
+                },
+                LinkTarget::Anchor(id) => {
+                    annotation.action().action_type(ActionType::GoTo).destination_named(Name(id.as_bytes()));
+                },
+            }
+        }
+        annotations.finish();
+        page_writer.finish();
+    }
+
+    // every header, note and definition is a named destination, so links like report.pdf#introduction work
+    let mut destinations = pdf.destinations(destinations_ref);
+    let mut anchors: Vec<_> = document.anchors.iter().collect();
+    anchors.sort_by(|a, b| a.0.cmp(b.0));
+    for (id, (page, y)) in anchors {
+        destinations.insert(Name(id.as_bytes())).page(page_refs[*page]).xyz(0.0, document.pages[*page].height - y, None);
+    }
+    destinations.finish();
+
+    if !document.outline.is_empty() {
+        write_outline(&mut pdf, &mut next_ref, outline_ref, &document.outline);
+    }
+    write_info(&mut pdf, info_ref, md);
+
+    for kind in FontKind::ALL {
+        let glyphs = &used_glyphs[kind.index()];
+        if glyphs.is_empty() { continue }
+        fonts.write_font(&mut pdf, &mut next_ref, font_refs[kind.index()], kind, &font_data[kind.index()], glyphs)?;
+    }
+
+    for (image, image_ref) in document.images.iter().zip(&image_refs) {
+        write_image(&mut pdf, &mut next_ref, *image_ref, image);
+    }
+
+    Ok(pdf.finish())
+}
+
+// lays the document out and writes it as a pdf, without going through html
+pub fn to_pdf(md: &PawsMarkdown) -> Result<Vec<u8>> {
+    let font_data = load_fonts(&md.header)?;
+    let fonts = FontSet::new(&font_data)?;
+    let document = layout(md, &fonts)?;
+    fonts.warn_missing();
+    write_pdf(md, &document, &fonts, &font_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf_native::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn test_jpeg_size() {
+        // a start of image marker, an app0 segment and then the start of frame
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00];
+        data.extend([0xFF, 0xC0, 0x00, 0x11, 0x08, 0x01, 0x2C, 0x02, 0x58, 0x03, 0x00, 0x00, 0x00]);
+        assert_eq!(jpeg_size(&data), Some((600, 300, 3)));
+    }
+
+    #[test]
+    fn test_outline_links() {
+        let links = outline_links(&[1, 2, 2, 1, 3]);
+        assert_eq!(links, vec![
+            OutlineLinks { parent: None,    prev: None,    next: Some(3), children: Some((1, 2)), count: 2 },
+            OutlineLinks { parent: Some(0), prev: None,    next: Some(2), children: None,         count: 0 },
+            OutlineLinks { parent: Some(0), prev: Some(1), next: None,    children: None,         count: 0 },
+            OutlineLinks { parent: None,    prev: Some(0), next: None,    children: Some((4, 4)), count: 1 },
+            OutlineLinks { parent: Some(3), prev: None,    next: None,    children: None,         count: 0 },
+        ]);
+    }
+
+    #[test]
+    fn test_info() {
+        let text = "---\ntitle: Report\nsubtitle: On things\nauthors:\n    - Ann\n    - Bo\nkeywords:\n    - pdf\n---\ntext\n".to_string();
+        let md = parse(&text, None).unwrap();
+        let mut pdf = Pdf::new();
+        write_info(&mut pdf, Ref::new(1), &md);
+        let info = String::from_utf8_lossy(&pdf.finish()).to_string();
+        assert!(info.contains("/Title (Report)"));
+        assert!(info.contains("/Subject (On things)"));
+        assert!(info.contains("/Author (Ann, Bo)"));
+        assert!(info.contains("/Keywords (pdf)"));
+        assert!(info.contains("/Creator (pmd-parser)"));
+    }
+
+    #[test]
+    fn test_to_pdf() {
+        let text = "---\ntitle: Report\npdf-no-first-page: true\n---\n# Intro\nsome text\n\n---\n\n# Method {#method}\nmore text\n".to_string();
+        let md = parse(&text, None).unwrap();
+
+        let font_data = load_fonts(&md.header).unwrap();
+        let fonts = FontSet::new(&font_data).unwrap();
+        let document = layout(&md, &fonts).unwrap();
+        assert_eq!(document.pages.len(), 2);
+        assert_eq!(document.anchors["Intro"].0, 0);
+        assert_eq!(document.anchors["method"].0, 1);
+
+        let pdf = to_pdf(&md).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(pdf.starts_with(b"%PDF"));
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("/method"));
+    }
+
+    #[test]
+    fn test_image_next_to_document() {
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00];
+        data.extend([0xFF, 0xC0, 0x00, 0x11, 0x08, 0x01, 0x2C, 0x02, 0x58, 0x03, 0x00, 0x00, 0x00]);
+        let directory = TestDir::new("pdf-image");
+        directory.write("img/photo.jpg", data);
+        let mut header = BlogHeader::default();
+        header.document_dir = directory.path().display().to_string();
+
+        let image = load_image(&"img/photo.jpg".to_string(), &header).unwrap();
+        assert_eq!((image.width, image.height), (600, 300));
+    }
+}
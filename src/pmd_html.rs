@@ -2,6 +2,9 @@ use contact::ContactDefinition;
 use ordered_map::OrderedMap;
 use pmd_html_shared::{ObjectKind, PMDSharedHTMLSerializer, Reference, PMDHTML};
 use crate::*;
+use config::{HTML_TEMPLATES, SELF_CONTAINED_SCRIPTS, SELF_CONTAINED_STYLESHEETS};
+#[cfg(not(feature = "wasm"))]
+use color_print::cprintln;
 
 pub struct PMDHTMLSerializer { 
     pub common: PMDHTML<PMDHTMLSerializer>,
@@ -12,6 +15,9 @@ pub struct PMDHTMLSerializer {
     notes_id: String,
     bibliography_id: String,
     contacts_id: String,
+    // a self contained page brings its own stylesheets and scripts and has no navbar, as the
+    // links in it lead to the rest of the site
+    pub self_contained: bool,
 /*
     quote_id:   usize,
     list_id :   usize,
@@ -62,7 +68,8 @@ impl PMDSharedHTMLSerializer for PMDHTMLSerializer {
         output += "<!-- highlight.js -->\n";
         output += "<script src=\"https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.7.0/highlight.min.js\"></script>\n";
         output += "<link rel=\"stylesheet\" href=\"https://cdnjs.cloudflare.com/ajax/libs/highlight.js/11.7.0/styles/default.min.css\">\n";
-        output += "<script>hljs.highlightAll();</script>\n";
+        // highlight.js is left out of self contained pages when it can't be downloaded
+        output += "<script>if (window.hljs) hljs.highlightAll();</script>\n";
         output += "\n";
         output += "<!-- tag needed for media query -->\n";
         output += "<meta name=\"viewport\"    content=\"width=device-width, initial-scale=1, minimum-scale=1\" />\n";
@@ -99,6 +106,15 @@ impl PMDSharedHTMLSerializer for PMDHTMLSerializer {
         }
         output += "\n";
         output += "<!-- stylesheets -->\n";
+        if self.self_contained {
+            for stylesheet in SELF_CONTAINED_STYLESHEETS {
+                output += format!("<style>\n{stylesheet}</style>\n").as_str();
+            }
+            for script in SELF_CONTAINED_SCRIPTS {
+                output += format!("<script>\n{script}</script>\n").as_str();
+            }
+            return output
+        }
         output += "<link rel=\"stylesheet\" href=\"../css/base.css\">\n";
         output += "<link rel=\"stylesheet\" href=\"../css/blog.css\">\n";
         output += "\n";
@@ -139,6 +155,26 @@ impl PMDSharedHTMLSerializer for PMDHTMLSerializer {
             ObjectKind::List  | ObjectKind::Paragraph => self.element_link(id, None, None),
         }
     }
+
+    // html-template is the name of a built in template, a template file or a directory with a
+    // template.html in it, next to the markdown file
+    fn html_template(&mut self) -> Option<String> {
+        let name = self.header.frontmatter.as_ref()?["html-template"].as_string()?;
+        let name = name.trim();
+        if let Some((_, template)) = HTML_TEMPLATES.iter().find(|(template, _)| template.eq_ignore_ascii_case(name)) {
+            return Some(template.to_string())
+        }
+        let path = self.header.resolve_path(name);
+        let path = if path.is_dir() { path.join("template.html") } else { path };
+        match std::fs::read_to_string(&path) {
+            Ok(template) => Some(template),
+            Err(_error) => {
+                #[cfg(not(feature = "wasm"))]
+                cprintln!("<y>warning:</> could not read the html template '{}': {}", path.display(), _error);
+                None
+            }
+        }
+    }
     
     
     fn references(&mut self) -> &OrderedMap<String, Reference<ReferenceDefinition>> {
@@ -182,6 +218,7 @@ impl PMDHTMLSerializer {
             notes_id: String::new(),
             bibliography_id: String::new(),
             contacts_id: String::new(),
+            self_contained: false,
         });
         value.common = PMDHTML::new(filename, &mut *value);
         value
@@ -296,7 +333,8 @@ impl PMDSerializer for PMDHTMLSerializer {
             self.notes.insert(id.clone(), Reference::new(note.clone()));
         }
 
-        self.common.html(md, Some(&navbar), &md.references, &md.notes, &md.contacts)
+        let navbar = if self.self_contained { None } else { Some(&navbar) };
+        self.common.html(md, navbar, &md.references, &md.notes, &md.contacts)
     }
 
     fn convert_factbox(&mut self, factbox: &FactBox, id: &String) -> Result<String> {
@@ -510,6 +548,26 @@ impl PMDSerializer for PMDHTMLSerializer {
         */
     }
 
+    fn convert_strikethrough(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_strikethrough(text)
+    }
+
+    fn convert_superscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_superscript(text)
+    }
+
+    fn convert_subscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_subscript(text)
+    }
+
+    fn convert_highlight(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_highlight(text)
+    }
+
+    fn convert_underline(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_underline(text)
+    }
+
     fn convert_inlinecode(&mut self, text: &String) -> Result<String> {
         self.common.convert_inlinecode(text)
         // Ok(format!("<code>{text}</code>"))
@@ -597,6 +655,10 @@ impl PMDSerializer for PMDHTMLSerializer {
         */
     }
 
+    fn convert_definition_list(&mut self, list: &Vec<(BlogBody, Vec<BlogBody>)>, id: &String) -> Result<String> {
+        self.common.convert_definition_list(list, id)
+    }
+
     fn convert_list(&mut self, list: &Vec<BlogBody>, id: &String) -> Result<String> {
         self.common.convert_list(list, id)
         /*
@@ -681,6 +743,10 @@ impl PMDSerializer for PMDHTMLSerializer {
         */
     }
     
+    fn convert_abbreviation(&mut self, abbreviation: &String, expansion: &String) -> Result<String> {
+        self.common.convert_abbreviation(abbreviation, expansion)
+    }
+
     fn convert_contact_citation(&mut self, id: &String) -> Result<String> {
         self.common.convert_contact_citation(id)
     }
@@ -721,6 +787,10 @@ impl PMDSerializer for PMDHTMLSerializer {
         }
         */
     }
+
+    fn convert_blocks(&mut self, blocks: &Vec<(BlogBody, String)>) -> Result<String> {
+        self.common.convert_blocks(blocks)
+    }
     
     fn convert_factbox_note(&mut self, factbox: &FactBox, factbox_id: Option<&String>, id: &String) -> Result<String> {
         self.common.convert_factbox_note(factbox, factbox_id, id)
@@ -772,6 +842,14 @@ impl PMDSerializer for PMDHTMLSerializer {
         */
     }
 
+    fn convert_index(&mut self) -> Result<String> {
+        self.common.convert_index()
+    }
+
+    fn convert_index_term(&mut self, term: &String, id: &String) -> Result<String> {
+        self.common.convert_index_term(term, id)
+    }
+
     fn convert_page_break(&mut self) -> Result<String> {
         self.common.convert_page_break()
         /*
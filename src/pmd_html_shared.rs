@@ -2,9 +2,10 @@
 use color_print::cprintln;
 use anyhow::{Context, Result, anyhow};
 use std::ops::{Deref, DerefMut};
+use std::collections::HashSet;
 
 use crate::{
-    any_non_empty, bibliograph_name, contact::ContactDefinition, ordered_map::OrderedMap, paws_markdown::BlogBody, to_citation, Alternative, BlogHeader, FactBox, PMDSerializer, PawsMarkdown, ReferenceDefinition, Span
+    any_non_empty, bibliograph_name, Frontmatter, FrontmatterHelper, config::DRAFT_WATERMARK, syntax::{code_theme, css_color, highlight, is_highlighted, CodeTheme, TokenKind, CODE_THEMES}, draft_timestamp, contact::ContactDefinition, ordered_map::OrderedMap, paws_markdown::BlogBody, to_citation, Alternative, BlogHeader, FactBox, PMDSerializer, PawsMarkdown, ReferenceDefinition, Span
 };
 
 pub struct Reference<T> {
@@ -76,10 +77,86 @@ pub enum ObjectKind {
     FactBox,
 }
 
+// the parts of a page, put together in the default layout or filled into the slots of a template
+#[derive(Default)]
+pub struct HtmlParts {
+    pub head: String,
+    // the styles the page needs whatever the template, like the watermark of drafts. they are a
+    // part of the head as well
+    pub style: String,
+    pub navbar: String,
+    pub title: String,
+    pub body: String,
+    pub notes: String,
+    pub glossary: String,
+    pub abbreviations: String,
+    pub contacts: String,
+    pub bibliography: String,
+    pub appendix: String,
+    // the popup for images and the build time of drafts
+    pub footer: String,
+}
+
+// a template is a html page where {{body}} and the other parts go, and {{field}} is replaced with
+// the title, subtitle, date, url or filename, or a field from the frontmatter. a part of the page
+// that has content but no slot is put around the body so nothing is lost, the head and navbar of
+// the site are just left out but the styles the page needs are still put in the <head>
+pub fn fill_template(template: &str, parts: &HtmlParts, md: &PawsMarkdown, filename: &str) -> String {
+    let header = &md.header;
+    let mut slots = vec![
+        ("head", &parts.head), ("navbar", &parts.navbar), ("title-section", &parts.title), ("body", &parts.body),
+        ("notes", &parts.notes), ("glossary", &parts.glossary), ("abbreviations", &parts.abbreviations),
+        ("contacts", &parts.contacts), ("bibliography", &parts.bibliography), ("appendix", &parts.appendix),
+        ("footer", &parts.footer),
+    ];
+    let used: Vec<&str> = template.split("{{").skip(1).filter_map(|slot| slot.split_once("}}")).map(|(name, _)| name.trim()).collect();
+    let mut body = parts.body.clone();
+    for (name, part) in &slots {
+        if part.trim().is_empty() || used.contains(name) || ["head", "navbar", "body"].contains(name) {
+            continue
+        }
+        if *name == "title-section" {
+            body = part.to_string() + body.as_str();
+        } else {
+            body += part.as_str();
+        }
+    }
+    slots[3].1 = &body;
+
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") && let Some(end) = rest[start..].find("}}") {
+        output += &rest[..start];
+        let name = rest[start + 2..start + end].trim();
+        if let Some((_, part)) = slots.iter().find(|(slot, _)| *slot == name) {
+            output += part.as_str();
+        } else {
+            output += match name {
+                "title"    => sanitize_text(&header.title),
+                "subtitle" => sanitize_text(&header.subtitle),
+                "date"     => sanitize_text(&header.frontmatter.as_ref().and_then(|frontmatter| frontmatter["date"].as_string()).unwrap_or_default()),
+                "url"      => sanitize_text(&header.url),
+                "filename" => sanitize_text(&filename.to_string()),
+                name => sanitize_text(&header.frontmatter.as_ref().map(|frontmatter| frontmatter[name].as_list().join(", ")).unwrap_or_default()),
+            }.as_str();
+        }
+        rest = &rest[start + end + 2..];
+    }
+    output += rest;
+
+    if !used.contains(&"head") && !parts.style.is_empty() {
+        let at = output.find("</head>").unwrap_or(0);
+        output.insert_str(at, parts.style.as_str());
+    }
+    output
+}
+
 pub trait PMDSharedHTMLSerializer: PMDSerializer {
     const LINK_ELEMENTS: bool = true;
     const POPUPS: bool = true;
     const SHOW_BACKREFS: bool = true;
+    // the index lists page numbers instead of links to the sections
+    const PAGE_REFERENCES: bool = false;
 
     // rust is dumb as shit so we have to copy the header
     fn get_header(&self) -> &BlogHeader;
@@ -87,6 +164,10 @@ pub trait PMDSharedHTMLSerializer: PMDSerializer {
     fn prepare_html_header(&mut self, description: &String) -> String;
     fn convert_body(&mut self, md: &PawsMarkdown) -> Result<String>;
     fn generate_link(&mut self, id: &String, kind: ObjectKind) -> String;
+    // the lines inside the title section, when the serializer doesn't use the plain title and subtitle
+    fn title_section(&mut self, _md: &PawsMarkdown) -> Option<Vec<String>> { None }
+    // a page with slots for the parts of the document, used instead of the default layout
+    fn html_template(&mut self) -> Option<String> { None }
 
     fn notes_id(&mut self) -> String;
     fn bibliography_id(&mut self) -> String;
@@ -107,7 +188,10 @@ pub struct PMDHTML<T: PMDSharedHTMLSerializer> {
     pub filename: String,
     pub parent: Weak<T>,
     num_tabs:   usize,
-    current_factbox: Option<(FactBox, Option<String>)>
+    current_factbox: Option<(FactBox, Option<String>)>,
+    // the notes of the document being converted, so drafts can mark the ones that are missing
+    defined_notes: Option<HashSet<String>>,
+    code_theme: &'static CodeTheme,
 }
 
 impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
@@ -117,6 +201,8 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
             parent: Weak::new(parent),
             num_tabs:   0,
             current_factbox: None,
+            defined_notes: None,
+            code_theme: &CODE_THEMES[0],
         }
     }
 
@@ -126,6 +212,8 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
             parent: Weak::uninit(),
             num_tabs:   0,
             current_factbox: None,
+            defined_notes: None,
+            code_theme: &CODE_THEMES[0],
         }
     }
     
@@ -179,6 +267,41 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
         self.current_factbox.clone()
     }
 
+    // the page break and heading of a section after the document (notes, glossary, ...), the
+    // section is left open for its content until close_section
+    fn open_section(&mut self, class: Option<&str>, id: &String, title: &String, link: bool) -> String {
+        let mut output = String::new();
+        output += self.tab().as_str();
+        output += format!("<section class='page-break'>\n").as_str();
+        self.push_tab();
+            output += self.tab().as_str();
+            output += "<hr>\n";
+        self.pop_tab();
+        output += self.tab().as_str();
+        output += format!("</section>\n").as_str();
+
+        let class = class.map(|class| format!("class='{class}' ")).unwrap_or_default();
+        output += self.tab().as_str();
+        output += format!("<section {class}id='{id}'>\n").as_str();
+        self.push_tab();
+        if link && T::LINK_ELEMENTS {
+            output += self.tab().as_str();
+            output += self.parent.generate_link(id, ObjectKind::Header(1)).as_str();
+            output.push('\n');
+        }
+
+            output += self.tab().as_str();
+            output += format!("<h1>{title}</h1>\n").as_str();
+        output
+    }
+
+    fn close_section(&mut self) -> String {
+        self.pop_tab();
+        let mut output = self.tab();
+        output += "</section>\n";
+        output
+    }
+
     pub fn html(
         &mut self, md: &PawsMarkdown, navbar: Option<&String>,
         references: &OrderedMap<String, ReferenceDefinition>, notes: &OrderedMap<String, BlogBody>, contacts: &OrderedMap<String, ContactDefinition>) 
@@ -189,6 +312,19 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
         let description = self.parent.get_description(md)?;
         let header = self.parent.prepare_html_header(&description);
         let blog_header = laundered_parent.get_header();
+        self.defined_notes = Some(notes.keys().cloned().collect());
+        self.code_theme = code_theme(blog_header.frontmatter.as_ref().unwrap_or(&Frontmatter::new())).unwrap_or_else(|_error| {
+            #[cfg(not(feature = "wasm"))]
+            cprintln!("<y>warning:</> {}", _error);
+            &CODE_THEMES[0]
+        });
+        let mut parts = HtmlParts::default();
+        parts.head = header.clone();
+        if blog_header.draft {
+            parts.style = Self::draft_style();
+            parts.head += parts.style.as_str();
+        }
+        parts.navbar = navbar.cloned().unwrap_or_default();
         output +=   "<!doctype html>\n";
         output +=   "<html>\n";
         self.push_tab();
@@ -201,6 +337,13 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
             output += line;
             output += "\n";
         }
+        if blog_header.draft {
+            for line in Self::draft_style().lines() {
+                output += self.tab().as_str();
+                output += line;
+                output += "\n";
+            }
+        }
         self.pop_tab();
         output += self.tab().as_str();
         output += "</head>\n";
@@ -220,8 +363,22 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
         output += self.tab().as_str();
         output += "<main>\n";
         self.push_tab();
+        let opening = std::mem::take(&mut output);
 
-        {
+        if let Some(lines) = self.parent.title_section(md) {
+            output += self.tab().as_str();
+            output += "<section class='title'>\n";
+            self.push_tab();
+            for line in lines {
+                output += self.tab().as_str();
+                output += line.as_str();
+                output += "\n";
+            }
+            self.pop_tab();
+
+            output += self.tab().as_str();
+            output += "</section>\n";
+        } else {
             let title    = &blog_header.title;
             let subtitle = &blog_header.subtitle;
             output += self.tab().as_str();
@@ -238,43 +395,23 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
             output += format!("</section>\n").as_str();
         }
 
+        parts.title = std::mem::take(&mut output);
         output += self.parent.convert_body(md)?.as_str();
+        parts.body = std::mem::take(&mut output);
 
         if !(notes.is_empty() || blog_header.hide_notes) {
             let id = self.parent.notes_id();
             let title = &blog_header.notes_title;
-            let link = if T::LINK_ELEMENTS {
-                self.parent.generate_link(&id, ObjectKind::Header(1))
-            } else {
-                "".into()
-            };
-
-            
-            output += self.tab().as_str();
-            output += format!("<section class='page-break'>\n").as_str();
-            self.push_tab();
-                output += self.tab().as_str();
-                output += "<hr>\n";
-            self.pop_tab();
-            output += self.tab().as_str();
-            output += format!("</section>\n").as_str();
-                
-            output += self.tab().as_str();
-            output += format!("<section class='notes' id='{id}'>\n").as_str();
-            self.push_tab();
-            if T::LINK_ELEMENTS {
-                output += self.tab().as_str();
-                output += link.as_str();
-                output.push('\n');
-            }
-                
-                output += self.tab().as_str();
-                output += format!("<h1>{title}</h1>\n").as_str();
-            self.pop_tab();
-            output += self.tab().as_str();
-            output += format!("</section>\n").as_str();
+            output += self.open_section(Some("notes"), &id, title, true).as_str();
+            output += self.close_section().as_str();
                 
             for (key, val) in notes {
+                if let BlogBody::Blocks(blocks) = val {
+                    let link = format!("<a href='#^{key}'>^{key}:</a>");
+                    output += self.convert_block_note(blocks, key, &link)?.as_str();
+                    continue;
+                }
+
                 let result = self.parent.convert_element((val, &String::new()))?;
                 let link = format!("<a href='#^{key}'>^{key}:</a>");
             
@@ -311,27 +448,54 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
             }
         }
 
-        if !(contacts.is_empty() || blog_header.hide_contacts) {
-            let id = self.parent.contacts_id();
-            let title = &md.header.contacts_title;
-
-            output += self.tab().as_str();
-            output += format!("<section class='page-break'>\n").as_str();
-            self.push_tab();
+        parts.notes = std::mem::take(&mut output);
+        if !md.glossary.is_empty() {
+            let id = &md.glossary_id;
+            let title = &blog_header.glossary_title;
+            output += self.open_section(Some("glossary"), id, title, true).as_str();
                 output += self.tab().as_str();
-                output += "<hr>\n";
-            self.pop_tab();
-            output += self.tab().as_str();
-            output += format!("</section>\n").as_str();
+                output += "<dl>\n";
+                self.push_tab();
+                for (term, definition, term_id) in &md.glossary {
+                    let definition = self.parent.convert_element(no_id!(definition))?;
+                    let term_id = sanitize_id(term_id);
+                    output += self.tab().as_str();
+                    output += format!("<dt id='{term_id}'>{term}</dt>\n").as_str();
+                    output += self.tab().as_str();
+                    output += format!("<dd>{definition}</dd>\n").as_str();
+                }
+                self.pop_tab();
+                output += self.tab().as_str();
+                output += "</dl>\n";
+            output += self.close_section().as_str();
+        }
 
-            output += self.tab().as_str();
-            output += format!("<section class='contacts' id='{id}'>\n").as_str();
-            self.push_tab();
+        parts.glossary = std::mem::take(&mut output);
+        if blog_header.list_abbreviations && !md.abbreviations.is_empty() {
+            let id = &md.abbreviations_id;
+            let title = &blog_header.abbreviations_title;
+            output += self.open_section(Some("abbreviations"), id, title, true).as_str();
                 output += self.tab().as_str();
-                output += format!("<h1>{title}</h1>\n").as_str();
-            self.pop_tab();
-            output += self.tab().as_str();
-            output += format!("</section>\n").as_str();
+                output += "<dl>\n";
+                self.push_tab();
+                for (abbreviation, expansion) in &md.abbreviations {
+                    output += self.tab().as_str();
+                    output += format!("<dt><abbr>{}</abbr></dt>\n", sanitize_text(abbreviation)).as_str();
+                    output += self.tab().as_str();
+                    output += format!("<dd>{}</dd>\n", sanitize_text(expansion)).as_str();
+                }
+                self.pop_tab();
+                output += self.tab().as_str();
+                output += "</dl>\n";
+            output += self.close_section().as_str();
+        }
+
+        parts.abbreviations = std::mem::take(&mut output);
+        if !(contacts.is_empty() || blog_header.hide_contacts) {
+            let id = self.parent.contacts_id();
+            let title = &md.header.contacts_title;
+            output += self.open_section(Some("contacts"), &id, title, false).as_str();
+            output += self.close_section().as_str();
 
             for (key, val) in self.parent.contacts() {
                 if val.times_used == 0 {
@@ -360,38 +524,12 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
             }
         }
 
+        parts.contacts = std::mem::take(&mut output);
         if !(references.is_empty() || blog_header.hide_references) {
             let id = self.parent.bibliography_id();
             let title = &blog_header.bibliography_title;
-            let link = if T::LINK_ELEMENTS {
-                self.parent.generate_link(&id, ObjectKind::Header(1))
-            } else {
-                "".into()
-            };
-
-            output += self.tab().as_str();
-            output += format!("<section class='page-break'>\n").as_str();
-            self.push_tab();
-                output += self.tab().as_str();
-                output += "<hr>\n";
-            self.pop_tab();
-            output += self.tab().as_str();
-            output += format!("</section>\n").as_str();
-
-            output += self.tab().as_str();
-            output += format!("<section id='{id}'>\n").as_str();
-            self.push_tab();
-            if T::LINK_ELEMENTS {
-                output += self.tab().as_str();
-                output += link.as_str();
-                output.push('\n');
-            }
-                
-                output += self.tab().as_str();
-                output += format!("<h1>{title}</h1>\n").as_str();
-            self.pop_tab();
-            output += self.tab().as_str();
-            output += format!("</section>\n").as_str();
+            output += self.open_section(None, &id, title, true).as_str();
+            output += self.close_section().as_str();
 
             for (key, val) in self.parent.references() {
                 if val.times_used == 0 {
@@ -431,9 +569,31 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
             }
         }        
 
+        parts.bibliography = std::mem::take(&mut output);
+        if !md.appendix.is_empty() {
+            let id = &md.appendix_id;
+            let title = &blog_header.appendix_title;
+            output += self.open_section(Some("appendix"), id, title, true).as_str();
+            output += self.close_section().as_str();
+
+            for (element, id) in &md.appendix {
+                output += self.parent.convert_element((element, id))?.as_str();
+                if let BlogBody::CodeBlock(_) = element {
+                    output += "\n";
+                }
+            }
+        }
+
+        parts.appendix = std::mem::take(&mut output);
         self.pop_tab();
         output += self.tab().as_str();
         output += "</main>\n";
+        let closing_main = std::mem::take(&mut output);
+
+        if blog_header.draft {
+            output += self.tab().as_str();
+            output += format!("<footer class='draft-timestamp'>{}</footer>\n", draft_timestamp()).as_str();
+        }
 
         if T::POPUPS {
             output += self.tab().as_str();
@@ -460,6 +620,7 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
             output +="</div>\n";
         }
 
+        parts.footer = std::mem::take(&mut output);
         self.pop_tab();
         output += self.tab().as_str();
         output +=   "</body>\n";
@@ -468,13 +629,20 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
         output += self.tab().as_str();
         output +=   "</html>\n";
 
-        Ok(output)
+        if let Some(template) = self.parent.html_template() {
+            return Ok(fill_template(&template, &parts, md, &self.filename))
+        }
+        Ok([
+            opening, parts.title, parts.body, parts.notes, parts.glossary, parts.abbreviations,
+            parts.contacts, parts.bibliography, parts.appendix, closing_main, parts.footer, output,
+        ].concat())
     }
     
     pub fn convert_factbox(&mut self, factbox: &FactBox, id: &String) -> Result<String> {
         let id = sanitize_id(id);
         let link = if T::LINK_ELEMENTS { self.parent.generate_link(&id, ObjectKind::FactBox) } else { String::new() };
-        let title = &factbox.title;
+        let title = factbox.display_title();
+        let class = if let Some(class) = factbox.kind.class_name() { format!("factbox {class}") } else { "factbox".into() };
         let laundered_parent = Weak::launder(&self.parent);
         let header = laundered_parent.get_header();
 
@@ -487,7 +655,7 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
         }
             
             output += self.tab().as_str();
-            output += "<article class='factbox'>\n";
+            output += format!("<article class='{class}'>\n").as_str();
             self.push_tab();
 
                 output += self.tab().as_str();
@@ -505,13 +673,14 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
                 output += "<section class='factbox-content'>\n";
                 self.push_tab();
 
-                self.current_factbox = Some((factbox.clone(), Some(id.clone())));
+                // nested fact boxes have their own notes, so the outer one is restored afterwards
+                let outer_factbox = self.current_factbox.replace((factbox.clone(), Some(id.clone())));
 
                 for element in self.parent.convert_factbox_elements(factbox, Some(&id))? {
                     output += element.as_str();
                 }
                 
-                self.current_factbox = None;
+                self.current_factbox = outer_factbox;
 
 
                 if !(factbox.notes.is_empty() || header.hide_notes) {
@@ -546,6 +715,12 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
                     output += format!("</section>\n").as_str();
                         
                     for (key, (val, note_id)) in &factbox.notes {
+                        if let BlogBody::Blocks(blocks) = val {
+                            let link = format!("<a href='#^{note_id}'>^{key}:</a>");
+                            output += self.convert_block_note(blocks, note_id, &link)?.as_str();
+                            continue;
+                        }
+
                         let result = self.parent.convert_element(no_id!(val))?;
                         let link = format!("<a href='#^{note_id}'>^{key}:</a>");
                     
@@ -626,7 +801,7 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
                     Ok(reference)
                 } else {
                     #[cfg(not(feature = "wasm"))]
-                    cprintln!("<y>warning:</> {} has no source", citation);
+                    cprintln!("<y>warning:</> {}{} has no source", header.source_of(citation), citation);
                     Ok(format!("<cite style='color=red; background-color: yellow'>{text}</cite>"))
                 }
             }, 
@@ -638,14 +813,25 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
                 }
             },
             _ => {
+                let text = match href.strip_prefix('#') {
+                    Some(target) if text.trim().is_empty() => {
+                        header.section_number(&target.to_string()).cloned().unwrap_or(text)
+                    },
+                    _ => text,
+                };
+                let href = if let Some(target) = href.strip_prefix('#') { header.resolve_href(target) } else { href };
                 Ok(format!("<a class='inline-link' href='{href}'>{text}</a>"))
             }
         }
     }
 
     pub fn convert_header(&mut self, text: &Box<BlogBody>, depth: usize, id: &String) -> Result<String> {
+        let number = self.parent.get_header().section_number(id).cloned();
         let id = sanitize_id(id);
-        let text  = self.parent.convert_element(no_id!(text))?;
+        let mut text  = self.parent.convert_element(no_id!(text))?;
+        if let Some(number) = number {
+            text = format!("<span class='section-number'>{number}</span> {text}");
+        }
         let link = if T::LINK_ELEMENTS { self.parent.generate_link(&id, ObjectKind::Header(depth)) } else { "".into() };
         
         let mut result = self.tab();
@@ -676,6 +862,31 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
         Ok(format!("<b>{inner_text}</b>"))
     }
 
+    pub fn convert_strikethrough(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        let inner_text = self.parent.convert_element(no_id!(&text))?;
+        Ok(format!("<s>{inner_text}</s>"))
+    }
+
+    pub fn convert_superscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        let inner_text = self.parent.convert_element(no_id!(&text))?;
+        Ok(format!("<sup>{inner_text}</sup>"))
+    }
+
+    pub fn convert_subscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        let inner_text = self.parent.convert_element(no_id!(&text))?;
+        Ok(format!("<sub>{inner_text}</sub>"))
+    }
+
+    pub fn convert_highlight(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        let inner_text = self.parent.convert_element(no_id!(&text))?;
+        Ok(format!("<mark>{inner_text}</mark>"))
+    }
+
+    pub fn convert_underline(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        let inner_text = self.parent.convert_element(no_id!(&text))?;
+        Ok(format!("<u>{inner_text}</u>"))
+    }
+
     pub fn convert_inlinecode(&mut self, text: &String) -> Result<String> {
         let text = sanitize_text(text);
         Ok(format!("<code>{text}</code>"))
@@ -686,12 +897,26 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
         let first_line = text.lines().nth(0).context("expected at least one line in codeblock")?;
         let mut words = first_line.split(|x: char| x.is_whitespace());
         let lang = words.nth(0).unwrap_or("plaintext");
-        let mut body = text[text.find('\n').context("expected at least one line in codeblock")? + 1..].to_string();
+        let code = text[text.find('\n').context("expected at least one line in codeblock")? + 1..].trim_end().replace("\r\n", "\n");
         let link = if T::LINK_ELEMENTS { self.parent.generate_link(&id, ObjectKind::CodeBlock) } else { String::new() };
-        
+        let theme = self.code_theme;
 
-        body = sanitize_text(&body);
-        body = body.trim_end().replace("\r\n", "\n");
+        // the colours are written on the spans, so they survive templates and self contained pages
+        let mut body = String::new();
+        for (kind, text) in highlight(lang, &code) {
+            if kind == TokenKind::Plain {
+                body += sanitize_text(&text).as_str();
+            } else {
+                body += format!("<span style='color: {}'>{}</span>", css_color(theme.color(kind)), sanitize_text(&text)).as_str();
+            }
+        }
+        let style = format!("background: {}; color: {}", css_color(theme.background), css_color(theme.foreground));
+        // highlight.js colours the languages that aren't highlighted here, and is told to leave the others alone
+        let code = if is_highlighted(lang) {
+            format!("<code class='language-{lang} nohighlight' style='{style}'>{body}</code>")
+        } else {
+            format!("<code class='language-{lang}'>{body}</code>")
+        };
         
         let mut result = self.tab();
         result += format!("<section class='code-block' id='{id}'>\n").as_str();
@@ -702,7 +927,7 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
         }
             
             result += self.tab().as_str();
-            result += format!("<pre><code class='language-{lang}'>{body}</code></pre>\n").as_str();
+            result += format!("<pre>{code}</pre>\n").as_str();
         self.pop_tab();
         result += self.tab().as_str();
         result += "</section>\n";
@@ -789,6 +1014,41 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
         Ok(result)
     }
 
+    pub fn convert_definition_list(&mut self, list: &Vec<(BlogBody, Vec<BlogBody>)>, id: &String) -> Result<String> {
+        let id = sanitize_id(id);
+        let link = if T::LINK_ELEMENTS { self.parent.generate_link(&id, ObjectKind::List) } else { String::new() };
+
+        let mut result = self.tab();
+        result += format!("<section class='definition-list' id='{id}'>\n").as_str();
+        self.push_tab();
+        if T::LINK_ELEMENTS {
+            result += self.tab().as_str();
+            result += format!("{link}\n").as_str();
+        }
+            
+            result += self.tab().as_str();
+            result += "<dl>\n";
+            self.push_tab();
+            for (term, definitions) in list {
+                let text = self.parent.convert_element(no_id!(term))?;
+                result += self.tab().as_str();
+                result += format!("<dt>{text}</dt>\n").as_str();
+                for definition in definitions {
+                    let text = self.parent.convert_element(no_id!(definition))?;
+                    result += self.tab().as_str();
+                    result += format!("<dd>{text}</dd>\n").as_str();
+                }
+            }
+            self.pop_tab();
+            result += self.tab().as_str();
+            result += "</dl>\n";
+        self.pop_tab();
+        result += self.tab().as_str();
+        result += "</section>\n";
+
+        Ok(result)
+    }
+
     pub fn convert_paragraph(&mut self, text: &Box<BlogBody>, id: &String) -> Result<String> {
         let id = sanitize_id(id);
         let paragraph = self.parent.convert_element(no_id!(text))?;
@@ -838,6 +1098,35 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
         Ok(result)
     }
     
+    // drafts mark what is missing, with the key, so it stands out when the draft is read
+    fn unresolved(text: &str, id: &String) -> String {
+        format!("<mark class='unresolved'>{text} {}</mark>", sanitize_text(id))
+    }
+
+    // drafts get a watermark, numbered paragraphs, a wide margin for comments and the time they
+    // were built. the lines themselves can't be numbered, they depend on the width of the window
+    fn draft_style() -> String {
+        let mut output = String::new();
+        output += "<style>\n";
+        output += format!("body::before {{ content: '{DRAFT_WATERMARK}'; position: fixed; top: 50%; left: 50%; transform: translate(-50%, -50%) rotate(-45deg); font-size: 6em; font-weight: bold; color: rgba(0, 0, 0, 0.08); white-space: nowrap; pointer-events: none; z-index: 1000; }}\n").as_str();
+        output += "main { counter-reset: draft-line; }\n";
+        output += "main p, main pre, main section:not(.toc) li { counter-increment: draft-line; position: relative; }\n";
+        output += "main p::before, main pre::before, main section:not(.toc) li::before { content: counter(draft-line); position: absolute; left: -4em; width: 3em; text-align: right; font-size: 7pt; font-weight: normal; font-style: normal; color: grey; }\n";
+        output += "mark.unresolved { color: red; background-color: yellow; }\n";
+        output += ".draft-timestamp { font-size: 7pt; color: grey; text-align: center; }\n";
+        output += "@media screen { main { margin-right: 6cm; } }\n";
+        output += "@media print {\n";
+        output += "    .draft-timestamp { display: none; }\n";
+        output += format!("    @page {{ @bottom-right-corner {{ content: '{}'; font-size: 7pt; color: grey; }} }}\n", draft_timestamp()).as_str();
+        output += "}\n";
+        output += "</style>\n";
+        output
+    }
+
+    pub fn convert_abbreviation(&mut self, abbreviation: &String, expansion: &String) -> Result<String> {
+        Ok(format!("<abbr title='{}'>{}</abbr>", sanitize_id(expansion), sanitize_text(abbreviation)))
+    }
+
     pub fn convert_contact_citation(&mut self, id: &String) -> Result<String> {
         if let Some(reference) = self.parent.get_mut_contact(id) {
             let num = reference.times_used;
@@ -861,7 +1150,10 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
             }
         } else {
             #[cfg(not(feature = "wasm"))]
-            cprintln!("<y>warning:</> {} has no source", id);
+            cprintln!("<y>warning:</> {}{} has no source", self.parent.get_header().source_of(id), id);
+            if self.parent.get_header().draft {
+                return Ok(Self::unresolved("(MISSING CONTACT)", id))
+            }
             Ok(format!("<span style=\"color: red; background-color: yellow\">(MISSING CONTACT)</span>").to_string())
         }
     }
@@ -889,7 +1181,10 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
             }
         } else {
             #[cfg(not(feature = "wasm"))]
-            cprintln!("<y>warning:</> {} has no source", id);
+            cprintln!("<y>warning:</> {}{} has no source", laundered_parent.get_header().source_of(id), id);
+            if laundered_parent.get_header().draft {
+                return Ok(Self::unresolved("(MISSING CITATION)", id))
+            }
             Ok(format!("(MISSING CITATION)").to_string())
         }
     }
@@ -908,13 +1203,52 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
     }
 
     pub fn convert_note(&mut self, id: &String) -> Result<String> {
-        if self.parent.as_mut().get_header().hide_notes {
+        let missing = self.defined_notes.as_ref().is_some_and(|notes| !notes.contains(id));
+        if self.parent.get_header().draft && missing {
+            Ok(Self::unresolved("(MISSING NOTE)", id))
+        } else if self.parent.as_mut().get_header().hide_notes {
             Ok("".into())
         } else {
             Ok(format!("<sup><a id='{id}-backref' href='#^{id}'>{id}</a></sup>"))
         }
     }
 
+    // notes spanning several blocks can't be placed inside of <sup>, so only the marker is
+    fn convert_block_note(&mut self, blocks: &Vec<(BlogBody, String)>, note_id: &String, link: &String) -> Result<String> {
+        let mut output = self.tab();
+        output += format!("<section class='note note-blocks' id=\"^{note_id}\">\n").as_str();
+        self.push_tab();
+            output += self.tab().as_str();
+            output += format!("<sup>{link}</sup>\n").as_str();
+
+            output += self.tab().as_str();
+            output += "<div class='note-body'>\n";
+            self.push_tab();
+                output += self.parent.convert_blocks(blocks)?.as_str();
+            self.pop_tab();
+            output += self.tab().as_str();
+            output += "</div>\n";
+
+        if T::SHOW_BACKREFS {
+            output += self.tab().as_str();
+            output += format!("<a href=\"#{note_id}-backref\">").as_str();
+            output += "↩";
+            output += "</a>\n";
+        }
+        self.pop_tab();
+        output += self.tab().as_str();
+        output += "</section>\n";
+        Ok(output)
+    }
+
+    pub fn convert_blocks(&mut self, blocks: &Vec<(BlogBody, String)>) -> Result<String> {
+        let mut result = String::new();
+        for (element, id) in blocks {
+            result += self.parent.convert_element((element, id))?.as_str();
+        }
+        Ok(result)
+    }
+
     pub fn convert_toc(&mut self) -> Result<String> {
         if self.parent.get_header().toc.is_none() { 
             return Err(anyhow!("expected a table of content but none was found")); 
@@ -949,19 +1283,114 @@ impl<T: PMDSharedHTMLSerializer> PMDHTML<T> {
             self.push_tab();
 
                 for (elem, depth, id) in &toc.headers {
-                    let text = if let &box BlogBody::FactBox(fbox) = &elem {
-                        fbox.title.clone()
+                    let mut text = if let &box BlogBody::FactBox(fbox) = &elem {
+                        fbox.display_title()
                     } else {
                         self.parent.convert_element(no_id!(elem))?
                     };
+                    if let Some(number) = self.parent.get_header().section_number(id) {
+                        text = format!("<span class='section-number'>{number}</span> {text}");
+                    }
 
                     result += self.tab().as_str();
-                    result += format!("<li class='toci-{depth}'><a href='#{id}'>{text}</a></li>\n").as_str();
+                    let href = self.parent.get_header().resolve_href(id);
+                    result += format!("<li class='toci-{depth}'><a href='{href}'>{text}</a></li>\n").as_str();
                 }
             self.pop_tab();
             result += self.tab().as_str();
             result += "</ul>\n";
 
+            if !toc.appendix.is_empty() {
+                result += self.tab().as_str();
+                result += "<ul class='toc-appendix'>\n";
+                self.push_tab();
+                    for (elem, depth, id) in &toc.appendix {
+                        let mut text = if let &box BlogBody::FactBox(fbox) = &elem {
+                            fbox.display_title()
+                        } else {
+                            self.parent.convert_element(no_id!(elem))?
+                        };
+                        if let Some(number) = self.parent.get_header().section_number(id) {
+                            text = format!("<span class='section-number'>{number}</span> {text}");
+                        }
+
+                        result += self.tab().as_str();
+                        let href = self.parent.get_header().resolve_href(id);
+                        result += format!("<li class='toci-{depth}'><a href='{href}'>{text}</a></li>\n").as_str();
+                    }
+                self.pop_tab();
+                result += self.tab().as_str();
+                result += "</ul>\n";
+            }
+
+        self.pop_tab();
+        result += self.tab().as_str();
+        result += "</section>\n";
+
+        Ok(result)
+    }
+
+    pub fn convert_index_term(&mut self, _: &String, id: &String) -> Result<String> {
+        // index terms inside of headers point to the header, so they don't need a marker
+        if id.is_empty() { return Ok(String::new()) }
+        Ok(format!("<span class='index-term' id='{}'></span>", sanitize_id(id)))
+    }
+
+    pub fn convert_index(&mut self) -> Result<String> {
+        if self.parent.get_header().index.is_none() { 
+            return Err(anyhow!("expected an index but none was found")); 
+        }
+        let link = if T::LINK_ELEMENTS {
+            self.parent.generate_link(&String::from("index"), ObjectKind::Header(1))
+        } else { String::new() };
+        let index = self.parent.get_header().index.clone().unwrap();
+        let title = &index.title;
+
+        let mut result = self.tab();
+        
+        result += "<section class='index' id='index'>\n";
+        self.push_tab();
+        if T::LINK_ELEMENTS {
+            result += self.tab().as_str();
+            result += link.as_str();
+            result.push('\n');
+        }
+            
+            result += self.tab().as_str();
+            result += format!("<h1>{title}</h1>\n").as_str();
+
+            result += self.tab().as_str();
+            result += "<ul class='index-entries'>\n";
+            self.push_tab();
+            for (term, locations) in &index.entries {
+                let mut references = Vec::<String>::new();
+                if T::PAGE_REFERENCES {
+                    // the page numbers are filled in by the stylesheet
+                    for (marker, _) in locations {
+                        references.push(format!("<a class='index-page' href='#{}'></a>", sanitize_id(marker)));
+                    }
+                } else {
+                    let mut seen = HashSet::<&String>::new();
+                    for (_, section) in locations {
+                        if !seen.insert(section) { continue }
+                        let mut text = if let Some(text) = index.sections.get(section) {
+                            self.parent.convert_element(no_id!(text))?
+                        } else {
+                            self.parent.get_header().title.clone()
+                        };
+                        if let Some(number) = self.parent.get_header().section_number(section) {
+                            text = format!("<span class='section-number'>{number}</span> {text}");
+                        }
+                        let href = if section.is_empty() { "#".into() } else { self.parent.get_header().resolve_href(section) };
+                        references.push(format!("<a href='{href}'>{text}</a>"));
+                    }
+                }
+                result += self.tab().as_str();
+                result += format!("<li><span class='index-entry'>{}</span> {}</li>\n", sanitize_text(term), references.join(", ")).as_str();
+            }
+            self.pop_tab();
+            result += self.tab().as_str();
+            result += "</ul>\n";
         self.pop_tab();
         result += self.tab().as_str();
         result += "</section>\n";
@@ -1307,3 +1736,44 @@ pub fn convert_custom_citation<T>(citation: Option<&mut Reference<T>>, id: &Stri
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use crate::pmd_html_shared::*;
+    use crate::parse;
+
+    #[test]
+    fn test_fill_template() {
+        let md = parse(&"---\ntitle: Report\nteam: [Ann, Bo]\n---\ntext\n".into(), None).unwrap();
+        let parts = HtmlParts {
+            head: "<meta>".into(),
+            navbar: "<nav>".into(),
+            body: "<p>text</p>".into(),
+            title: "<h1>Report</h1>".into(),
+            notes: "<section>notes</section>".into(),
+            footer: "<div id='popup'></div>".into(),
+            ..Default::default()
+        };
+        let template = "<title>{{title}}</title>{{ body }}<footer>{{footer}}</footer><i>{{team}}{{filename}}</i>";
+        assert_eq!(
+            fill_template(template, &parts, &md, "report"),
+            "<title>Report</title><h1>Report</h1><p>text</p><section>notes</section><footer><div id='popup'></div></footer><i>Ann, Boreport</i>",
+        );
+    }
+
+    #[test]
+    fn test_fill_template_without_head() {
+        let md = parse(&"---\ntitle: R&D <draft>\nteam: [Ann & Bo]\n---\ntext\n".into(), None).unwrap();
+        let parts = HtmlParts {
+            head: "<meta><style>draft</style>".into(),
+            style: "<style>draft</style>".into(),
+            body: "<p>text</p>".into(),
+            ..Default::default()
+        };
+        let template = "<head><title>{{title}}</title></head>{{body}}<i>{{team}}</i>";
+        assert_eq!(
+            fill_template(template, &parts, &md, "report"),
+            "<head><title>R&amp;D &lt;draft&gt;</title><style>draft</style></head><p>text</p><i>Ann &amp; Bo</i>",
+        );
+    }
+}
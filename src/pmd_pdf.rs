@@ -2,8 +2,10 @@ use anyhow::anyhow;
 use contact::ContactDefinition;
 use ordered_map::OrderedMap;
 use pdf::build_pdf;
+use page_geometry::PageGeometry;
+use title_page::{substitute_document_fields, TitleAlign, TitleItem, TitleSize};
 use pmd_html_shared::Reference;
-use crate::pmd_html_shared::{ObjectKind, PMDSharedHTMLSerializer, PMDHTML};
+use crate::pmd_html_shared::{sanitize_id, sanitize_text, ObjectKind, PMDSharedHTMLSerializer, PMDHTML};
 use crate::*;
 use tempfile::Builder;
 use std::io::Write;
@@ -24,6 +26,7 @@ impl PMDSharedHTMLSerializer for PMDPDFSerializer {
     const LINK_ELEMENTS: bool = false;
     const POPUPS: bool = false;
     const SHOW_BACKREFS: bool = false;
+    const PAGE_REFERENCES: bool = true;
 
     fn get_header(&self) -> &BlogHeader {
         &self.header
@@ -63,24 +66,13 @@ impl PMDSharedHTMLSerializer for PMDPDFSerializer {
 
         let frontmatter = self.header.frontmatter.clone().unwrap_or(Frontmatter::new());
 
-        let top_left   = Self::parse_pdf_string(&frontmatter["pdf-header-left"]  );
-        let mut top_center = Self::parse_pdf_string(&frontmatter["pdf-header-center"]);
-        let top_right  = Self::parse_pdf_string(&frontmatter["pdf-header-right"]  );
-
-        let bottom_left   = Self::parse_pdf_string(&frontmatter["pdf-footer-left"]  );
-        let mut bottom_center = Self::parse_pdf_string(&frontmatter["pdf-footer-center"]);
-        let bottom_right  = Self::parse_pdf_string(&frontmatter["pdf-footer-right"]  );
+        let [top_left, top_center, top_right] = Self::pdf_string_row(&frontmatter, "pdf-header");
+        let [bottom_left, bottom_center, bottom_right] = Self::pdf_string_row(&frontmatter, "pdf-footer");
         let font = &frontmatter["pdf-font"].as_str();
 
-        if top_center == "" {
-            top_center = Self::parse_pdf_string(&frontmatter["pdf-header"]);
-        }
-        if bottom_center == "" {
-            bottom_center = Self::parse_pdf_string(&frontmatter["pdf-footer"]);
-        }
-
         let text_size   = frontmatter["pdf-text-size"].as_i64();
         let line_height = frontmatter["pdf-line-height"].as_i64();
+        let geometry = PageGeometry::from_frontmatter(&frontmatter);
 
         output += "<meta http-equiv=\"content-type\" content=\"text/html; charset=utf-8\">\n";
         output += "\n";
@@ -114,9 +106,16 @@ impl PMDSharedHTMLSerializer for PMDPDFSerializer {
         output += "h4 {margin-top: 2em; margin-bottom: 0; font-size: 12pt; }\n";
         output += "h5 {margin-top: 2em; margin-bottom: 0; font-size: 12pt; }\n";
         output += "h6 {margin-top: 2em; margin-bottom: 0; font-size: 12pt; }\n";
+        // for %chapter and %section in the headers
+        output += "h1 { string-set: chapter content(text); }\n";
+        output += "h2 { string-set: section content(text); }\n";
         output += ".bibliography h1 { margin-top: auto; margin-bottom: auto; font-size: 18pt; }\n";
         output += ".notes h1 { margin-top: auto; margin-bottom: auto; font-size: 18pt; }\n";
         output += ".contacts h1 { margin-top: auto; margin-bottom: auto; font-size: 18pt; }\n";
+        output += ".appendix h1 { margin-top: auto; margin-bottom: auto; font-size: 18pt; }\n";
+        output += ".glossary h1 { margin-top: auto; margin-bottom: auto; font-size: 18pt; }\n";
+        output += ".abbreviations h1 { margin-top: auto; margin-bottom: auto; font-size: 18pt; }\n";
+        output += ".note-body { margin-left: 1em; }\n";
         output += "\n";
         output += ".contact-citation {margin-left: -3px; font-style: normal; font-size: small;}\n";
         output += "\n";
@@ -192,6 +191,11 @@ impl PMDSharedHTMLSerializer for PMDPDFSerializer {
         output += "    content: target-counter(attr(href), page);\n";
         output += "}\n";
         output += "\n";
+        output += ".index-entries { list-style: none; padding-left: 0; columns: 2; }\n";
+        output += ".index-page::after {\n";
+        output += "    content: target-counter(attr(href), page);\n";
+        output += "}\n";
+        output += "\n";
         output += ".toci-1 {\n";
         output += "    display: grid;\n";
         output += "    grid-template-columns: auto max-content;\n";
@@ -258,12 +262,31 @@ impl PMDSharedHTMLSerializer for PMDPDFSerializer {
         output += "    width: calc(100%);\n";
         output += "    padding-left: 1em;\n";
         output += "}\n";
+        output += "\n";
+        output += ".factbox.note    { border-left: 4pt solid #4a7bd0; }\n";
+        output += ".factbox.warning { border-left: 4pt solid #d0904a; background-color: #fdf3e7; }\n";
+        output += ".factbox.tip     { border-left: 4pt solid #4aa05a; }\n";
+        output += ".factbox.definition, .factbox.theorem { border-left: 4pt solid #555555; }\n";
+        output += ".factbox.example { border-left: 4pt dashed #888888; }\n";
+        output += ".factbox .factbox { background-color: #e6e6e6; margin-right: 1em; }\n";
+        output += "\n";
+        output += "dt { font-weight: bold; }\n";
+        output += "dd { margin-left: 2em; margin-bottom: .5em; }\n";
+        // the expansion of an abbreviation can't be hovered on paper, so it isn't marked
+        output += "abbr { text-decoration: none; }\n";
 
         output += "\n";
         output += "@media print {\n";
         if !frontmatter.has("pdf-no-first-page") {
             output += "    .title {\n";
             output += "        page-break-after: always;\n";
+            output += "        display: flex;\n";
+            output += "        flex-direction: column;\n";
+            output += "        height: 100vh;\n";
+            output += "    }\n";
+            output += "\n";
+            output += "    .title .bottom {\n";
+            output += "        margin-top: auto;\n";
             output += "    }\n";
         } else {
             output += "    .title {\n";
@@ -276,7 +299,23 @@ impl PMDSharedHTMLSerializer for PMDPDFSerializer {
         output += "        page-break-after: always;\n";
         output += "    }\n";
         output += "\n";
+        output += "    .appendix {\n";
+        output += "        page-break-before: always;\n";
+        output += "    }\n";
+        output += "\n";
+        // landscape sections are only supported by the native pdf backend
+        if geometry.columns > 1 {
+            output += format!("    main {{ column-count: {}; column-gap: {}pt; }}\n", geometry.columns, geometry.column_gap).as_str();
+            output += "\n";
+        }
+        if geometry.mirrored {
+            output += format!("    @page :right {{ margin-left: {}pt; margin-right: {}pt; }}\n", geometry.inner, geometry.outer).as_str();
+            output += format!("    @page :left  {{ margin-left: {}pt; margin-right: {}pt; }}\n", geometry.outer, geometry.inner).as_str();
+            output += "\n";
+        }
         output += "    @page {\n";
+        output += format!("       size: {}pt {}pt;\n", geometry.width, geometry.height).as_str();
+        output += format!("       margin: {}pt {}pt {}pt {}pt;\n", geometry.top, geometry.outer, geometry.bottom, geometry.inner).as_str();
         output += format!("       @top-left   {{content: {top_left  }}}\n").as_str();
         output += format!("       @top-center {{content: {top_center}}}\n").as_str();
         output += format!("       @top-right  {{content: {top_right }}}\n").as_str();
@@ -286,6 +325,17 @@ impl PMDSharedHTMLSerializer for PMDPDFSerializer {
         output += format!("       @bottom-right  {{content: {bottom_right }}}\n").as_str();
         output += "    }\n";
         output += "\n";
+        // even pages are the left hand pages
+        for (key, margin_box) in [("pdf-header-even", "top"), ("pdf-footer-even", "bottom")] {
+            let row = Self::pdf_string_row(&frontmatter, key);
+            if row.iter().all(|text| text == "none") { continue }
+            output += "    @page :left {\n";
+            for (text, place) in row.iter().zip(["left", "center", "right"]) {
+                output += format!("       @{margin_box}-{place} {{content: {text}}}\n").as_str();
+            }
+            output += "    }\n";
+            output += "\n";
+        }
         if !frontmatter.has("pdf-no-first-page") {
             output += "   @page:first {\n";
             output += "       @top-left   {content: none}\n";
@@ -351,6 +401,46 @@ impl PMDSharedHTMLSerializer for PMDPDFSerializer {
         String::new()
     }
 
+    // the first page is filled in from the title page template
+    fn title_section(&mut self, md: &PawsMarkdown) -> Option<Vec<String>> {
+        if md.header.frontmatter.as_ref().is_some_and(|frontmatter| frontmatter.has("pdf-no-first-page")) {
+            return None
+        }
+        let mut lines = vec![];
+        let mut bottom = false;
+        for item in title_page::title_page(md) {
+            let indent = if bottom { "    " } else { "" };
+            match item {
+                TitleItem::Text { text, size, align } => {
+                    let align = match align {
+                        TitleAlign::Left   => "left",
+                        TitleAlign::Center => "center",
+                        TitleAlign::Right  => "right",
+                    };
+                    let tag = match size {
+                        TitleSize::Title    => "h1",
+                        TitleSize::Subtitle => "h2",
+                        TitleSize::Heading  => "h3",
+                        TitleSize::Text     => "p",
+                    };
+                    lines.push(format!("{indent}<{tag} style='text-align: {align}'>{}</{tag}>", sanitize_text(&text)));
+                },
+                TitleItem::Space(space) => lines.push(format!("{indent}<div style='height: {space}pt'></div>")),
+                TitleItem::Image(src) => {
+                    // tmp.html is written to the current directory, the image is relative to the markdown file
+                    let src = md.header.resolve_path(&src).display().to_string();
+                    lines.push(format!("{indent}<img src='{}' class='banner'></img>", sanitize_id(&src)));
+                },
+                TitleItem::Bottom => if !bottom {
+                    lines.push("<div class='bottom'>".into());
+                    bottom = true;
+                },
+            }
+        }
+        if bottom { lines.push("</div>".into()); }
+        Some(lines)
+    }
+
     fn references(&mut self) -> &OrderedMap<String, Reference<ReferenceDefinition>> {
         &self.references
     }
@@ -397,11 +487,22 @@ impl PMDPDFSerializer {
         value
     }
 
-    fn parse_pdf_string(text: &Value) -> String {
+    // the left, center and right text of e.g. pdf-header, where pdf-header is the same as pdf-header-center
+    fn pdf_string_row(frontmatter: &Frontmatter, key: &str) -> [String; 3] {
+        let mut row = ["left", "center", "right"].map(|place| Self::parse_pdf_string(&frontmatter[format!("{key}-{place}").as_str()], frontmatter));
+        if row[1] == "none" {
+            row[1] = Self::parse_pdf_string(&frontmatter[key], frontmatter);
+        }
+        row
+    }
+
+    fn parse_pdf_string(text: &Value, frontmatter: &Frontmatter) -> String {
         if let Some(text) = text.as_string() {
-            let mut text = text.clone();
+            let mut text = substitute_document_fields(&text, frontmatter).replace('"', "\\\"");
             text.insert(0, '"');
             text.push('"');
+            text = text.replace("%chapter", "\" string(chapter) \"");
+            text = text.replace("%section", "\" string(section) \"");
             text = text.replace("%pages", "\"counter(pages)\"");
             text = text.replace("%page", "\"counter(page)\"");
             text = text.replace("%np", "\"counter(pages)\"");
@@ -471,6 +572,10 @@ impl PMDPDFSerializer {
         output += ".bibliography h1 { margin-top: auto; margin-bottom: auto; font-size: 18pt; }\n";
         output += ".notes h1 { margin-top: auto; margin-bottom: auto; font-size: 18pt; }\n";
         output += ".contacts h1 { margin-top: auto; margin-bottom: auto; font-size: 18pt; }\n";
+        output += ".appendix h1 { margin-top: auto; margin-bottom: auto; font-size: 18pt; }\n";
+        output += ".glossary h1 { margin-top: auto; margin-bottom: auto; font-size: 18pt; }\n";
+        output += ".abbreviations h1 { margin-top: auto; margin-bottom: auto; font-size: 18pt; }\n";
+        output += ".note-body { margin-left: 1em; }\n";
         output += "\n";
         output += ".contact-citation {margin-left: -3px; font-style: normal; font-size: small;}\n";
         output += "\n";
@@ -546,6 +651,11 @@ impl PMDPDFSerializer {
         output += "    content: target-counter(attr(href), page);\n";
         output += "}\n";
         output += "\n";
+        output += ".index-entries { list-style: none; padding-left: 0; columns: 2; }\n";
+        output += ".index-page::after {\n";
+        output += "    content: target-counter(attr(href), page);\n";
+        output += "}\n";
+        output += "\n";
         output += ".toci-1 {\n";
         output += "    display: grid;\n";
         output += "    grid-template-columns: auto max-content;\n";
@@ -588,6 +698,10 @@ impl PMDPDFSerializer {
         output += "        page-break-after: always;\n";
         output += "    }\n";
         output += "\n";
+        output += "    .appendix {\n";
+        output += "        page-break-before: always;\n";
+        output += "    }\n";
+        output += "\n";
         output += "    @page {\n";
         output += format!("       @top-left   {{content: {top_left  }}}\n").as_str();
         output += format!("       @top-center {{content: {top_center}}}\n").as_str();
@@ -1017,6 +1131,26 @@ impl PMDSerializer for PMDPDFSerializer {
         */
     }
 
+    fn convert_strikethrough(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_strikethrough(text)
+    }
+
+    fn convert_superscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_superscript(text)
+    }
+
+    fn convert_subscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_subscript(text)
+    }
+
+    fn convert_highlight(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_highlight(text)
+    }
+
+    fn convert_underline(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_underline(text)
+    }
+
     fn convert_inlinecode(&mut self, text: &String) -> Result<String> {
         // Ok(format!("<code>{text}</code>"))
         self.common.convert_inlinecode(text)
@@ -1087,6 +1221,10 @@ impl PMDSerializer for PMDPDFSerializer {
         */
     }
 
+    fn convert_definition_list(&mut self, list: &Vec<(BlogBody, Vec<BlogBody>)>, id: &String) -> Result<String> {
+        self.common.convert_definition_list(list, id)
+    }
+
     fn convert_list(&mut self, list: &Vec<BlogBody>, id: &String) -> Result<String> {
         self.common.convert_list(list, id)
         /*
@@ -1185,6 +1323,10 @@ impl PMDSerializer for PMDPDFSerializer {
         self.common.convert_factbox_note(factbox, factbox_id, id)
     }
     
+    fn convert_abbreviation(&mut self, abbreviation: &String, expansion: &String) -> Result<String> {
+        self.common.convert_abbreviation(abbreviation, expansion)
+    }
+
     fn convert_contact_citation(&mut self, id: &String) -> Result<String> {
         self.common.convert_contact_citation(id)
         // if let Some(reference) = self.contacts.get_mut(id) {
@@ -1214,6 +1356,10 @@ impl PMDSerializer for PMDPDFSerializer {
         self.common.convert_note(id)
     }
 
+    fn convert_blocks(&mut self, blocks: &Vec<(BlogBody, String)>) -> Result<String> {
+        self.common.convert_blocks(blocks)
+    }
+
     fn convert_toc(&mut self) -> Result<String> {
         // self.common.convert_toc()
        
@@ -1240,11 +1386,14 @@ impl PMDSerializer for PMDPDFSerializer {
                 for (elem, depth, id) in &toc.headers {
                     // let text = self.convert_element(elem)?;
                     // let id    = Self::generate_id(&text, ||"missing".into());
-                    let text = if let &box BlogBody::FactBox(fbox) = &elem {
-                        fbox.title.clone()
+                    let mut text = if let &box BlogBody::FactBox(fbox) = &elem {
+                        fbox.display_title()
                     } else {
                         self.convert_element(no_id!(elem))?
                     };
+                    if let Some(number) = self.header.section_number(id) {
+                        text = format!("<span class='section-number'>{number}</span> {text}");
+                    }
                     result += self.common.tab().as_str();
                     result += format!("<li class='toci-{depth}'>\n").as_str();
                     self.common.push_tab();
@@ -1290,6 +1439,35 @@ impl PMDSerializer for PMDPDFSerializer {
             result += self.common.tab().as_str();
             result += "</ul>\n";
 
+            if !toc.appendix.is_empty() {
+                result += self.common.tab().as_str();
+                result += "<ul class='toc-appendix'>\n";
+                self.common.push_tab();
+                    for (elem, depth, id) in &toc.appendix {
+                        let mut text = if let &box BlogBody::FactBox(fbox) = &elem {
+                            fbox.display_title()
+                        } else {
+                            self.convert_element(no_id!(elem))?
+                        };
+                        if let Some(number) = self.header.section_number(id) {
+                            text = format!("<span class='section-number'>{number}</span> {text}");
+                        }
+                        result += self.common.tab().as_str();
+                        result += format!("<li class='toci-{depth}'>\n").as_str();
+                        self.common.push_tab();
+                            result += self.common.tab().as_str();
+                            result += format!("<a class='toci-header' href='#{id}'>{text}</a>\n").as_str();
+                            result += self.common.tab().as_str();
+                            result += format!("<a class='toci-number' href='#{id}'></a>\n").as_str();
+                        self.common.pop_tab();
+                        result += self.common.tab().as_str();
+                        result += "</li>\n";
+                    }
+                self.common.pop_tab();
+                result += self.common.tab().as_str();
+                result += "</ul>\n";
+            }
+
         self.common.pop_tab();
         result += self.common.tab().as_str();
         result += "</section>\n";
@@ -1297,6 +1475,14 @@ impl PMDSerializer for PMDPDFSerializer {
         Ok(result)
     }
 
+    fn convert_index(&mut self) -> Result<String> {
+        self.common.convert_index()
+    }
+
+    fn convert_index_term(&mut self, term: &String, id: &String) -> Result<String> {
+        self.common.convert_index_term(term, id)
+    }
+
     fn convert_page_break(&mut self) -> Result<String> {
         self.common.convert_page_break()
         /*
@@ -1,6 +1,7 @@
 use crate::*;
 use anyhow::*;
 use ordered_map::OrderedMap;
+use std::collections::HashMap;
 
 pub struct PMDPureTextSerializer {
     pub notes_title: String,
@@ -8,6 +9,8 @@ pub struct PMDPureTextSerializer {
     pub hide_notes: bool,
     pub hide_contacts: bool,
     pub toc: Option<TableOfContent>,
+    pub index: Option<Index>,
+    pub section_numbers: HashMap<String, String>,
     pub references: OrderedMap<String, ReferenceDefinition>,
 }
 
@@ -19,6 +22,8 @@ impl PMDPureTextSerializer {
             hide_notes: false, 
             hide_contacts: false, 
             toc: None, 
+            index: None,
+            section_numbers: HashMap::new(),
             references: OrderedMap::new()
         } 
     }
@@ -30,7 +35,7 @@ impl PMDSerializer for PMDPureTextSerializer {
     }
 
     fn convert_factbox(&mut self, factbox: &FactBox, _: &String) -> Result<String> {
-        let title = &factbox.title;
+        let title = factbox.display_title();
         let mut result = String::new();
         result += "--------------------------------------------------------------------------------\n";
         result += format!("| {title}   \n").as_str();
@@ -81,13 +86,27 @@ impl PMDSerializer for PMDPureTextSerializer {
             },
             _ => {
                 let link = self.convert_element(no_id!(&link.base))?;
+                if link.trim().is_empty() && let Some(target) = text.strip_prefix('#') {
+                    if let Some(number) = self.section_numbers.get(target) {
+                        return Ok(number.clone());
+                    }
+                }
+                // an autolink is just its address
+                if link == text {
+                    return Ok(text);
+                }
                 Ok(format!("{link}({text})"))
             }
         }
     }
 
-    fn convert_header(&mut self, text: &Box<BlogBody>, _: usize, _: &String) -> Result<String> {
-        self.convert_element(no_id!(text))
+    fn convert_header(&mut self, text: &Box<BlogBody>, _: usize, id: &String) -> Result<String> {
+        let text = self.convert_element(no_id!(text))?;
+        if let Some(number) = self.section_numbers.get(id) {
+            Ok(format!("{number} {text}"))
+        } else {
+            Ok(text)
+        }
     }
 
     fn convert_italics(&mut self, text: &Box<BlogBody>) -> Result<String> {
@@ -98,6 +117,29 @@ impl PMDSerializer for PMDPureTextSerializer {
         self.convert_element(no_id!(text))
     }
 
+    fn convert_strikethrough(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        let text = self.convert_element(no_id!(text))?;
+        Ok(format!("~~{text}~~"))
+    }
+
+    fn convert_superscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        let text = self.convert_element(no_id!(text))?;
+        Ok(format!("^{text}"))
+    }
+
+    fn convert_subscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        let text = self.convert_element(no_id!(text))?;
+        Ok(format!("_{text}"))
+    }
+
+    fn convert_highlight(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.convert_element(no_id!(text))
+    }
+
+    fn convert_underline(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.convert_element(no_id!(text))
+    }
+
     fn convert_inlinecode(&mut self, text: &String) -> Result<String> {
         Ok(text.clone())
     }
@@ -136,6 +178,10 @@ impl PMDSerializer for PMDPureTextSerializer {
         }
     }
     
+    fn convert_abbreviation(&mut self, abbreviation: &String, _: &String) -> Result<String> {
+        Ok(abbreviation.clone())
+    }
+
     fn convert_contact_citation(&mut self, _: &String) -> Result<String> {
         Ok("".into())
     }
@@ -148,6 +194,15 @@ impl PMDSerializer for PMDPureTextSerializer {
         }
     }
     
+    fn convert_blocks(&mut self, blocks: &Vec<(BlogBody, String)>) -> Result<String> {
+        let mut block_elements: Vec<String> = vec![];
+        for (element, id) in blocks {
+            let text = self.convert_element((element, id))?;
+            block_elements.push(text.trim_end().to_string());
+        }
+        Ok(block_elements.join("\n\n"))
+    }
+    
     fn convert_factbox_note(&mut self, _: &FactBox, _: Option<&String>, id: &String) -> Result<String> {
         Ok(format!("^{id}").to_string())
     }
@@ -162,6 +217,19 @@ impl PMDSerializer for PMDPureTextSerializer {
         Ok(text)
     }
 
+    fn convert_definition_list(&mut self, list: &Vec<(BlogBody, Vec<BlogBody>)>, _: &String) -> Result<String> {
+        let mut list_elements: Vec<String> = vec![];
+        for (term, definitions) in list {
+            let mut text = self.convert_element(no_id!(term))?;
+            for definition in definitions {
+                text += format!("\n    {}", self.convert_element(no_id!(definition))?).as_str();
+            }
+            list_elements.push(text);
+        }
+        let text = list_elements.join("\n");
+        Ok(text)
+    }
+
     fn convert_paragraph(&mut self, text: &Box<BlogBody>, _: &String) -> Result<String> {
         self.convert_element(no_id!(text))
     }
@@ -191,10 +259,24 @@ impl PMDSerializer for PMDPureTextSerializer {
         let title = &toc.title;
 
         result += format!("{title}:\n").as_str();
-        for (text, depth, _) in &toc.headers {
+        for (text, depth, id) in &toc.headers {
             for _ in 0..depth.clone() {
                 result += "    ";
             }
+            if let Some(number) = self.section_numbers.get(id) {
+                result += format!("{number} ").as_str();
+            }
+            result += self.convert_element(no_id!(&text))?.as_str();
+            result += "\n"
+        }
+
+        for (text, depth, id) in &toc.appendix {
+            for _ in 0..depth.clone() {
+                result += "    ";
+            }
+            if let Some(number) = self.section_numbers.get(id) {
+                result += format!("{number} ").as_str();
+            }
             result += self.convert_element(no_id!(&text))?.as_str();
             result += "\n"
         }
@@ -203,6 +285,36 @@ impl PMDSerializer for PMDPureTextSerializer {
     }
 
 
+    fn convert_index_term(&mut self, _: &String, _: &String) -> Result<String> {
+        Ok("".into())
+    }
+
+    fn convert_index(&mut self) -> Result<String> {
+        if self.index.is_none() { return Err(anyhow!("")); }
+        let index = self.index.clone().unwrap();
+        let mut result = String::new();
+
+        result += format!("{}:\n", index.title).as_str();
+        for (term, locations) in &index.entries {
+            let mut sections = Vec::<String>::new();
+            for (_, section) in locations {
+                let mut text = if let Some(text) = index.sections.get(section) {
+                    self.convert_element(no_id!(text))?
+                } else {
+                    continue
+                };
+                if let Some(number) = self.section_numbers.get(section) {
+                    text = format!("{number} {text}");
+                }
+                if !sections.contains(&text) {
+                    sections.push(text);
+                }
+            }
+            result += format!("    {term}: {}\n", sections.join(", ")).as_str();
+        }
+        Ok(result)
+    }
+
     fn convert_page_break(&mut self) -> Result<String> {
         Ok("---\n".into())
     }
@@ -211,13 +323,15 @@ impl PMDSerializer for PMDPureTextSerializer {
         let mut output = String::new();
         self.references = md.references.clone();
         self.toc = md.header.toc.clone();
+        self.index = md.header.index.clone();
+        self.section_numbers = md.header.section_numbers.clone();
         self.hide_references = md.header.hide_references;
         self.hide_notes      = md.header.hide_notes;
         self.hide_contacts   = md.header.hide_contacts;
         self.notes_title     = md.header.notes_title.clone();
 
-        for (element, _) in &md.body {
-            let result = self.convert_element(no_id!(element))?;
+        for (element, id) in &md.body {
+            let result = self.convert_element((element, id))?;
             output += result.as_str();
             match element {
                 BlogBody::Header(_, _) => {
@@ -235,11 +349,34 @@ impl PMDSerializer for PMDPureTextSerializer {
             output += format!("{}: \n", md.header.notes_title).as_str();
             for (key, val) in &md.notes {
                 let result = self.convert_element(no_id!(val))?;
+                let result = result.lines().enumerate()
+                    .map(|(i, line)| if i == 0 || line.is_empty() { line.to_string() } else { format!("        {line}") })
+                    .collect::<Vec<_>>()
+                    .join("\n");
                 output += format!("    ^{key}: {result}\n").as_str();
             }
             output.push('\n');
         }
         
+        if !md.glossary.is_empty() {
+            output += "--------------------------------------------------------------------------------\n";
+            output += format!("{}: \n", md.header.glossary_title).as_str();
+            for (term, definition, _) in &md.glossary {
+                let result = self.convert_element(no_id!(definition))?;
+                output += format!("    {term}: {result}\n").as_str();
+            }
+            output.push('\n');
+        }
+        
+        if md.header.list_abbreviations && !md.abbreviations.is_empty() {
+            output += "--------------------------------------------------------------------------------\n";
+            output += format!("{}: \n", md.header.abbreviations_title).as_str();
+            for (abbreviation, expansion) in &md.abbreviations {
+                output += format!("    {abbreviation}: {expansion}\n").as_str();
+            }
+            output.push('\n');
+        }
+        
         if !(md.contacts.is_empty() || self.hide_contacts) {
             output += "--------------------------------------------------------------------------------\n";
             output += format!("{}: \n", md.header.contacts_title).as_str();
@@ -319,6 +456,24 @@ impl PMDSerializer for PMDPureTextSerializer {
             }
         }
 
+        if !md.appendix.is_empty() {
+            output += "--------------------------------------------------------------------------------\n";
+            output += format!("{}: \n", md.header.appendix_title).as_str();
+            for (element, id) in &md.appendix {
+                let result = self.convert_element((element, id))?;
+                output += result.as_str();
+                match element {
+                    BlogBody::Header(_, _) => {
+                        output += ":\n";
+                    },
+                    _ => {
+                        output += "\n";
+                        output += "\n";
+                    }
+                }
+            }
+        }
+
         Ok(output)
     }
 }
@@ -13,18 +13,28 @@ pub trait PMDSerializer {
     fn convert_header(&mut self, text: &Box<BlogBody>, depth: usize, id: &String) -> Result<String>;
     fn convert_italics(&mut self, text: &Box<BlogBody>) -> Result<String>;
     fn convert_bold(&mut self, text: &Box<BlogBody>) -> Result<String>;
+    fn convert_strikethrough(&mut self, text: &Box<BlogBody>) -> Result<String>;
+    fn convert_superscript(&mut self, text: &Box<BlogBody>) -> Result<String>;
+    fn convert_subscript(&mut self, text: &Box<BlogBody>) -> Result<String>;
+    fn convert_highlight(&mut self, text: &Box<BlogBody>) -> Result<String>;
+    fn convert_underline(&mut self, text: &Box<BlogBody>) -> Result<String>;
     fn convert_inlinecode(&mut self, text: &String) -> Result<String>;
     fn convert_codeblock(&mut self, text: &String, id: &String) -> Result<String>;
     fn convert_image(&mut self, src: &String, alt: &String, id: &String) -> Result<String>;
     fn convert_quote(&mut self, lines: &Vec<BlogBody>, id: &String) -> Result<String>;
     fn convert_list(&mut self, list: &Vec<BlogBody>, id: &String) -> Result<String>;
+    fn convert_definition_list(&mut self, list: &Vec<(BlogBody, Vec<BlogBody>)>, id: &String) -> Result<String>;
     fn convert_paragraph(&mut self, text: &Box<BlogBody>, id: &String) -> Result<String>;
     fn convert_text(&mut self, text: &String) -> Result<String>;
     fn convert_span(&mut self, span: &Span) -> Result<String>;
     fn convert_citation(&mut self, citation: &String) -> Result<String>;
     fn convert_contact_citation(&mut self, citation: &String) -> Result<String>;
+    fn convert_abbreviation(&mut self, abbreviation: &String, expansion: &String) -> Result<String>;
     fn convert_note(&mut self, id: &String) -> Result<String>;
+    fn convert_blocks(&mut self, blocks: &Vec<(BlogBody, String)>) -> Result<String>;
     fn convert_toc(&mut self) -> Result<String>;
+    fn convert_index(&mut self) -> Result<String>;
+    fn convert_index_term(&mut self, term: &String, id: &String) -> Result<String>;
     fn convert_page_break(&mut self) -> Result<String>;
     // fn convert_embedded_link(&mut self, src: &String, alt: &String) -> Result<String>;
 
@@ -42,19 +52,29 @@ pub trait PMDSerializer {
                 (BlogBody::Header(text, depth), id)   => result.push(self.convert_header(text, *depth, id)?),
                 (BlogBody::Italics(text), _)                 => result.push(self.convert_italics(text)?),
                 (BlogBody::Bold(text), _)                    => result.push(self.convert_bold(text)?)   ,
+                (BlogBody::Strikethrough(text), _)                   => result.push(self.convert_strikethrough(text)?),
+                (BlogBody::Superscript(text), _)                     => result.push(self.convert_superscript(text)?),
+                (BlogBody::Subscript(text), _)                       => result.push(self.convert_subscript(text)?),
+                (BlogBody::Highlight(text), _)                       => result.push(self.convert_highlight(text)?),
+                (BlogBody::Underline(text), _)                       => result.push(self.convert_underline(text)?),
                 (BlogBody::InlineCode(text), _)                     => result.push(self.convert_inlinecode(text)?) ,
                 (BlogBody::CodeBlock(text), id)                      => result.push(self.convert_codeblock(text, id)?)  ,
                 (BlogBody::Image(text, alt), id)            => result.push(self.convert_image(text, alt, id)?),
                 // BlogBody::EmbeddedLink(text, alt) => self.convert_embedresult.push(ded_link(text, alt),
                 (BlogBody::Quote(lines), id)                  => result.push(self.convert_quote(lines, id)?),
                 (BlogBody::List(list), id)                    => result.push(self.convert_list(list, id)?),
+                (BlogBody::DefinitionList(list), id)          => result.push(self.convert_definition_list(list, id)?),
                 (BlogBody::Paragraph(text), id)               => result.push(self.convert_paragraph(text, id)?),
                 (BlogBody::Text(text), _)                           => result.push(self.convert_text(text)?),
                 (BlogBody::Span(span), _)                             => result.push(self.convert_span(span)?),
                 (BlogBody::Citation(text), _)                       => result.push(self.convert_citation(text)?),
                 (BlogBody::ContactCitation(text), _)                => result.push(self.convert_contact_citation(text)?),
+                (BlogBody::Abbreviation(text, expansion), _)        => result.push(self.convert_abbreviation(text, expansion)?),
                 (BlogBody::Note(text), _)                           => result.push(self.convert_factbox_note(factbox, factbox_id, text)?),
+                (BlogBody::Blocks(blocks), _)                       => result.push(self.convert_blocks(blocks)?),
                 (BlogBody::TOCLocationMarker, _)                             => result.push(self.convert_toc()?),
+                (BlogBody::IndexLocationMarker, _)                           => result.push(self.convert_index()?),
+                (BlogBody::IndexTerm(term, id), _)                  => result.push(self.convert_index_term(term, id)?),
                 (BlogBody::PageBreak, _)                                     => result.push(self.convert_page_break()?),
             }
         }
@@ -70,17 +90,24 @@ pub trait PMDSerializer {
             (BlogBody::Header(text, depth), id)     => self.convert_header(text, *depth, id),
             (BlogBody::Italics(text), _)           => self.convert_italics(text),
             (BlogBody::Bold(text), _)              => self.convert_bold(text)   ,
+            (BlogBody::Strikethrough(text), _)     => self.convert_strikethrough(text),
+            (BlogBody::Superscript(text), _)       => self.convert_superscript(text),
+            (BlogBody::Subscript(text), _)         => self.convert_subscript(text),
+            (BlogBody::Highlight(text), _)         => self.convert_highlight(text),
+            (BlogBody::Underline(text), _)         => self.convert_underline(text),
             (BlogBody::InlineCode(text), _)        => self.convert_inlinecode(text) ,
             (BlogBody::CodeBlock(text), id)         => self.convert_codeblock(text, id)  ,
             (BlogBody::Image(text, alt), id)        => self.convert_image(text, alt, id),
             // BlogBody::EmbeddedLink(text, alt) => self.convert_embedded_link(text, alt),
             (BlogBody::Quote(lines), id)            => self.convert_quote(lines, id),
             (BlogBody::List(list), id)              => self.convert_list(list, id),
+            (BlogBody::DefinitionList(list), id)    => self.convert_definition_list(list, id),
             (BlogBody::Paragraph(text), id)         => self.convert_paragraph(text, id),
             (BlogBody::Text(text), _)              => self.convert_text(text),
             (BlogBody::Span(span), _)              => self.convert_span(span),
             (BlogBody::Citation(text), _)          => self.convert_citation(text),
             (BlogBody::ContactCitation(text), _)   => self.convert_contact_citation(text),
+            (BlogBody::Abbreviation(text, expansion), _) => self.convert_abbreviation(text, expansion),
             (BlogBody::Note(text), _)              => {
                 if let Some((factbox, id)) = self.current_factbox() {
                     self.convert_factbox_note(&factbox, id.as_ref(), text)
@@ -88,7 +115,10 @@ pub trait PMDSerializer {
                     self.convert_note(text)
                 }
             },
+            (BlogBody::Blocks(blocks), _)          => self.convert_blocks(blocks),
             (BlogBody::TOCLocationMarker, _)       => self.convert_toc(),
+            (BlogBody::IndexLocationMarker, _)     => self.convert_index(),
+            (BlogBody::IndexTerm(term, id), _)     => self.convert_index_term(term, id),
             (BlogBody::PageBreak, _)               => self.convert_page_break(),
         }
     }
@@ -70,6 +70,41 @@ impl PMDSerializer for PMDWASMSerializer {
         }
     }
 
+    fn convert_strikethrough(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        match self {
+            PMDWASMSerializer::AsPDF(x)   => x.convert_strikethrough(text),
+            PMDWASMSerializer::AsHTML(x) => x.convert_strikethrough(text),
+        }
+    }
+
+    fn convert_superscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        match self {
+            PMDWASMSerializer::AsPDF(x)   => x.convert_superscript(text),
+            PMDWASMSerializer::AsHTML(x) => x.convert_superscript(text),
+        }
+    }
+
+    fn convert_subscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        match self {
+            PMDWASMSerializer::AsPDF(x)   => x.convert_subscript(text),
+            PMDWASMSerializer::AsHTML(x) => x.convert_subscript(text),
+        }
+    }
+
+    fn convert_highlight(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        match self {
+            PMDWASMSerializer::AsPDF(x)   => x.convert_highlight(text),
+            PMDWASMSerializer::AsHTML(x) => x.convert_highlight(text),
+        }
+    }
+
+    fn convert_underline(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        match self {
+            PMDWASMSerializer::AsPDF(x)   => x.convert_underline(text),
+            PMDWASMSerializer::AsHTML(x) => x.convert_underline(text),
+        }
+    }
+
     fn convert_inlinecode(&mut self, text: &String) -> Result<String> {
         match self {
             PMDWASMSerializer::AsPDF(x)   => x.convert_inlinecode(text),
@@ -98,6 +133,13 @@ impl PMDSerializer for PMDWASMSerializer {
         }
     }
 
+    fn convert_definition_list(&mut self, list: &Vec<(BlogBody, Vec<BlogBody>)>, id: &String) -> Result<String> {
+        match self {
+            PMDWASMSerializer::AsPDF(x)   => x.convert_definition_list(list, id),
+            PMDWASMSerializer::AsHTML(x) => x.convert_definition_list(list, id),
+        }
+    }
+
     fn convert_list(&mut self, list: &Vec<BlogBody>, id: &String) -> Result<String> {
         match self {
             PMDWASMSerializer::AsPDF(x)   => x.convert_list(list, id),
@@ -126,6 +168,13 @@ impl PMDSerializer for PMDWASMSerializer {
         }
     }
 
+    fn convert_abbreviation(&mut self, abbreviation: &String, expansion: &String) -> Result<String> {
+        match self {
+            PMDWASMSerializer::AsPDF(x)   => x.convert_abbreviation(abbreviation, expansion),
+            PMDWASMSerializer::AsHTML(x) => x.convert_abbreviation(abbreviation, expansion),
+        }
+    }
+
     fn convert_citation(&mut self, citation: &String) -> Result<String> {
         match self {
             PMDWASMSerializer::AsPDF(x)   => x.convert_citation(citation),
@@ -140,6 +189,13 @@ impl PMDSerializer for PMDWASMSerializer {
         }
     }
 
+    fn convert_blocks(&mut self, blocks: &Vec<(BlogBody, String)>) -> Result<String> {
+        match self {
+            PMDWASMSerializer::AsPDF(x)   => x.convert_blocks(blocks),
+            PMDWASMSerializer::AsHTML(x) => x.convert_blocks(blocks),
+        }
+    }
+
     fn convert_toc(&mut self) -> Result<String> {
         match self {
             PMDWASMSerializer::AsPDF(x)   => x.convert_toc(),
@@ -147,6 +203,20 @@ impl PMDSerializer for PMDWASMSerializer {
         }
     }
 
+    fn convert_index(&mut self) -> Result<String> {
+        match self {
+            PMDWASMSerializer::AsPDF(x)   => x.convert_index(),
+            PMDWASMSerializer::AsHTML(x) => x.convert_index(),
+        }
+    }
+
+    fn convert_index_term(&mut self, term: &String, id: &String) -> Result<String> {
+        match self {
+            PMDWASMSerializer::AsPDF(x)   => x.convert_index_term(term, id),
+            PMDWASMSerializer::AsHTML(x) => x.convert_index_term(term, id),
+        }
+    }
+
     fn convert_page_break(&mut self) -> Result<String> {
         match self {
             PMDWASMSerializer::AsPDF(x)   => x.convert_page_break(),
@@ -641,6 +711,26 @@ impl PMDSerializer for PMDWASMSerializerHTML {
         */
     }
 
+    fn convert_strikethrough(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_strikethrough(text)
+    }
+
+    fn convert_superscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_superscript(text)
+    }
+
+    fn convert_subscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_subscript(text)
+    }
+
+    fn convert_highlight(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_highlight(text)
+    }
+
+    fn convert_underline(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_underline(text)
+    }
+
     fn convert_inlinecode(&mut self, text: &String) -> Result<String> {
         self.common.convert_inlinecode(text)
         // Ok(format!("<code>{text}</code>"))
@@ -728,6 +818,10 @@ impl PMDSerializer for PMDWASMSerializerHTML {
         */
     }
 
+    fn convert_definition_list(&mut self, list: &Vec<(BlogBody, Vec<BlogBody>)>, id: &String) -> Result<String> {
+        self.common.convert_definition_list(list, id)
+    }
+
     fn convert_list(&mut self, list: &Vec<BlogBody>, id: &String) -> Result<String> {
         self.common.convert_list(list, id)
         /*
@@ -812,6 +906,10 @@ impl PMDSerializer for PMDWASMSerializerHTML {
         */
     }
     
+    fn convert_abbreviation(&mut self, abbreviation: &String, expansion: &String) -> Result<String> {
+        self.common.convert_abbreviation(abbreviation, expansion)
+    }
+
     fn convert_citation(&mut self, id: &String) -> Result<String> {
         self.common.convert_citation(id)
         /*
@@ -848,6 +946,10 @@ impl PMDSerializer for PMDWASMSerializerHTML {
         }
         */
     }
+
+    fn convert_blocks(&mut self, blocks: &Vec<(BlogBody, String)>) -> Result<String> {
+        self.common.convert_blocks(blocks)
+    }
     
     fn convert_factbox_note(&mut self, factbox: &FactBox, factbox_id: Option<&String>, id: &String) -> Result<String> {
         self.common.convert_factbox_note(factbox, factbox_id, id)
@@ -899,6 +1001,14 @@ impl PMDSerializer for PMDWASMSerializerHTML {
         */
     }
 
+    fn convert_index(&mut self) -> Result<String> {
+        self.common.convert_index()
+    }
+
+    fn convert_index_term(&mut self, term: &String, id: &String) -> Result<String> {
+        self.common.convert_index_term(term, id)
+    }
+
     fn convert_page_break(&mut self) -> Result<String> {
         self.common.convert_page_break()
         /*
@@ -930,6 +1040,7 @@ impl PMDSharedHTMLSerializer for PMDWASMSerializerPDF {
     const LINK_ELEMENTS: bool = false;
     const POPUPS: bool = false;
     const SHOW_BACKREFS: bool = false;
+    const PAGE_REFERENCES: bool = true;
 
     fn get_header(&self) -> &BlogHeader {
         &self.header
@@ -1091,6 +1202,11 @@ impl PMDSharedHTMLSerializer for PMDWASMSerializerPDF {
         output += "    content: target-counter(attr(href), page);\n";
         output += "}\n";
         output += "\n";
+        output += ".index-entries { list-style: none; padding-left: 0; columns: 2; }\n";
+        output += ".index-page::after {\n";
+        output += "    content: target-counter(attr(href), page);\n";
+        output += "}\n";
+        output += "\n";
         output += ".toci-1 {\n";
         output += "    display: grid;\n";
         output += "    grid-template-columns: auto max-content;\n";
@@ -1157,6 +1273,18 @@ impl PMDSharedHTMLSerializer for PMDWASMSerializerPDF {
         output += "    width: calc(100%);\n";
         output += "    padding-left: 1em;\n";
         output += "}\n";
+        output += "\n";
+        output += ".factbox.note    { border-left: 4pt solid #4a7bd0; }\n";
+        output += ".factbox.warning { border-left: 4pt solid #d0904a; background-color: #fdf3e7; }\n";
+        output += ".factbox.tip     { border-left: 4pt solid #4aa05a; }\n";
+        output += ".factbox.definition, .factbox.theorem { border-left: 4pt solid #555555; }\n";
+        output += ".factbox.example { border-left: 4pt dashed #888888; }\n";
+        output += ".factbox .factbox { background-color: #e6e6e6; margin-right: 1em; }\n";
+        output += "\n";
+        output += "dt { font-weight: bold; }\n";
+        output += "dd { margin-left: 2em; margin-bottom: .5em; }\n";
+        // the expansion of an abbreviation can't be hovered on paper, so it isn't marked
+        output += "abbr { text-decoration: none; }\n";
 
         output += "\n";
         output += "@media print {\n";
@@ -1175,6 +1303,10 @@ impl PMDSharedHTMLSerializer for PMDWASMSerializerPDF {
         output += "        page-break-after: always;\n";
         output += "    }\n";
         output += "\n";
+        output += "    .appendix {\n";
+        output += "        page-break-before: always;\n";
+        output += "    }\n";
+        output += "\n";
         output += "    @page {\n";
         output += format!("       @top-left   {{content: {top_left  }}}\n").as_str();
         output += format!("       @top-center {{content: {top_center}}}\n").as_str();
@@ -1427,6 +1559,11 @@ impl PMDWASMSerializerPDF {
         output += "    content: target-counter(attr(href), page);\n";
         output += "}\n";
         output += "\n";
+        output += ".index-entries { list-style: none; padding-left: 0; columns: 2; }\n";
+        output += ".index-page::after {\n";
+        output += "    content: target-counter(attr(href), page);\n";
+        output += "}\n";
+        output += "\n";
         output += ".toci-1 {\n";
         output += "    display: grid;\n";
         output += "    grid-template-columns: auto max-content;\n";
@@ -1469,6 +1606,10 @@ impl PMDWASMSerializerPDF {
         output += "        page-break-after: always;\n";
         output += "    }\n";
         output += "\n";
+        output += "    .appendix {\n";
+        output += "        page-break-before: always;\n";
+        output += "    }\n";
+        output += "\n";
         output += "    @page {\n";
         output += format!("       @top-left   {{content: {top_left  }}}\n").as_str();
         output += format!("       @top-center {{content: {top_center}}}\n").as_str();
@@ -1847,6 +1988,26 @@ impl PMDSerializer for PMDWASMSerializerPDF {
         */
     }
 
+    fn convert_strikethrough(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_strikethrough(text)
+    }
+
+    fn convert_superscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_superscript(text)
+    }
+
+    fn convert_subscript(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_subscript(text)
+    }
+
+    fn convert_highlight(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_highlight(text)
+    }
+
+    fn convert_underline(&mut self, text: &Box<BlogBody>) -> Result<String> {
+        self.common.convert_underline(text)
+    }
+
     fn convert_inlinecode(&mut self, text: &String) -> Result<String> {
         // Ok(format!("<code>{text}</code>"))
         self.common.convert_inlinecode(text)
@@ -1917,6 +2078,10 @@ impl PMDSerializer for PMDWASMSerializerPDF {
         */
     }
 
+    fn convert_definition_list(&mut self, list: &Vec<(BlogBody, Vec<BlogBody>)>, id: &String) -> Result<String> {
+        self.common.convert_definition_list(list, id)
+    }
+
     fn convert_list(&mut self, list: &Vec<BlogBody>, id: &String) -> Result<String> {
         self.common.convert_list(list, id)
         /*
@@ -1991,6 +2156,10 @@ impl PMDSerializer for PMDWASMSerializerPDF {
         */
     }
     
+    fn convert_abbreviation(&mut self, abbreviation: &String, expansion: &String) -> Result<String> {
+        self.common.convert_abbreviation(abbreviation, expansion)
+    }
+
     fn convert_citation(&mut self, id: &String) -> Result<String> {
         self.common.convert_citation(id)
         /*
@@ -2020,6 +2189,10 @@ impl PMDSerializer for PMDWASMSerializerPDF {
         self.common.convert_note(id)
     }
 
+    fn convert_blocks(&mut self, blocks: &Vec<(BlogBody, String)>) -> Result<String> {
+        self.common.convert_blocks(blocks)
+    }
+
     fn convert_toc(&mut self) -> Result<String> {
         // self.common.convert_toc()
        
@@ -2046,11 +2219,14 @@ impl PMDSerializer for PMDWASMSerializerPDF {
                 for (elem, depth, id) in &toc.headers {
                     // let text = self.convert_element(elem)?;
                     // let id    = Self::generate_id(&text, ||"missing".into());
-                    let text = if let &box BlogBody::FactBox(fbox) = &elem {
-                        fbox.title.clone()
+                    let mut text = if let &box BlogBody::FactBox(fbox) = &elem {
+                        fbox.display_title()
                     } else {
                         self.convert_element(no_id!(elem))?
                     };
+                    if let Some(number) = self.header.section_number(id) {
+                        text = format!("<span class='section-number'>{number}</span> {text}");
+                    }
                     result += self.common.tab().as_str();
                     result += format!("<li class='toci-{depth}'>\n").as_str();
                     self.common.push_tab();
@@ -2096,6 +2272,35 @@ impl PMDSerializer for PMDWASMSerializerPDF {
             result += self.common.tab().as_str();
             result += "</ul>\n";
 
+            if !toc.appendix.is_empty() {
+                result += self.common.tab().as_str();
+                result += "<ul class='toc-appendix'>\n";
+                self.common.push_tab();
+                    for (elem, depth, id) in &toc.appendix {
+                        let mut text = if let &box BlogBody::FactBox(fbox) = &elem {
+                            fbox.display_title()
+                        } else {
+                            self.convert_element(no_id!(elem))?
+                        };
+                        if let Some(number) = self.header.section_number(id) {
+                            text = format!("<span class='section-number'>{number}</span> {text}");
+                        }
+                        result += self.common.tab().as_str();
+                        result += format!("<li class='toci-{depth}'>\n").as_str();
+                        self.common.push_tab();
+                            result += self.common.tab().as_str();
+                            result += format!("<a class='toci-header' href='#{id}'>{text}</a>\n").as_str();
+                            result += self.common.tab().as_str();
+                            result += format!("<a class='toci-number' href='#{id}'></a>\n").as_str();
+                        self.common.pop_tab();
+                        result += self.common.tab().as_str();
+                        result += "</li>\n";
+                    }
+                self.common.pop_tab();
+                result += self.common.tab().as_str();
+                result += "</ul>\n";
+            }
+
         self.common.pop_tab();
         result += self.common.tab().as_str();
         result += "</section>\n";
@@ -2103,6 +2308,14 @@ impl PMDSerializer for PMDWASMSerializerPDF {
         Ok(result)
     }
 
+    fn convert_index(&mut self) -> Result<String> {
+        self.common.convert_index()
+    }
+
+    fn convert_index_term(&mut self, term: &String, id: &String) -> Result<String> {
+        self.common.convert_index_term(term, id)
+    }
+
     fn convert_page_break(&mut self) -> Result<String> {
         self.common.convert_page_break()
         /*
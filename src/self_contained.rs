@@ -0,0 +1,276 @@
+use std::path::{Path, PathBuf};
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+#[cfg(not(feature = "wasm"))]
+use color_print::cprintln;
+
+// a self contained page has its stylesheets, scripts and fonts written into it and its local
+// images as data uris, so the single .html file works offline and can be sent to someone.
+// relative paths are looked up in the given directories, only the addresses written in the page
+// itself are downloaded. anything that can't be found is left out with a warning
+pub struct Inliner {
+    // where relative paths are looked for, the output directory first
+    directories: Vec<PathBuf>,
+    inlined_scripts: HashSet<String>,
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn base64(data: &[u8]) -> String {
+    let mut output = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let number = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for n in 0..4 {
+            if n <= chunk.len() {
+                output.push(BASE64_ALPHABET[(number >> (18 - n * 6)) as usize & 63] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+fn mime_type(path: &str) -> &'static str {
+    let path = path.split(['?', '#']).next().unwrap_or(path).to_lowercase();
+    match path.rsplit('.').next().unwrap_or("") {
+        "png"          => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif"          => "image/gif",
+        "svg"          => "image/svg+xml",
+        "webp"         => "image/webp",
+        "woff2"        => "font/woff2",
+        "woff"         => "font/woff",
+        "ttf"          => "font/ttf",
+        "otf"          => "font/otf",
+        "css"          => "text/css",
+        "js"           => "text/javascript",
+        _              => "application/octet-stream",
+    }
+}
+
+fn is_remote(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://") || path.starts_with("//")
+}
+
+// the value of an attribute in a tag, quoted with either ' or "
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    while let Some(start) = rest.find(name) {
+        let after = rest[start + name.len()..].trim_start();
+        let preceded = rest[..start].ends_with(|c: char| c.is_whitespace());
+        if preceded && let Some(value) = after.strip_prefix('=') {
+            let value = value.trim_start();
+            let quote = value.chars().next()?;
+            if quote == '\'' || quote == '"' {
+                let end = value[1..].find(quote)?;
+                return Some(value[1..end + 1].to_string());
+            }
+        }
+        rest = &rest[start + name.len()..];
+    }
+    None
+}
+
+fn fetch(url: &str) -> Result<Vec<u8>> {
+    let url = if url.starts_with("//") { format!("https:{url}") } else { url.to_string() };
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let response = reqwest::get(url.as_str()).await?.error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    })
+}
+
+impl Inliner {
+    pub fn new(directories: Vec<PathBuf>) -> Self {
+        Self { directories, inlined_scripts: HashSet::new() }
+    }
+
+    // the contents of a file the page refers to, and where it was found, so the paths in a
+    // stylesheet can be resolved from there
+    fn load(&self, path: &str, base: Option<&str>) -> Result<(Vec<u8>, String)> {
+        if path.starts_with("data:") {
+            return Err(anyhow!("already inlined"));
+        }
+        if is_remote(path) {
+            return Ok((fetch(path)?, path.to_string()));
+        }
+        if let Some(base) = base && is_remote(base) {
+            let url = join_url(base, path);
+            return Ok((fetch(&url)?, url));
+        }
+        let directories: Vec<PathBuf> = match base {
+            Some(base) => vec![Path::new(base).parent().map(Path::to_path_buf).unwrap_or_default()],
+            None => self.directories.clone(),
+        };
+        let local = path.split(['?', '#']).next().unwrap_or(path);
+        for directory in &directories {
+            let candidate = directory.join(local);
+            if let Ok(data) = std::fs::read(&candidate) {
+                return Ok((data, candidate.to_string_lossy().to_string()));
+            }
+        }
+        Err(anyhow!("the file could not be found"))
+    }
+
+    fn data_uri(&self, path: &str, base: Option<&str>) -> Result<String> {
+        let (data, _) = self.load(path, base)?;
+        Ok(format!("data:{};base64,{}", mime_type(path), base64(&data)))
+    }
+
+    // url(...) in a stylesheet, e.g. the fonts, are written into it as well
+    fn inline_css(&self, css: &str, base: &str) -> String {
+        let mut output = String::new();
+        let mut rest = css;
+        while let Some(start) = rest.find("url(") {
+            output += &rest[..start + 4];
+            rest = &rest[start + 4..];
+            let Some(end) = rest.find(')') else { break };
+            let path = rest[..end].trim().trim_matches(['\'', '"']);
+            match self.data_uri(path, Some(base)) {
+                Ok(uri) => output += format!("'{uri}'").as_str(),
+                Err(_) => output += &rest[..end],
+            }
+            rest = &rest[end..];
+        }
+        output += rest;
+        output
+    }
+
+    fn warn(path: &str, _error: &anyhow::Error) {
+        #[cfg(not(feature = "wasm"))]
+        cprintln!("<y>warning:</> could not inline '{}', it is left out: {}", path, _error);
+    }
+
+    // a <link>, <script> or <img> tag, as it is written in the self contained page
+    fn tag(&mut self, tag: &str) -> String {
+        let lower = tag.to_lowercase();
+        if lower.starts_with("<link") {
+            let rel = attribute(tag, "rel").unwrap_or_default();
+            if rel == "preconnect" { return String::new() }
+            if rel != "stylesheet" { return tag.to_string() }
+            let Some(href) = attribute(tag, "href") else { return tag.to_string() };
+            match self.load(&href, None) {
+                Ok((css, base)) => format!("<style>\n{}\n</style>", self.inline_css(&String::from_utf8_lossy(&css), &base)),
+                Err(error) => {
+                    Self::warn(&href, &error);
+                    String::new()
+                },
+            }
+        } else if lower.starts_with("<script") {
+            let Some(src) = attribute(tag, "src") else { return tag.to_string() };
+            if !self.inlined_scripts.insert(src.clone()) { return String::new() }
+            match self.load(&src, None) {
+                // a </script> in the code would end the tag early
+                Ok((js, _)) => format!("<script>\n{}\n</script>", String::from_utf8_lossy(&js).replace("</script", "<\\/script")),
+                Err(error) => {
+                    Self::warn(&src, &error);
+                    String::new()
+                },
+            }
+        } else if lower.starts_with("<img") {
+            let Some(src) = attribute(tag, "src") else { return tag.to_string() };
+            // only local images are embedded, the ones on other sites are linked to as before
+            if src.is_empty() || is_remote(&src) || src.starts_with("data:") { return tag.to_string() }
+            match self.data_uri(&src, None) {
+                Ok(uri) => tag.replacen(src.as_str(), uri.as_str(), 1),
+                Err(error) => {
+                    Self::warn(&src, &error);
+                    tag.to_string()
+                },
+            }
+        } else {
+            tag.to_string()
+        }
+    }
+
+    pub fn inline(&mut self, html: &str) -> String {
+        let mut output = String::with_capacity(html.len());
+        let mut rest = html;
+        while let Some(start) = rest.find('<') {
+            output += &rest[..start];
+            rest = &rest[start..];
+            let lower = rest.get(..7).unwrap_or(rest).to_lowercase();
+            if !(lower.starts_with("<link") || lower.starts_with("<script") || lower.starts_with("<img")) {
+                output.push('<');
+                rest = &rest[1..];
+                continue;
+            }
+            let Some(end) = rest.find('>') else { break };
+            let mut tag = &rest[..end + 1];
+            // a script that is loaded from a file is closed right after
+            if lower.starts_with("<script") && attribute(tag, "src").is_some() && rest[end + 1..].starts_with("</script>") {
+                tag = &rest[..end + 1 + "</script>".len()];
+            }
+            output += self.tag(tag).as_str();
+            rest = &rest[tag.len()..];
+        }
+        output += rest;
+        output
+    }
+}
+
+// a relative path on a site, as a browser would resolve it from the page at base
+fn join_url(base: &str, path: &str) -> String {
+    if is_remote(path) { return path.to_string() }
+    let (origin, base_path) = match base.find("://") {
+        Some(scheme) => match base[scheme + 3..].find('/') {
+            Some(slash) => base.split_at(scheme + 3 + slash),
+            None => (base, "/"),
+        },
+        None => ("", base),
+    };
+    if path.starts_with('/') { return format!("{origin}{path}") }
+
+    let mut segments: Vec<&str> = base_path.split('/').collect();
+    segments.pop();
+    for segment in path.split('/') {
+        match segment {
+            ".." => { if segments.len() > 1 { segments.pop(); } },
+            "." => {},
+            segment => segments.push(segment),
+        }
+    }
+    format!("{origin}{}", segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::self_contained::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(base64(b""), "");
+    }
+
+    #[test]
+    fn test_join_url() {
+        assert_eq!(join_url("https://sirpaws.dev/blog/post.html", "../css/base.css"), "https://sirpaws.dev/css/base.css");
+        assert_eq!(join_url("https://sirpaws.dev/blog/post.html", "img/a.png"), "https://sirpaws.dev/blog/img/a.png");
+        assert_eq!(join_url("https://fonts.googleapis.com/css2?family=A", "/s/a.woff2"), "https://fonts.googleapis.com/s/a.woff2");
+    }
+
+    #[test]
+    fn test_inline_local_files() {
+        let directory = TestDir::new("self-contained");
+        directory.write("style.css", "p { color: red; }");
+        directory.write("a.png", [1, 2, 3]);
+        let mut inliner = Inliner::new(vec![directory.path().to_path_buf()]);
+        let html = "<link rel=\"preconnect\" href=\"x\">\n<link rel=\"stylesheet\" href=\"style.css\">\n<p>a < b</p><img src='a.png' class='banner'>";
+        assert_eq!(inliner.inline(html), "\n<style>\np { color: red; }\n</style>\n<p>a < b</p><img src='data:image/png;base64,AQID' class='banner'>");
+    }
+
+    #[test]
+    fn test_missing_files_are_not_downloaded() {
+        let directory = TestDir::new("self-contained-nothing");
+        let mut inliner = Inliner::new(vec![directory.path().to_path_buf()]);
+        let html = "<link rel=\"stylesheet\" href=\"../css/base.css\"><script src='../js/popup.js'></script><p>text</p>";
+        assert_eq!(inliner.inline(html), "<p>text</p>");
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(not(feature = "wasm"))]
+use color_print::cprintln;
+
+use crate::*;
+
+// shortcodes are written as :name:, unknown names are left as they are
+pub const EMOJI_SHORTCODES: [(&'static str, &'static str); 64] = [
+    ("smile", "😄"), ("smiley", "😃"), ("grin", "😁"), ("laughing", "😆"), ("joy", "😂"),
+    ("wink", "😉"), ("blush", "😊"), ("slightly_smiling_face", "🙂"), ("upside_down_face", "🙃"),
+    ("heart_eyes", "😍"), ("sunglasses", "😎"), ("thinking", "🤔"), ("neutral_face", "😐"),
+    ("confused", "😕"), ("worried", "😟"), ("cry", "😢"), ("sob", "😭"), ("angry", "😠"),
+    ("scream", "😱"), ("sweat_smile", "😅"), ("sleeping", "😴"), ("innocent", "😇"),
+    ("+1", "👍"), ("thumbsup", "👍"), ("-1", "👎"), ("thumbsdown", "👎"), ("clap", "👏"),
+    ("wave", "👋"), ("pray", "🙏"), ("muscle", "💪"), ("point_right", "👉"), ("point_left", "👈"),
+    ("eyes", "👀"), ("heart", "❤️"), ("broken_heart", "💔"), ("star", "⭐"), ("sparkles", "✨"),
+    ("fire", "🔥"), ("tada", "🎉"), ("rocket", "🚀"), ("bulb", "💡"), ("warning", "⚠️"),
+    ("x", "❌"), ("white_check_mark", "✅"), ("heavy_check_mark", "✔️"), ("question", "❓"),
+    ("exclamation", "❗"), ("no_entry", "⛔"), ("lock", "🔒"), ("key", "🔑"), ("memo", "📝"),
+    ("book", "📖"), ("books", "📚"), ("pencil2", "✏️"), ("mag", "🔍"), ("link", "🔗"),
+    ("calendar", "📅"), ("email", "📧"), ("computer", "💻"), ("bug", "🐛"), ("coffee", "☕"),
+    ("mortar_board", "🎓"), ("chart_with_upwards_trend", "📈"), ("hourglass", "⌛"),
+];
+
+pub fn get_symbols(data: &Frontmatter) -> Vec<(String, String)> {
+    let mapping = if let Some(mapping) = data["symbols"].as_mapping() {
+        mapping
+    } else if let Some(mapping) = data["substitutions"].as_mapping() {
+        mapping
+    } else {
+        return vec![];
+    };
+
+    let mut symbols = Vec::<(String, String)>::new();
+    for (key, value) in mapping {
+        if let (Some(key), Some(value)) = (key.as_string(), value.as_string()) && !key.is_empty() {
+            symbols.push((key, value));
+        } else {
+            #[cfg(not(feature = "wasm"))]
+            cprintln!("<y>warning:</> symbols are expected to be text, as in '\"(c)\": \"©\"', ignoring '{:?}'", key);
+        }
+    }
+    // the longest symbol wins when several of them start at the same place
+    symbols.sort_by_key(|(key, _)| std::cmp::Reverse(key.len()));
+    symbols
+}
+
+fn find_shortcode(name: &str) -> Option<&'static str> {
+    EMOJI_SHORTCODES.iter().find(|(shortcode, _)| *shortcode == name).map(|(_, emoji)| *emoji)
+}
+
+// replaces the symbols and (if emoji is set) the shortcodes in one pass, so a replacement is
+// never replaced again
+pub fn substitute_text(text: &str, symbols: &Vec<(String, String)>, emoji: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut remaining = text;
+    'outer: while let Some(character) = remaining.chars().nth(0) {
+        for (symbol, replacement) in symbols {
+            if let Some(rest) = remaining.strip_prefix(symbol.as_str()) {
+                result += replacement;
+                remaining = rest;
+                continue 'outer;
+            }
+        }
+
+        if emoji && character == ':' {
+            let name_length = remaining[1..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '+' || c == '-'))
+                .unwrap_or(remaining.len() - 1);
+            let name = &remaining[1..(1 + name_length)];
+            if remaining[(1 + name_length)..].starts_with(':') && let Some(emoji) = find_shortcode(name) {
+                result += emoji;
+                remaining = &remaining[(2 + name_length)..];
+                continue;
+            }
+        }
+
+        result.push(character);
+        remaining = &remaining[character.len_utf8()..];
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::symbols::*;
+
+    #[test]
+    fn test_shortcodes() {
+        assert_eq!(substitute_text(":smile: at 10:30:00 :not_an_emoji: :+1:", &vec![], true), "😄 at 10:30:00 :not_an_emoji: 👍");
+        assert_eq!(substitute_text(":smile:", &vec![], false), ":smile:");
+    }
+
+    #[test]
+    fn test_symbols() {
+        let symbols = vec![
+            ("<->".to_string(), "↔".to_string()),
+            ("(c)".to_string(), "©".to_string()),
+            ("->".to_string(), "→".to_string()),
+        ];
+        assert_eq!(substitute_text("(c) a -> b <-> c", &symbols, true), "© a → b ↔ c");
+    }
+}
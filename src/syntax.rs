@@ -0,0 +1,542 @@
+use anyhow::{anyhow, Result};
+
+use crate::*;
+
+// code blocks are coloured here instead of by highlight.js in the browser, so pdfs and pages that
+// are read offline get colours too. it's a plain scan for comments, strings, numbers and names,
+// which is close enough for the listings in a report, languages it doesn't know are left as they are
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    Literal,
+    Type,
+    String,
+    Number,
+    Comment,
+    Function,
+    // attributes, decorators, preprocessor lines and shell variables
+    Meta,
+}
+
+struct Language {
+    names: &'static [&'static str],
+    keywords: &'static [&'static str],
+    literals: &'static [&'static str],
+    types: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    // ' is only a quote for single characters, as in 'a' and '\n', so rust lifetimes aren't strings
+    character_quotes: bool,
+    // capitalised names are types or classes
+    capitalised_types: bool,
+    // keywords in any case, as in sql
+    ignore_case: bool,
+    // # for attributes and preprocessor lines, @ for decorators and $ for shell variables
+    meta: Option<char>,
+}
+
+const LANGUAGES: [Language; 13] = [
+    Language {
+        names: &["rust", "rs"],
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "fn",
+            "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+            "self", "Self", "static", "struct", "super", "trait", "type", "unsafe", "use", "where", "while",
+        ],
+        literals: &["true", "false", "None", "Some", "Ok", "Err"],
+        types: &[
+            "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+            "f32", "f64", "bool", "char", "str",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        character_quotes: true,
+        capitalised_types: true,
+        ignore_case: false,
+        meta: Some('#'),
+    },
+    Language {
+        names: &["c", "h"],
+        keywords: &[
+            "auto", "break", "case", "const", "continue", "default", "do", "else", "enum", "extern", "for", "goto",
+            "if", "inline", "register", "return", "sizeof", "static", "struct", "switch", "typedef", "union", "while",
+        ],
+        literals: &["NULL", "true", "false"],
+        types: &[
+            "int", "char", "float", "double", "void", "long", "short", "signed", "unsigned", "bool",
+            "size_t", "int8_t", "int16_t", "int32_t", "int64_t", "uint8_t", "uint16_t", "uint32_t", "uint64_t", "FILE",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        character_quotes: true,
+        capitalised_types: false,
+        ignore_case: false,
+        meta: Some('#'),
+    },
+    Language {
+        names: &["cpp", "c++", "cc", "cxx", "hpp"],
+        keywords: &[
+            "auto", "break", "case", "catch", "class", "const", "constexpr", "continue", "default", "delete",
+            "do", "else", "enum", "explicit", "extern", "for", "friend", "goto", "if", "inline", "namespace",
+            "new", "noexcept", "operator", "override", "private", "protected", "public", "return", "sizeof",
+            "static", "struct", "switch", "template", "this", "throw", "try", "typedef", "typename", "union",
+            "using", "virtual", "while",
+        ],
+        literals: &["nullptr", "NULL", "true", "false"],
+        types: &[
+            "int", "char", "float", "double", "void", "long", "short", "signed", "unsigned", "bool",
+            "size_t", "std", "string", "vector", "map",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        character_quotes: true,
+        capitalised_types: true,
+        ignore_case: false,
+        meta: Some('#'),
+    },
+    Language {
+        names: &["java"],
+        keywords: &[
+            "abstract", "assert", "break", "case", "catch", "class", "continue", "default", "do", "else", "enum",
+            "extends", "final", "finally", "for", "if", "implements", "import", "instanceof", "interface",
+            "native", "new", "package", "private", "protected", "public", "record", "return", "static", "super",
+            "switch", "synchronized", "this", "throw", "throws", "try", "var", "volatile", "while",
+        ],
+        literals: &["true", "false", "null"],
+        types: &["int", "long", "short", "byte", "char", "boolean", "float", "double", "void"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        character_quotes: true,
+        capitalised_types: true,
+        ignore_case: false,
+        meta: Some('@'),
+    },
+    Language {
+        names: &["cs", "csharp", "c#"],
+        keywords: &[
+            "abstract", "as", "async", "await", "base", "break", "case", "catch", "class", "const", "continue",
+            "default", "delegate", "do", "else", "enum", "event", "finally", "for", "foreach", "get", "if", "in",
+            "interface", "internal", "is", "namespace", "new", "out", "override", "private", "protected",
+            "public", "readonly", "ref", "return", "sealed", "set", "static", "struct", "switch", "this",
+            "throw", "try", "typeof", "using", "var", "virtual", "while", "yield",
+        ],
+        literals: &["true", "false", "null"],
+        types: &["int", "long", "short", "byte", "char", "bool", "float", "double", "decimal", "string", "object", "void"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        character_quotes: true,
+        capitalised_types: true,
+        ignore_case: false,
+        meta: Some('#'),
+    },
+    Language {
+        names: &["javascript", "js", "jsx", "mjs"],
+        keywords: &[
+            "async", "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default",
+            "delete", "do", "else", "export", "extends", "finally", "for", "function", "if", "import", "in",
+            "instanceof", "let", "new", "of", "return", "static", "super", "switch", "this", "throw", "try",
+            "typeof", "var", "void", "while", "yield",
+        ],
+        literals: &["true", "false", "null", "undefined", "NaN", "Infinity"],
+        types: &[],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        character_quotes: false,
+        capitalised_types: true,
+        ignore_case: false,
+        meta: None,
+    },
+    Language {
+        names: &["typescript", "ts", "tsx"],
+        keywords: &[
+            "abstract", "as", "async", "await", "break", "case", "catch", "class", "const", "continue", "declare",
+            "default", "delete", "do", "else", "enum", "export", "extends", "finally", "for", "function", "if",
+            "implements", "import", "in", "instanceof", "interface", "keyof", "let", "namespace", "new", "of",
+            "private", "protected", "public", "readonly", "return", "static", "super", "switch", "this", "throw",
+            "try", "type", "typeof", "var", "while", "yield",
+        ],
+        literals: &["true", "false", "null", "undefined", "NaN", "Infinity"],
+        types: &["number", "string", "boolean", "any", "unknown", "never", "void", "object"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        character_quotes: false,
+        capitalised_types: true,
+        ignore_case: false,
+        meta: Some('@'),
+    },
+    Language {
+        names: &["python", "py", "python3"],
+        keywords: &[
+            "and", "as", "assert", "async", "await", "break", "case", "class", "continue", "def", "del", "elif",
+            "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "match",
+            "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+        ],
+        literals: &["True", "False", "None"],
+        types: &["int", "float", "str", "bool", "list", "dict", "set", "tuple", "bytes", "object"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        character_quotes: false,
+        capitalised_types: true,
+        ignore_case: false,
+        meta: Some('@'),
+    },
+    Language {
+        names: &["go", "golang"],
+        keywords: &[
+            "break", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough", "for",
+            "func", "go", "goto", "if", "import", "interface", "map", "package", "range", "return", "select",
+            "struct", "switch", "type", "var",
+        ],
+        literals: &["true", "false", "nil", "iota"],
+        types: &[
+            "bool", "byte", "error", "float32", "float64", "int", "int8", "int16", "int32", "int64", "rune",
+            "string", "uint", "uint8", "uint16", "uint32", "uint64", "uintptr", "any",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        character_quotes: true,
+        capitalised_types: false,
+        ignore_case: false,
+        meta: None,
+    },
+    Language {
+        names: &["bash", "sh", "shell", "zsh"],
+        keywords: &[
+            "if", "then", "else", "elif", "fi", "case", "esac", "for", "while", "until", "do", "done", "in",
+            "function", "return", "local", "export", "break", "continue",
+        ],
+        literals: &["true", "false"],
+        // the builtins
+        types: &["echo", "cd", "printf", "read", "exit", "source", "set", "unset", "eval", "exec", "test"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        character_quotes: false,
+        capitalised_types: false,
+        ignore_case: false,
+        meta: Some('$'),
+    },
+    Language {
+        names: &["json"],
+        keywords: &[],
+        literals: &["true", "false", "null"],
+        types: &[],
+        line_comments: &[],
+        block_comment: None,
+        quotes: &['"'],
+        character_quotes: false,
+        capitalised_types: false,
+        ignore_case: false,
+        meta: None,
+    },
+    Language {
+        names: &["sql", "mysql", "postgresql", "sqlite"],
+        keywords: &[
+            "select", "from", "where", "insert", "into", "values", "update", "set", "delete", "create", "table",
+            "drop", "alter", "add", "primary", "key", "foreign", "references", "join", "inner", "left", "right",
+            "outer", "on", "as", "and", "or", "not", "is", "in", "order", "by", "group", "having", "limit",
+            "offset", "distinct", "union", "all", "index", "view", "default", "unique", "check", "constraint",
+            "between", "like", "exists", "case", "when", "then", "else", "end", "begin", "commit", "rollback",
+        ],
+        literals: &["null", "true", "false"],
+        types: &["int", "integer", "varchar", "char", "text", "date", "timestamp", "boolean", "float", "real", "decimal", "numeric", "serial"],
+        line_comments: &["--"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['\'', '"'],
+        character_quotes: false,
+        capitalised_types: false,
+        ignore_case: true,
+        meta: None,
+    },
+    Language {
+        names: &["haskell", "hs"],
+        keywords: &[
+            "case", "class", "data", "deriving", "do", "else", "forall", "hiding", "if", "import", "in", "infix",
+            "infixl", "infixr", "instance", "let", "module", "newtype", "of", "qualified", "then", "type", "where",
+        ],
+        literals: &["True", "False", "Nothing", "Just"],
+        types: &[],
+        line_comments: &["--"],
+        block_comment: Some(("{-", "-}")),
+        quotes: &['"', '\''],
+        character_quotes: true,
+        capitalised_types: true,
+        ignore_case: false,
+        meta: None,
+    },
+];
+
+fn language(name: &str) -> Option<&'static Language> {
+    let name = name.trim().to_lowercase();
+    LANGUAGES.iter().find(|language| language.names.contains(&name.as_str()))
+}
+
+// the html leaves the languages that aren't highlighted here to highlight.js
+pub fn is_highlighted(name: &str) -> bool {
+    language(name).is_some()
+}
+
+fn is_name(character: char) -> bool {
+    character.is_alphanumeric() || character == '_'
+}
+
+fn starts_with(code: &[char], start: usize, text: &str) -> bool {
+    let mut n = start;
+    for character in text.chars() {
+        if code.get(n) != Some(&character) { return false }
+        n += 1;
+    }
+    true
+}
+
+// where the text ends, or the end of the code when it isn't there
+fn find(code: &[char], start: usize, text: &str) -> usize {
+    (start..code.len()).find(|n| starts_with(code, *n, text)).map(|n| n + text.chars().count()).unwrap_or(code.len())
+}
+
+fn line_end(code: &[char], start: usize) -> usize {
+    (start..code.len()).find(|n| code[*n] == '\n').unwrap_or(code.len())
+}
+
+// the end of a string that starts with a quote at start, strings that aren't closed end with the line
+fn string_end(code: &[char], start: usize, language: &Language) -> Option<usize> {
+    let quote = code[start];
+    let triple: String = [quote; 3].iter().collect();
+    if !language.character_quotes && starts_with(code, start, &triple) {
+        return Some(find(code, start + 3, &triple))
+    }
+    let mut n = start + 1;
+    while n < code.len() {
+        match code[n] {
+            '\\' => n += 2,
+            character if character == quote => return Some(n + 1),
+            '\n' if quote != '`' => break,
+            _ => n += 1,
+        }
+        if quote == '\'' && language.character_quotes && n > start + 3 && code[start + 1] != '\\' {
+            return None
+        }
+    }
+    if quote == '\'' && language.character_quotes { None } else { Some(n.min(code.len())) }
+}
+
+fn name_kind(name: &str, code: &[char], end: usize, language: &Language) -> TokenKind {
+    let matches = |names: &[&str]| if language.ignore_case {
+        names.iter().any(|known| known.eq_ignore_ascii_case(name))
+    } else {
+        names.contains(&name)
+    };
+    // println!(...) and vec![...] are called like functions
+    let next = code.get(end).copied();
+    let called = next == Some('(') || (next == Some('!') && matches!(code.get(end + 1), Some('(' | '[' | '{')));
+    if matches(language.keywords) {
+        TokenKind::Keyword
+    } else if matches(language.literals) {
+        TokenKind::Literal
+    } else if matches(language.types) {
+        TokenKind::Type
+    } else if called {
+        TokenKind::Function
+    } else if language.capitalised_types && name.starts_with(|character: char| character.is_uppercase()) {
+        TokenKind::Type
+    } else {
+        TokenKind::Plain
+    }
+}
+
+// the end of an attribute, decorator, preprocessor line or variable that starts at start
+fn meta_end(code: &[char], start: usize, marker: char) -> Option<usize> {
+    let next = *code.get(start + 1)?;
+    match marker {
+        '#' if next == '[' || (next == '!' && code.get(start + 2) == Some(&'[')) => Some(find(code, start, "]")),
+        // preprocessor lines start the line
+        '#' if code[..start].iter().rev().take_while(|character| **character != '\n').all(|character| character.is_whitespace()) => {
+            Some(line_end(code, start))
+        },
+        '$' if next == '{' => Some(find(code, start, "}")),
+        '@' | '$' if is_name(next) => Some((start + 1..code.len()).find(|n| !(is_name(code[*n]) || (marker == '@' && code[*n] == '.'))).unwrap_or(code.len())),
+        _ => None,
+    }
+}
+
+// the code split in pieces that are coloured the same, put together they are the code as it was
+pub fn highlight(language_name: &str, code: &str) -> Vec<(TokenKind, String)> {
+    let Some(language) = language(language_name) else {
+        return vec![(TokenKind::Plain, code.to_string())]
+    };
+    let code: Vec<char> = code.chars().collect();
+    let mut tokens: Vec<(TokenKind, String)> = vec![];
+    let mut push = |kind: TokenKind, text: &[char]| {
+        match tokens.last_mut() {
+            Some((last, previous)) if *last == kind => previous.extend(text),
+            _ => tokens.push((kind, text.iter().collect())),
+        }
+    };
+
+    let mut n = 0;
+    while n < code.len() {
+        let character = code[n];
+        let (kind, end) = if language.line_comments.iter().any(|comment| starts_with(&code, n, comment)) {
+            (TokenKind::Comment, line_end(&code, n))
+        } else if let Some((open, close)) = language.block_comment && starts_with(&code, n, open) {
+            (TokenKind::Comment, find(&code, n + open.chars().count(), close))
+        } else if language.quotes.contains(&character) && let Some(end) = string_end(&code, n, language) {
+            (TokenKind::String, end)
+        } else if character.is_ascii_digit() && !(n > 0 && is_name(code[n - 1])) {
+            let length = code[n..].iter().enumerate()
+                .take_while(|(m, character)| is_name(**character) || (**character == '.' && code.get(n + m + 1).is_some_and(char::is_ascii_digit)))
+                .count();
+            (TokenKind::Number, n + length)
+        } else if is_name(character) {
+            let end = (n..code.len()).find(|m| !is_name(code[*m])).unwrap_or(code.len());
+            let name: String = code[n..end].iter().collect();
+            (name_kind(&name, &code, end, language), end)
+        } else if language.meta == Some(character) && let Some(end) = meta_end(&code, n, character) {
+            (TokenKind::Meta, end)
+        } else {
+            (TokenKind::Plain, n + 1)
+        };
+        push(kind, &code[n..end]);
+        n = end;
+    }
+    tokens
+}
+
+// colours are written as 0xrrggbb
+pub struct CodeTheme {
+    pub name: &'static str,
+    pub background: u32,
+    pub foreground: u32,
+    keyword: u32,
+    literal: u32,
+    type_name: u32,
+    string: u32,
+    number: u32,
+    comment: u32,
+    function: u32,
+    meta: u32,
+}
+
+impl CodeTheme {
+    pub fn color(&self, kind: TokenKind) -> u32 {
+        match kind {
+            TokenKind::Plain    => self.foreground,
+            TokenKind::Keyword  => self.keyword,
+            TokenKind::Literal  => self.literal,
+            TokenKind::Type     => self.type_name,
+            TokenKind::String   => self.string,
+            TokenKind::Number   => self.number,
+            TokenKind::Comment  => self.comment,
+            TokenKind::Function => self.function,
+            TokenKind::Meta     => self.meta,
+        }
+    }
+}
+
+// the first theme is the default, 'none' is black on white for printing
+pub const CODE_THEMES: [CodeTheme; 4] = [
+    CodeTheme {
+        name: "github", background: 0xf6f8fa, foreground: 0x24292e, keyword: 0xd73a49, literal: 0x005cc5, type_name: 0x6f42c1,
+        string: 0x032f62, number: 0x005cc5, comment: 0x6a737d, function: 0x6f42c1, meta: 0x22863a,
+    },
+    CodeTheme {
+        name: "solarized", background: 0xfdf6e3, foreground: 0x586e75, keyword: 0x859900, literal: 0x2aa198, type_name: 0xb58900,
+        string: 0x2aa198, number: 0xd33682, comment: 0x93a1a1, function: 0x268bd2, meta: 0xcb4b16,
+    },
+    CodeTheme {
+        name: "monokai", background: 0x272822, foreground: 0xf8f8f2, keyword: 0xf92672, literal: 0xae81ff, type_name: 0x66d9ef,
+        string: 0xe6db74, number: 0xae81ff, comment: 0x75715e, function: 0xa6e22e, meta: 0xfd971f,
+    },
+    CodeTheme {
+        name: "none", background: 0xf0f0f0, foreground: 0x000000, keyword: 0x000000, literal: 0x000000, type_name: 0x000000,
+        string: 0x000000, number: 0x000000, comment: 0x000000, function: 0x000000, meta: 0x000000,
+    },
+];
+
+// code-theme in the frontmatter picks one of the themes
+pub fn code_theme(frontmatter: &Frontmatter) -> Result<&'static CodeTheme> {
+    let Some(name) = frontmatter["code-theme"].as_string() else { return Ok(&CODE_THEMES[0]) };
+    CODE_THEMES.iter().find(|theme| theme.name.eq_ignore_ascii_case(name.trim())).ok_or_else(|| {
+        let names: Vec<&str> = CODE_THEMES.iter().map(|theme| theme.name).collect();
+        anyhow!("there is no code theme '{}', it is one of {}", name, names.join(", "))
+    })
+}
+
+pub fn css_color(color: u32) -> String {
+    format!("#{color:06x}")
+}
+
+// the colour with each channel from 0 to 1, as the pdf wants it
+pub fn rgb(color: u32) -> (f32, f32, f32) {
+    let channel = |shift: u32| ((color >> shift) & 0xff) as f32 / 255.0;
+    (channel(16), channel(8), channel(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syntax::*;
+    use TokenKind::{Plain, Keyword, Literal, Type, Number, Comment, Function, Meta};
+
+    fn tokens(language: &str, code: &str) -> Vec<(TokenKind, String)> {
+        highlight(language, code)
+    }
+
+    fn expected(tokens: &[(TokenKind, &str)]) -> Vec<(TokenKind, String)> {
+        tokens.iter().map(|(kind, text)| (*kind, text.to_string())).collect()
+    }
+
+    #[test]
+    fn test_highlight_rust() {
+        assert_eq!(tokens("rust", "fn f<'a>() {\n    // hi\n    println!(\"{}\", 1.5);\n}"), expected(&[
+            (Keyword, "fn"), (Plain, " f<'a>() {\n    "), (Comment, "// hi"), (Plain, "\n    "), (Function, "println"),
+            (Plain, "!("), (TokenKind::String, "\"{}\""), (Plain, ", "), (Number, "1.5"), (Plain, ");\n}"),
+        ]));
+        assert_eq!(tokens("rust", "let c = '\\n';"), expected(&[
+            (Keyword, "let"), (Plain, " c = "), (TokenKind::String, "'\\n'"), (Plain, ";"),
+        ]));
+    }
+
+    #[test]
+    fn test_highlight_languages() {
+        assert_eq!(tokens("Python", "@cache\ndef f(): return None # done"), expected(&[
+            (Meta, "@cache"), (Plain, "\n"), (Keyword, "def"), (Plain, " "), (Function, "f"), (Plain, "(): "),
+            (Keyword, "return"), (Plain, " "), (Literal, "None"), (Plain, " "), (Comment, "# done"),
+        ]));
+        assert_eq!(tokens("sql", "SELECT name FROM t -- all"), expected(&[
+            (Keyword, "SELECT"), (Plain, " name "), (Keyword, "FROM"), (Plain, " t "), (Comment, "-- all"),
+        ]));
+        assert_eq!(tokens("c", "#include <stdio.h>\nint x;"), expected(&[
+            (Meta, "#include <stdio.h>"), (Plain, "\n"), (Type, "int"), (Plain, " x;"),
+        ]));
+        assert_eq!(tokens("plaintext", "fn x"), expected(&[(Plain, "fn x")]));
+    }
+
+    #[test]
+    fn test_is_highlighted() {
+        assert!(is_highlighted("Rust"));
+        assert!(is_highlighted("c++"));
+        assert!(!is_highlighted("ocaml"));
+        assert!(!is_highlighted(""));
+    }
+
+    #[test]
+    fn test_code_theme() {
+        let (frontmatter, _) = parse_frontmatter("---\ncode-theme: Monokai\n---\n");
+        assert_eq!(code_theme(&frontmatter.unwrap()).unwrap().name, "monokai");
+        let (frontmatter, _) = parse_frontmatter("---\ncode-theme: nope\n---\n");
+        assert!(code_theme(&frontmatter.unwrap()).is_err());
+        assert_eq!(css_color(0x00ff10), "#00ff10");
+        assert_eq!(rgb(0xff0000), (1.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,249 @@
+#[cfg(not(feature = "wasm"))]
+use color_print::cprintln;
+use serde_yaml::Value;
+use config::*;
+use page_geometry::parse_length;
+use crate::*;
+
+// a title page template is a text file with one line of the page per line:
+//
+//     # {title}            the title, "##" for a subtitle and "###" for a bold line
+//     {author}             a line for every author, also used with {student-number}
+//     {course-code}        any other field is read from the frontmatter
+//     :::center            aligns the lines below, also :::left and :::right
+//     :::space 2cm         vertical space
+//     :::image {banner}    an image, as wide as the page allows
+//     :::bottom            puts the rest of the lines at the bottom of the page
+//     :::if supervisor     leaves the lines out until :::end, unless the field is set
+//
+// lines where every field is empty are left out, and so are empty brackets
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TitleAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TitleSize {
+    Title,
+    Subtitle,
+    Heading,
+    Text,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TitleItem {
+    Text { text: String, size: TitleSize, align: TitleAlign },
+    Space(f32),
+    Image(String),
+    Bottom,
+}
+
+// the fields that aren't read from the frontmatter as they are
+fn field(md: &PawsMarkdown, frontmatter: &Frontmatter, name: &str) -> String {
+    let header = &md.header;
+    match name {
+        "title"      => header.title.clone(),
+        "subtitle"   => header.subtitle.clone(),
+        "banner"     => header.banner.clone(),
+        "date"       => frontmatter["date"].as_string().unwrap_or_default(),
+        "characters" => character_count(&md.body).to_string(),
+        name => frontmatter[name].as_list().join(", "),
+    }
+}
+
+// %title, %date and %author are the same on every page of the running headers and footers
+pub fn substitute_document_fields(text: &str, frontmatter: &Frontmatter) -> String {
+    let names: Vec<String> = authors(frontmatter).into_iter().map(|(name, _)| name).collect();
+    text.replace("%title", frontmatter["title"].as_string().unwrap_or_default().as_str())
+        .replace("%date", frontmatter["date"].as_string().unwrap_or_default().as_str())
+        .replace("%author", names.join(", ").as_str())
+}
+
+// authors are either names, with the student numbers in their own list, or "name: student number" pairs
+fn authors(frontmatter: &Frontmatter) -> Vec<(String, String)> {
+    let key = if frontmatter.has("authors") { "authors" } else { "author" };
+    let numbers = frontmatter["student-numbers"].as_list();
+    let mut authors = vec![];
+    if let Value::Sequence(values) = &frontmatter[key] {
+        for (n, value) in values.iter().enumerate() {
+            if let Value::Mapping(mapping) = value {
+                for (name, number) in mapping {
+                    authors.push((name.as_string().unwrap_or_default(), number.as_string().unwrap_or_default()));
+                }
+            } else {
+                authors.push((value.as_string().unwrap_or_default(), numbers.get(n).cloned().unwrap_or_default()));
+            }
+        }
+    } else if let Some(name) = frontmatter[key].as_string() {
+        authors.push((name, numbers.first().cloned().unwrap_or_default()));
+    }
+    authors
+}
+
+// the names of the fields in a line, as in "{course-code}"
+fn fields_of(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut rest = line;
+    while let Some(start) = rest.find('{') && let Some(end) = rest[start..].find('}') {
+        fields.push(rest[start + 1..start + end].trim().to_string());
+        rest = &rest[start + end + 1..];
+    }
+    fields
+}
+
+fn fill(line: &str, values: &dyn Fn(&str) -> String) -> Option<String> {
+    let fields = fields_of(line);
+    let mut text = line.to_string();
+    let mut empty = true;
+    for name in &fields {
+        let value = values(name);
+        if !value.trim().is_empty() { empty = false; }
+        text = text.replace(format!("{{{name}}}").as_str(), value.trim());
+    }
+    if !fields.is_empty() && empty { return None }
+    for brackets in ["()", "[]", "( )"] {
+        text = text.replace(brackets, "");
+    }
+    Some(text.trim().to_string())
+}
+
+pub fn parse_title_page(template: &str, md: &PawsMarkdown) -> Vec<TitleItem> {
+    let frontmatter = md.header.frontmatter.clone().unwrap_or(Frontmatter::new());
+    let authors = authors(&frontmatter);
+    let value = |name: &str| field(md, &frontmatter, name);
+
+    let mut items = vec![];
+    let mut align = TitleAlign::Center;
+    let mut skipping = false;
+    for line in template.lines() {
+        let line = line.trim();
+        if let Some(directive) = line.strip_prefix(":::") {
+            let (name, argument) = directive.split_once(' ').unwrap_or((directive, ""));
+            let argument = argument.trim();
+            match name.trim() {
+                "end" => skipping = false,
+                _ if skipping => {},
+                "if" => skipping = fields_of(&format!("{{{argument}}}")).iter().all(|name| match name.as_str() {
+                    "author" | "authors" | "student-number" => authors.is_empty(),
+                    name => value(name).trim().is_empty(),
+                }),
+                "left"   => align = TitleAlign::Left,
+                "center" => align = TitleAlign::Center,
+                "right"  => align = TitleAlign::Right,
+                "bottom" => items.push(TitleItem::Bottom),
+                "space" => match parse_length(&Value::String(argument.into())) {
+                    Some(space) => items.push(TitleItem::Space(space)),
+                    None => {
+                        #[cfg(not(feature = "wasm"))]
+                        cprintln!("<y>warning:</> '{}' in the title page is not a length", argument);
+                    },
+                },
+                "image" => if let Some(src) = fill(argument, &value) && !src.is_empty() {
+                    items.push(TitleItem::Image(src));
+                },
+                _name => {
+                    #[cfg(not(feature = "wasm"))]
+                    cprintln!("<y>warning:</> unknown title page directive ':::{}'", _name);
+                },
+            }
+            continue;
+        }
+        if skipping { continue }
+
+        let (size, text) = if let Some(text) = line.strip_prefix("### ") {
+            (TitleSize::Heading, text)
+        } else if let Some(text) = line.strip_prefix("## ") {
+            (TitleSize::Subtitle, text)
+        } else if let Some(text) = line.strip_prefix("# ") {
+            (TitleSize::Title, text)
+        } else {
+            (TitleSize::Text, line)
+        };
+
+        let fields = fields_of(text);
+        if fields.iter().any(|name| name == "author" || name == "student-number") {
+            for (name, number) in &authors {
+                let author_value = |field: &str| match field {
+                    "author" => name.clone(),
+                    "student-number" => number.clone(),
+                    field => value(field),
+                };
+                if let Some(text) = fill(text, &author_value) {
+                    items.push(TitleItem::Text { text, size, align });
+                }
+            }
+        } else if line.is_empty() {
+            items.push(TitleItem::Space(PDF_TEXT_SIZE));
+        } else if let Some(text) = fill(text, &value) {
+            items.push(TitleItem::Text { text, size, align });
+        }
+    }
+    items
+}
+
+// pdf-title-page is a template file next to the markdown file, or the name of one of the built in templates
+pub fn title_page(md: &PawsMarkdown) -> Vec<TitleItem> {
+    let frontmatter = md.header.frontmatter.clone().unwrap_or(Frontmatter::new());
+    let name = frontmatter["pdf-title-page"].as_string().unwrap_or(TITLE_PAGE_TEMPLATES[0].0.into());
+    let template = if let Some((_, template)) = TITLE_PAGE_TEMPLATES.iter().find(|(template, _)| template.eq_ignore_ascii_case(name.trim())) {
+        template.to_string()
+    } else {
+        let path = md.header.resolve_path(name.trim());
+        std::fs::read_to_string(&path).unwrap_or_else(|_error| {
+            #[cfg(not(feature = "wasm"))]
+            cprintln!("<y>warning:</> could not read the title page template '{}': {}", path.display(), _error);
+            TITLE_PAGE_TEMPLATES[0].1.into()
+        })
+    };
+    parse_title_page(&template, md)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::title_page::*;
+    use crate::test_dir::TestDir;
+
+    fn items(markdown: &str, template: &str) -> Vec<TitleItem> {
+        let md = parse(&markdown.to_string(), None).unwrap();
+        parse_title_page(template, &md)
+    }
+
+    fn text(text: &str, size: TitleSize, align: TitleAlign) -> TitleItem {
+        TitleItem::Text { text: text.into(), size, align }
+    }
+
+    #[test]
+    fn test_title_page_fields() {
+        let markdown = "---\ntitle: Report\nauthors: [{Ann: 123}, Bo]\ncourse-code: DM123\n---\nsome text\n";
+        let template = "# {title}\n:::if supervisor\nVejleder: {supervisor}\n:::end\n:::left\n{author} ({student-number})\n{course-code} {semester}\nAnslag: {characters}";
+        assert_eq!(items(markdown, template), vec![
+            text("Report", TitleSize::Title, TitleAlign::Center),
+            text("Ann (123)", TitleSize::Text, TitleAlign::Left),
+            text("Bo", TitleSize::Text, TitleAlign::Left),
+            text("DM123", TitleSize::Text, TitleAlign::Left),
+            text("Anslag: 9", TitleSize::Text, TitleAlign::Left),
+        ]);
+    }
+
+    #[test]
+    fn test_document_fields() {
+        let markdown = "---\ntitle: Report\ndate: 2026-06-01\nauthors: [{Ann: 123}, Bo]\n---\n";
+        let md = parse(&markdown.to_string(), None).unwrap();
+        let frontmatter = md.header.frontmatter.unwrap();
+        assert_eq!(substitute_document_fields("%title by %author, %date", &frontmatter), "Report by Ann, Bo, 2026-06-01");
+    }
+
+    #[test]
+    fn test_template_next_to_document() {
+        let directory = TestDir::new("title-page");
+        directory.write("front.txt", "# {title}");
+        let file = directory.write("report.md", "---\ntitle: Report\npdf-title-page: front.txt\n---\n");
+
+        let md = file_parse(&file.to_string_lossy().to_string()).unwrap();
+        let items = title_page(&md);
+
+        assert_eq!(items, vec![text("Report", TitleSize::Title, TitleAlign::Center)]);
+    }
+}
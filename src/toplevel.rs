@@ -1,5 +1,10 @@
 
-use config::DEFAULT_FACTBOX_TITLE;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+#[cfg(not(feature = "wasm"))]
+use color_print::cprintln;
 use contact::{parse_contact, ContactDefinition};
 
 use crate::*;
@@ -13,12 +18,21 @@ pub enum TopLevelSyntax {
     List(Vec<String>),
     Paragraph(String),
     Quote(Vec<String>),
+    DefinitionList(Vec<(/*term: */ String, /*definitions: */ Vec<String>)>),
+    GlossaryEntry{term: String, definition: String},
     ReferenceDefinition(ReferenceDefinition),
     ContactDefinition(ContactDefinition),
     NoteDefinition{id: String, text: String},
+    BlockNoteDefinition{id: String, body: Vec<TopLevelSyntax>},
+    LinkDefinition{label: String, url: String},
+    AbbreviationDefinition{abbreviation: String, expansion: String},
     TOC(String),
+    Index(String),
+    Appendix(String),
+    Include(String),
+    Chapter(String),
     PageBreak,
-    FactBox{title: String, body: Vec<TopLevelSyntax>},
+    FactBox{kind: FactBoxKind, title: String, body: Vec<TopLevelSyntax>},
 //  EmbeddedLink(String, String)
 }
 
@@ -108,6 +122,19 @@ fn is_meta(text: &str, check: &str) -> Option<usize> {
     }
 }
 
+// fact boxes and the other callouts start with their kind, as in [[fact] or [[warning]
+fn is_factbox(line: &str) -> Option<(FactBoxKind, usize)> {
+    for kind in FactBoxKind::ALL {
+        for marker in kind.markers() {
+            let marker = format!("[[{marker}]");
+            if line.starts_with(&marker) {
+                return Some((kind, marker.len()));
+            }
+        }
+    }
+    None
+}
+
 fn try_parse_note(content: &str) -> Option<(String, String)> {
     if !content.starts_with("[") { return None }
     if !content[1..].trim_start().starts_with("^") { return None }
@@ -134,8 +161,222 @@ fn try_parse_note(content: &str) -> Option<(String, String)> {
     Some((note_id, text.trim().to_string()))
 }
 
-pub fn toplevel_parse_file(file_path: &String) -> Result<Vec<TopLevelSyntax>> {
-    toplevel_parse(&fs::read_to_string(file_path)?)
+// gathers the indented lines following a note definition, blank lines are only included
+// when the block continues after them. returns the dedented text and how much was consumed
+fn gather_indented_block(content: &str) -> (String, usize) {
+    let mut result = String::new();
+    let mut consumed = 0;
+    let mut pending = String::new();
+    let mut pending_length = 0;
+    for line in content.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            pending.push('\n');
+            pending_length += line.len();
+            continue;
+        }
+
+        let dedented = if let Some(line) = line.strip_prefix("    ") {
+            line
+        } else if let Some(line) = line.strip_prefix('\t') {
+            line
+        } else {
+            break;
+        };
+
+        result += pending.as_str();
+        result += dedented;
+        consumed += pending_length + line.len();
+        pending.clear();
+        pending_length = 0;
+    }
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    (result, consumed)
+}
+
+fn try_parse_link_definition(content: &str) -> Option<(String, String)> {
+    if !content.starts_with("[") { return None }
+    let remaining = &content[1..];
+    if remaining.starts_with(|c| c == '^' || c == '£' || c == '?' || c == '[') { return None }
+
+    // this is most likely a link definition. as in [label]: https://...
+    let end = remaining.find("]:")?;
+    let label = remaining[..end].trim();
+    if label.is_empty() || label.contains(|c| c == '[' || c == ']') { return None }
+
+    // anything after the url is an optional title in quotes or parentheses, which isn't used
+    let definition = remaining[(end + 2)..].trim();
+    let (url, title) = definition.split_once(char::is_whitespace).unwrap_or((definition, ""));
+    let url = url.strip_prefix('<').and_then(|url| url.strip_suffix('>')).unwrap_or(url);
+    let title = title.trim();
+    if !title.is_empty() && !title.starts_with(|c| c == '"' || c == '\'' || c == '(') { return None }
+
+    // a line like [Note]: remember to ... is a paragraph, only urls and paths are link definitions
+    if !looks_like_link_target(url) { return None }
+
+    Some((label.to_string(), url.to_string()))
+}
+
+fn looks_like_link_target(url: &str) -> bool {
+    if url.contains("://") || url.starts_with("mailto:") || url.contains(|c| c == '/' || c == '#') {
+        return true;
+    }
+    // a file or a domain, as in notes.pdf or example.com
+    url.rsplit_once('.').is_some_and(|(name, extension)|
+        !name.is_empty() && !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+// abbreviations are defined as *[SDU]: Syddansk Universitet
+fn try_parse_abbreviation(content: &str) -> Option<(String, String)> {
+    let remaining = content.strip_prefix("*[")?;
+    let end = remaining.find("]:")?;
+    let abbreviation = remaining[..end].trim();
+    let expansion = remaining[(end + 2)..].trim();
+    if abbreviation.is_empty() || expansion.is_empty() { return None }
+    Some((abbreviation.to_string(), expansion.to_string()))
+}
+
+fn collect_link_definitions(syntax: &[TopLevelSyntax], links: &mut HashMap<String, String>) {
+    for element in syntax {
+        match element {
+            TopLevelSyntax::LinkDefinition{ label, url } => {
+                links.insert(label.to_lowercase(), url.clone());
+            },
+            TopLevelSyntax::FactBox{ body, .. } => collect_link_definitions(body, links),
+            TopLevelSyntax::BlockNoteDefinition{ id: _, body } => collect_link_definitions(body, links),
+            _ => {}
+        }
+    }
+}
+
+// rewrites [text][label] and [label][] into [text](url) using the [label]: url definitions
+fn expand_link_references_in_text(text: &String, links: &HashMap<String, String>, source: &Option<String>) -> String {
+    let mut result = String::new();
+    let mut remaining = text.as_str();
+    while let Some(start) = remaining.find('[') {
+        result += &remaining[..start];
+        remaining = &remaining[start..];
+
+        let Some(text_end) = find_end_balanced(&remaining[1..], ('[', ']')) else { break };
+        let after_text = &remaining[(text_end + 2)..];
+        if !after_text.starts_with('[') || remaining[1..].starts_with(|c| c == '^' || c == '£' || c == '?' || c == '[') {
+            result += &remaining[..1];
+            remaining = &remaining[1..];
+            continue;
+        }
+
+        let Some(label_end) = after_text[1..].find(']') else { break };
+        let link_text = &remaining[1..(text_end + 1)];
+        let label = &after_text[1..(label_end + 1)];
+        let label = if label.trim().is_empty() { link_text } else { label };
+
+        if let Some(url) = links.get(&label.trim().to_lowercase()) {
+            result += format!("[{link_text}]({url})").as_str();
+        } else {
+            #[cfg(not(feature = "wasm"))]
+            cprintln!("<y>warning:</> {}link reference '{}' has no definition", source_prefix(source), label);
+            result += &remaining[..(text_end + 2 + label_end + 2)];
+        }
+        remaining = &after_text[(label_end + 2)..];
+    }
+    result += remaining;
+    result
+}
+
+pub fn expand_link_references(syntax: Vec<(TopLevelSyntax, /*source: */ Option<String>)>) -> Vec<(TopLevelSyntax, Option<String>)> {
+    let mut links = HashMap::<String, String>::new();
+    for (element, _) in &syntax {
+        collect_link_definitions(std::slice::from_ref(element), &mut links);
+    }
+    syntax.into_iter()
+        .map(|(element, source)| (expand_link_references_with(element, &links, &source), source))
+        .collect()
+}
+
+fn expand_link_references_with(element: TopLevelSyntax, links: &HashMap<String, String>, source: &Option<String>) -> TopLevelSyntax {
+    let expand = |text: &String| expand_link_references_in_text(text, links, source);
+    let expand_all = |body: Vec<TopLevelSyntax>| body.into_iter().map(|element| expand_link_references_with(element, links, source)).collect();
+    match element {
+        TopLevelSyntax::Paragraph(text) => TopLevelSyntax::Paragraph(expand(&text)),
+        TopLevelSyntax::Header(text, level) => TopLevelSyntax::Header(expand(&text), level),
+        TopLevelSyntax::List(list) => TopLevelSyntax::List(list.iter().map(expand).collect()),
+        TopLevelSyntax::Quote(list) => TopLevelSyntax::Quote(list.iter().map(expand).collect()),
+        TopLevelSyntax::DefinitionList(list) => TopLevelSyntax::DefinitionList(list.iter().map(|(term, definitions)| (
+            expand(term),
+            definitions.iter().map(expand).collect(),
+        )).collect()),
+        TopLevelSyntax::GlossaryEntry{ term, definition } => TopLevelSyntax::GlossaryEntry{ term, definition: expand(&definition) },
+        TopLevelSyntax::NoteDefinition{ id, text } => TopLevelSyntax::NoteDefinition{ id, text: expand(&text) },
+        TopLevelSyntax::FactBox{ kind, title, body } => TopLevelSyntax::FactBox{ kind, title, body: expand_all(body) },
+        TopLevelSyntax::BlockNoteDefinition{ id, body } => TopLevelSyntax::BlockNoteDefinition{ id, body: expand_all(body) },
+        element => element,
+    }
+}
+
+pub fn toplevel_parse_file(file_path: &String) -> Result<Vec<(TopLevelSyntax, Option<String>)>> {
+    resolve_includes(toplevel_parse(&fs::read_to_string(file_path)?)?, Some(file_path))
+}
+
+// "'ch/a.pmd': " in front of the warnings about an included file, nothing for the document itself
+pub fn source_prefix(source: &Option<String>) -> String {
+    source.as_ref().map(|source| format!("'{source}': ")).unwrap_or_default()
+}
+
+// replaces every #[include] with the syntax of the included file, 
+// paths are resolved relative to the file doing the including.
+// only the frontmatter of the root document is kept.
+// every element comes with the included file it is from, or none for the document itself
+pub fn resolve_includes(syntax: Vec<TopLevelSyntax>, file_path: Option<&String>) -> Result<Vec<(TopLevelSyntax, /*source: */ Option<String>)>> {
+    let mut stack = Vec::<PathBuf>::new();
+    if let Some(file_path) = file_path && let Ok(path) = fs::canonicalize(file_path) {
+        stack.push(path);
+    }
+    resolve_includes_recursive(syntax, file_path.map(|path| Path::new(path)), &None, &mut stack, true)
+}
+
+fn resolve_includes_recursive(syntax: Vec<TopLevelSyntax>, file_path: Option<&Path>, source: &Option<String>, 
+    stack: &mut Vec<PathBuf>, is_root: bool) -> Result<Vec<(TopLevelSyntax, Option<String>)>> 
+{
+    let base_dir = file_path.and_then(|path| path.parent()).unwrap_or(Path::new("."));
+    let mut result = Vec::<(TopLevelSyntax, Option<String>)>::new();
+
+    for element in syntax {
+        match element {
+            TopLevelSyntax::Include(include) => {
+                let include_path = base_dir.join(&include);
+                let Ok(canonical) = fs::canonicalize(&include_path) else {
+                    return Err(anyhow!("'{}' includes '{}' which could not be found", 
+                        file_path.unwrap_or(Path::new("<input>")).display(), include_path.display()));
+                };
+
+                if stack.contains(&canonical) {
+                    return Err(anyhow!("'{}' includes '{}' which is already being included, this would be an include cycle", 
+                        file_path.unwrap_or(Path::new("<input>")).display(), include_path.display()));
+                }
+
+                let content = fs::read_to_string(&include_path)
+                    .with_context(|| format!("could not read included file '{}'", include_path.display()))?;
+                let included_source = Some(include_path.display().to_string());
+                let included = toplevel_parse_from(&content, &included_source)
+                    .with_context(|| format!("could not parse included file '{}'", include_path.display()))?;
+
+                stack.push(canonical);
+                result.extend(resolve_includes_recursive(included, Some(&include_path), &included_source, stack, false)?);
+                stack.pop();
+            },
+            TopLevelSyntax::FrontMatter(_) if !is_root => { continue },
+            TopLevelSyntax::FactBox{ kind, title, body } => {
+                // the fact box keeps the source of the file it is written in
+                let body = resolve_includes_recursive(body, file_path, source, stack, is_root)?
+                    .into_iter().map(|(element, _)| element).collect();
+                result.push((TopLevelSyntax::FactBox{ kind, title, body }, source.clone()));
+            },
+            element => result.push((element, source.clone())),
+        }
+    }
+
+    Ok(result)
 }
 
 fn remove_comments(text: &String) -> String {
@@ -199,6 +440,17 @@ impl ParseObject {
         self.consume();
         self.syntax.push(syntax);
     }
+
+    // the last line eaten is the term of a definition list, as in "term\n: definition"
+    fn take_last_line(&mut self) -> Option<String> {
+        if !self.is_eating { return None }
+        let text = self.text.trim_end();
+        let start = text.rfind('\n').map(|n| n + 1).unwrap_or(0);
+        let line = text[start..].trim().to_string();
+        if line.is_empty() { return None }
+        self.text.truncate(start);
+        Some(line)
+    }
 }
 
 pub fn find_end_balanced(text: &str, delimiters: (char, char)) -> Option<usize> {
@@ -228,6 +480,11 @@ pub fn find_end_balanced(text: &str, delimiters: (char, char)) -> Option<usize>
 }
 
 pub fn toplevel_parse(file_content: &String) -> Result<Vec<TopLevelSyntax>> {
+    toplevel_parse_from(file_content, &None)
+}
+
+// the source is the included file being parsed, so the warnings can name it
+fn toplevel_parse_from(file_content: &String, source: &Option<String>) -> Result<Vec<TopLevelSyntax>> {
     // let matter = Matter::<YAML>::new();
     let (frontmatter, content) = parse_frontmatter(&file_content);
 
@@ -350,6 +607,46 @@ pub fn toplevel_parse(file_content: &String) -> Result<Vec<TopLevelSyntax>> {
             continue;
         }
 
+        if let Some(n) = is_meta(current, "index") {
+            let text: String = current[n..].trim().into();
+            object.push(TopLevelSyntax::Index(text));
+            content = next_line(&content[current.len()..]).into();
+            continue;
+        }
+
+        if let Some(n) = is_meta(current, "appendix") {
+            let text: String = current[n..].trim_start().into();
+            object.push(TopLevelSyntax::Appendix(text));
+            content = next_line(&content[current.len()..]).into();
+            continue;
+        }
+        
+        if let Some(n) = is_meta(current, "appendices") {
+            let text: String = current[n..].trim_start().into();
+            object.push(TopLevelSyntax::Appendix(text));
+            content = next_line(&content[current.len()..]).into();
+            continue;
+        }
+        
+        if let Some(n) = is_meta(current, "glossary") {
+            let text = current[n..].trim();
+            if let Some((term, definition)) = text.split_once(':') && !term.trim().is_empty() {
+                object.push(TopLevelSyntax::GlossaryEntry{ term: term.trim().into(), definition: definition.trim().into() });
+            } else {
+                #[cfg(not(feature = "wasm"))]
+                cprintln!("<y>warning:</> {}glossary entry '{}' is missing a definition, expected '#[glossary] term: definition'", source_prefix(source), text);
+            }
+            content = next_line(&content[current.len()..]).into();
+            continue;
+        }
+
+        if let Some(n) = is_meta(current, "include") {
+            let text: String = current[n..].trim().into();
+            object.push(TopLevelSyntax::Include(text));
+            content = next_line(&content[current.len()..]).into();
+            continue;
+        }
+
         if current.starts_with('#') {
             let mut counter = 0;
             while current[counter..].starts_with('#') { 
@@ -365,13 +662,31 @@ pub fn toplevel_parse(file_content: &String) -> Result<Vec<TopLevelSyntax>> {
 
 
         if let Some((note_id, note_text)) = try_parse_note(current) {
-            object.push(TopLevelSyntax::NoteDefinition { id: note_id, text: note_text });
+            content = next_line(&content[current.len()..]).into();
+            let (continuation, consumed) = gather_indented_block(&content);
+            if continuation.is_empty() {
+                object.push(TopLevelSyntax::NoteDefinition { id: note_id, text: note_text });
+            } else {
+                let body = toplevel_parse_from(&format!("{note_text}\n{continuation}"), source)?;
+                object.push(TopLevelSyntax::BlockNoteDefinition { id: note_id, body });
+                content = content[consumed..].into();
+            }
+            continue;
+        }
+
+        if let Some((label, url)) = try_parse_link_definition(current) {
+            object.push(TopLevelSyntax::LinkDefinition { label, url });
+            content = next_line(&content[current.len()..]).into();
+            continue;
+        }
+
+        if let Some((abbreviation, expansion)) = try_parse_abbreviation(current) {
+            object.push(TopLevelSyntax::AbbreviationDefinition { abbreviation, expansion });
             content = next_line(&content[current.len()..]).into();
             continue;
         }
 
-        if current.starts_with("[[fact]") || current.starts_with("[[factbox]") {
-            let len = if current.starts_with("[[fact]") { "[[fact]".len() } else { "[[factbox]".len() };
+        if let Some((kind, len)) = is_factbox(current) {
             let last = find_end_balanced(&content[len..], ('[', ']')).context("expected a fact box but couldn't find the end")?;
 
             let mut text_to_parse = String::new();
@@ -383,15 +698,15 @@ pub fn toplevel_parse(file_content: &String) -> Result<Vec<TopLevelSyntax>> {
                 text_to_parse.push('\n');
             }
 
-            let body = toplevel_parse(&text_to_parse)?;
+            let body = toplevel_parse_from(&text_to_parse, source)?;
 
             let title = if let Some(text) = title && !text.trim().is_empty() {
                 text
             } else {  
-                DEFAULT_FACTBOX_TITLE
+                kind.default_title()
             }.to_string();
             
-            object.push(TopLevelSyntax::FactBox{title, body});
+            object.push(TopLevelSyntax::FactBox{kind, title, body});
             content = next_line(&content[last + len + 1..]).into();
             continue;
         }
@@ -462,6 +777,28 @@ pub fn toplevel_parse(file_content: &String) -> Result<Vec<TopLevelSyntax>> {
         }
         
 
+        if current.starts_with(": ") && let Some(term) = object.take_last_line() {
+            let mut list = vec![(term, Vec::<String>::new())];
+            let mut line = current;
+            loop {
+                if let Some(definition) = line.strip_prefix(": ") {
+                    list.last_mut().unwrap().1.push(definition.trim().into());
+                } else {
+                    // a new term has to be followed by a definition
+                    let next = next_line(&content[line.len()..]).lines().nth(0);
+                    if line.trim().is_empty() || !next.is_some_and(|next| next.starts_with(": ")) { break }
+                    list.push((line.trim().into(), vec![]));
+                }
+
+                content = next_line(&content[line.len()..]).into();
+                let Some(new_line) = content.lines().nth(0) else { break };
+                line = new_line;
+            }
+
+            object.push(TopLevelSyntax::DefinitionList(list));
+            continue;
+        }
+
         object.eat(&content[0..current.len()]);
         content = next_line(&content[current.len()..]).into();
     }
@@ -485,6 +822,8 @@ pub fn toplevel_parse(file_content: &String) -> Result<Vec<TopLevelSyntax>> {
 #[cfg(test)]
 mod tests {
     use crate::toplevel::*;
+    use crate::test_dir::TestDir;
+    use config::DEFAULT_WARNING_TITLE;
 
     #[test]
     fn test_next_line() {
@@ -701,6 +1040,63 @@ mod tests {
         assert_eq!(syntax, vec![TopLevelSyntax::TOC("Refs".into())])
     }
     
+    #[test]
+    fn test_index() {
+        let text = "#[index] Register\n#[index]".to_string();
+
+        let result = toplevel_parse(&text);
+        assert!(result.is_ok());
+        let syntax = result.unwrap();
+        assert_eq!(syntax, vec![TopLevelSyntax::Index("Register".into()), TopLevelSyntax::Index("".into())])
+    }
+    
+    #[test]
+    fn test_include() {
+        let text = "#[include] chapters/02-method.pmd".to_string();
+
+        let result = toplevel_parse(&text);
+        assert!(result.is_ok());
+        let syntax = result.unwrap();
+        assert_eq!(syntax, vec![TopLevelSyntax::Include("chapters/02-method.pmd".into())])
+    }
+    
+    #[test]
+    fn test_include_cycle() {
+        let dir = TestDir::new("include-cycle");
+        let root = dir.write("root.pmd", "# root\n#[include] a.pmd\n");
+        dir.write("a.pmd", "# a\n#[include] root.pmd\n");
+
+        let result = toplevel_parse_file(&root.to_string_lossy().to_string());
+
+        let error = format!("{:#}", result.unwrap_err());
+        assert!(error.contains(&format!("'{}' includes '{}'", dir.path().join("a.pmd").display(), dir.path().join("root.pmd").display())));
+        assert!(error.contains("include cycle"));
+    }
+
+    #[test]
+    fn test_missing_include() {
+        let dir = TestDir::new("missing-include");
+        let root = dir.write("root.pmd", "# root\n#[include] chapters/missing.pmd\n");
+
+        let result = toplevel_parse_file(&root.to_string_lossy().to_string());
+
+        let error = format!("{:#}", result.unwrap_err());
+        assert!(error.contains(&format!("'{}' includes '{}'", root.display(), dir.path().join("chapters/missing.pmd").display())));
+    }
+    
+    #[test]
+    fn test_appendix() {
+        let text = "#[appendix] Bilag\n# first appendix".to_string();
+
+        let result = toplevel_parse(&text);
+        assert!(result.is_ok());
+        let syntax = result.unwrap();
+        assert_eq!(syntax, vec![
+            TopLevelSyntax::Appendix("Bilag".into()),
+            TopLevelSyntax::Header("first appendix".into(), 1),
+        ])
+    }
+    
     // #[test]
     // fn test_banner() {
     //     let text = "#[banner] this/banner/path.png".to_string();
@@ -827,6 +1223,103 @@ mod tests {
         assert_eq!(syntax, vec![TopLevelSyntax::NoteDefinition { id: "0".into(), text: "this is a random note".into() }]);
     }
 
+    #[test]
+    fn test_definition_list() {
+        let text = "intro\nApple\n: a fruit\n: a company\nPear\n: another fruit\n\nafter".to_string();
+
+        let result = toplevel_parse(&text);
+        assert!(result.is_ok());
+        let syntax = result.unwrap();
+        assert_eq!(syntax, vec![
+            TopLevelSyntax::Paragraph("intro\n".into()),
+            TopLevelSyntax::DefinitionList(vec![
+                ("Apple".into(), vec!["a fruit".into(), "a company".into()]),
+                ("Pear".into(),  vec!["another fruit".into()]),
+            ]),
+            TopLevelSyntax::Paragraph("after\n".into()),
+        ]);
+    }
+
+    #[test]
+    fn test_glossary_entry() {
+        let text = "#[glossary] API: Application Programming Interface".to_string();
+
+        let result = toplevel_parse(&text);
+        assert!(result.is_ok());
+        let syntax = result.unwrap();
+        assert_eq!(syntax, vec![TopLevelSyntax::GlossaryEntry{ term: "API".into(), definition: "Application Programming Interface".into() }]);
+    }
+
+    #[test]
+    fn test_abbreviation_definition() {
+        let text = "*[SDU]: Syddansk Universitet\n*[]: nothing".to_string();
+
+        let result = toplevel_parse(&text);
+        assert!(result.is_ok());
+        let syntax = result.unwrap();
+        assert_eq!(syntax[0], TopLevelSyntax::AbbreviationDefinition{ abbreviation: "SDU".into(), expansion: "Syddansk Universitet".into() });
+        assert!(!syntax.iter().skip(1).any(|x| matches!(x, TopLevelSyntax::AbbreviationDefinition{ .. })));
+    }
+
+    #[test]
+    fn test_callout() {
+        let text = "[[warning]\n    careful\n]\n[[theorem] Pythagoras\n    a^2 + b^2 = c^2\n]".to_string();
+
+        let result = toplevel_parse(&text);
+        assert!(result.is_ok());
+        let syntax = result.unwrap();
+        assert_eq!(syntax, vec![
+            TopLevelSyntax::FactBox { kind: FactBoxKind::Warning, title: DEFAULT_WARNING_TITLE.into(), body: vec![
+                TopLevelSyntax::Paragraph("careful\n".into()),
+            ]},
+            TopLevelSyntax::FactBox { kind: FactBoxKind::Theorem, title: " Pythagoras".into(), body: vec![
+                TopLevelSyntax::Paragraph("a^2 + b^2 = c^2\n".into()),
+            ]},
+        ]);
+    }
+
+    #[test]
+    fn test_block_note() {
+        let text = "[^1]: first\n    line\n\n    - item\n\nafter".to_string();
+
+        let result = toplevel_parse(&text);
+        assert!(result.is_ok());
+        let syntax = result.unwrap();
+        assert_eq!(syntax, vec![
+            TopLevelSyntax::BlockNoteDefinition { id: "1".into(), body: vec![
+                TopLevelSyntax::Paragraph("first\nline\n".into()),
+                TopLevelSyntax::List(vec!["item".into()]),
+            ]},
+            TopLevelSyntax::Paragraph("after\n".into()),
+        ]);
+    }
+
+    #[test]
+    fn test_link_definition() {
+        let text = "[Docs]: <https://docs.rs> \"the docs\"\n[docs][] and [the docs][DOCS]\n".to_string();
+
+        let result = toplevel_parse(&text);
+        assert!(result.is_ok());
+        let syntax = result.unwrap();
+        assert_eq!(syntax[0], TopLevelSyntax::LinkDefinition { label: "Docs".into(), url: "https://docs.rs".into() });
+
+        let syntax = expand_link_references(syntax.into_iter().map(|element| (element, None)).collect());
+        assert_eq!(syntax[1].0, TopLevelSyntax::Paragraph("[docs](https://docs.rs) and [the docs](https://docs.rs)\n".into()));
+    }
+
+    #[test]
+    fn test_not_a_link_definition() {
+        assert_eq!(try_parse_link_definition("[Note]: remember to bring the slides"), None);
+        assert_eq!(try_parse_link_definition("[Note]: Done."), None);
+        assert_eq!(try_parse_link_definition("[notes]: notes.pdf (the notes)"), Some(("notes".into(), "notes.pdf".into())));
+        assert_eq!(try_parse_link_definition("[top]: #intro"), Some(("top".into(), "#intro".into())));
+
+        // references without a definition are left alone, also when there are no definitions at all
+        let text = "[Note]: remember the [slides][deck]\n".to_string();
+        let syntax = expand_link_references(toplevel_parse(&text).unwrap().into_iter().map(|element| (element, None)).collect());
+        assert_eq!(syntax[0].0, TopLevelSyntax::Paragraph(text));
+    }
+
     #[test]
     fn test_single_line_comment() {
         let text = "          %% this is a comment %%        ".to_string();
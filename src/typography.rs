@@ -0,0 +1,219 @@
+#[cfg(not(feature = "wasm"))]
+use color_print::cprintln;
+
+use config::*;
+use crate::*;
+
+// the typography pass turns straight quotes, dashes and dots into their typeset versions and
+// keeps numbers with their units (and abbreviations with what follows) on the same line.
+// it runs on the parsed document, so code, urls and ids are never touched.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Typography {
+    Danish,
+    English,
+}
+
+impl Typography {
+    fn double_quotes(&self) -> (char, char) {
+        match self {
+            Typography::Danish  => ('»', '«'),
+            Typography::English => ('“', '”'),
+        }
+    }
+
+    fn single_quotes(&self) -> (char, char) {
+        match self {
+            Typography::Danish  => ('›', '‹'),
+            Typography::English => ('‘', '’'),
+        }
+    }
+}
+
+pub fn get_typography(data: &Frontmatter) -> Option<Typography> {
+    let value = if let Some(value) = data["typography"].as_string() {
+        value
+    } else if let Some(value) = data["smart-typography"].as_string() {
+        value
+    } else if let Some(value) = data["smart_typography"].as_string() {
+        value
+    } else if let Some(value) = data["smart typography"].as_string() {
+        value
+    } else {
+        return None;
+    };
+
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "da" | "danish" | "dansk" => Some(Typography::Danish),
+        "en" | "english" | "engelsk" => Some(Typography::English),
+        "false" | "no" => None,
+        value => {
+            #[cfg(not(feature = "wasm"))]
+            cprintln!("<y>warning:</> '{}' is not a valid value for typography, expected true, false, danish or english", value);
+            None
+        }
+    }
+}
+
+// the state carried from one piece of text to the next, a quote right after *emphasis* still
+// needs to know what came before it
+pub struct TypesetState {
+    typography: Typography,
+    previous: Option<char>,
+    single_quote_open: bool,
+}
+
+impl TypesetState {
+    pub fn new(typography: Typography) -> Self {
+        Self { typography, previous: None, single_quote_open: false }
+    }
+
+    // called at the start of every block, quotes don't carry over between paragraphs
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.single_quote_open = false;
+    }
+}
+
+fn opens_quote(previous: Option<char>) -> bool {
+    previous.is_none_or(|c| c.is_whitespace() || "([{-–—/»›“‘".contains(c))
+}
+
+fn is_word_at(characters: &Vec<char>, start: usize, word: &str) -> bool {
+    let mut n = start;
+    for c in word.chars() {
+        if characters.get(n) != Some(&c) { return false }
+        n += 1;
+    }
+    true
+}
+
+// whether one of the words ends right before position, it has to be a whole word
+fn ends_with_word(characters: &Vec<char>, position: usize, words: &[&str]) -> bool {
+    for word in words {
+        let length = word.chars().count();
+        if length > position { continue }
+        let start = position - length;
+        if !is_word_at(characters, start, word) { continue }
+        if start > 0 && characters[start - 1].is_alphanumeric() { continue }
+        return true;
+    }
+    false
+}
+
+fn starts_with_unit(characters: &Vec<char>, position: usize) -> bool {
+    for unit in TYPOGRAPHY_UNITS {
+        if !is_word_at(characters, position, unit) { continue }
+        let after = characters.get(position + unit.chars().count());
+        if after.is_some_and(|c| c.is_alphanumeric()) { continue }
+        return true;
+    }
+    false
+}
+
+pub fn typeset_text(text: &str, state: &mut TypesetState) -> String {
+    let characters: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut n = 0;
+    while n < characters.len() {
+        let character = characters[n];
+        let next = characters.get(n + 1).copied();
+        let typeset = match character {
+            '-' if is_word_at(&characters, n, "---") => { n += 2; '—' },
+            // arrows like --> are left alone
+            '-' if next == Some('-') && characters.get(n + 2) != Some(&'>') => { n += 1; '–' },
+            '.' if is_word_at(&characters, n, "...") => { n += 2; '…' },
+            '"' => {
+                let (open, close) = state.typography.double_quotes();
+                if opens_quote(state.previous) { open } else { close }
+            },
+            '\'' => {
+                let (open, close) = state.typography.single_quotes();
+                if opens_quote(state.previous) {
+                    state.single_quote_open = true;
+                    open
+                } else if state.single_quote_open && !next.is_some_and(|c| c.is_alphanumeric()) {
+                    state.single_quote_open = false;
+                    close
+                } else {
+                    // an apostrophe, as in "don't" or "Anders' bog"
+                    '’'
+                }
+            },
+            ' ' if state.previous.is_some_and(|c| c.is_ascii_digit()) && starts_with_unit(&characters, n + 1) => '\u{a0}',
+            ' ' if ends_with_word(&characters, n, &TYPOGRAPHY_ABBREVIATIONS) => '\u{a0}',
+            character => character,
+        };
+        result.push(typeset);
+        state.previous = Some(typeset);
+        n += 1;
+    }
+    result
+}
+
+pub fn typeset(element: &mut BlogBody, state: &mut TypesetState) {
+    match element {
+        BlogBody::Text(text) => *text = typeset_text(text, state),
+        BlogBody::FactBox(factbox) => {
+            state.reset();
+            factbox.title = typeset_text(&factbox.title, state);
+        },
+        _ => {}
+    }
+    // quotes don't continue from one block to the next, or into the text shown on hover
+    for_each_child_mut(element, &mut |child, part| match part {
+        Part::Block | Part::Heading => {
+            state.reset();
+            typeset(child, state);
+        },
+        Part::Hover => typeset(child, &mut TypesetState::new(state.typography)),
+        Part::Inline | Part::Label => typeset(child, state),
+        Part::Target => {},
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::typography::*;
+
+    fn danish(text: &str) -> String {
+        typeset_text(text, &mut TypesetState::new(Typography::Danish))
+    }
+
+    fn english(text: &str) -> String {
+        typeset_text(text, &mut TypesetState::new(Typography::English))
+    }
+
+    #[test]
+    fn test_quotes() {
+        assert_eq!(danish("han sagde \"hej\" og 'farvel'"), "han sagde »hej« og ›farvel‹");
+        assert_eq!(english("she said \"hi\" and 'bye', don't"), "she said “hi” and ‘bye’, don’t");
+    }
+
+    #[test]
+    fn test_dashes_and_ellipsis() {
+        assert_eq!(english("1--2 and then--- wait..."), "1–2 and then— wait…");
+        assert_eq!(english("a --> b"), "a --> b");
+    }
+
+    #[test]
+    fn test_non_breaking_spaces() {
+        assert_eq!(danish("10 km på s. 4, se fig. 2"), "10\u{a0}km på s.\u{a0}4, se fig.\u{a0}2");
+        assert_eq!(danish("10 kmt og hus. 4"), "10 kmt og hus. 4");
+    }
+
+    #[test]
+    fn test_quote_after_element() {
+        let mut state = TypesetState::new(Typography::Danish);
+        let mut element = BlogBody::Paragraph(Box::new(BlogBody::Span(Span{ elements: vec![
+            BlogBody::Text("\"".into()),
+            BlogBody::Italics(Box::new(BlogBody::Text("hej".into()))),
+            BlogBody::Text("\"".into()),
+        ]})));
+        typeset(&mut element, &mut state);
+        assert_eq!(element, BlogBody::Paragraph(Box::new(BlogBody::Span(Span{ elements: vec![
+            BlogBody::Text("»".into()),
+            BlogBody::Italics(Box::new(BlogBody::Text("hej".into()))),
+            BlogBody::Text("«".into()),
+        ]}))));
+    }
+}